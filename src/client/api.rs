@@ -1,22 +1,33 @@
 use crate::client::{AuthenticatedOpenPaymentsClient, BaseClient};
 use crate::types::{
-    AccessTokenResponse, ContinueResponse, GrantRequest, GrantResponse, IncomingPayment,
-    IncomingPaymentRequest, JsonWebKeySet, ListIncomingPaymentsResponse,
-    ListOutgoingPaymentsResponse, OutgoingPayment, OutgoingPaymentRequest, PublicIncomingPayment,
-    Quote, QuoteRequest, WalletAddress,
+    AccessToken, AccessTokenResponse, Continue, ContinueResponse, DidDocument, GrantRequest,
+    GrantResponse, IncomingPayment, IncomingPaymentAction, IncomingPaymentRequest, InteractRequest,
+    IntoWalletAddressUrl, JsonWebKeySet, ListIncomingPaymentsResponse,
+    ListOutgoingPaymentsResponse, OutgoingPayment, OutgoingPaymentAction, OutgoingPaymentRequest,
+    PublicIncomingPayment, Quote, QuoteAction, QuoteRequest, Subject, WalletAddress,
 };
 use crate::{
-    grant::{cancel_grant, continue_grant, request_grant},
+    grant::{cancel_grant, continue_grant, poll_until_granted, request_grant, PollOptions},
+    grant_manager::{select_token, track_rotated_token, untrack_token, AccessScope},
+    pagination::ListOptions,
     payments::{
         complete_incoming_payment, create_incoming_payment, create_outgoing_payment,
         get_incoming_payment, get_outgoing_payment, get_public_incoming_payment,
-        list_incoming_payments, list_outgoing_payments,
+        list_incoming_payments, list_outgoing_payments, stream_incoming_payments,
+        stream_outgoing_payments,
     },
+    push::PendingPush,
     quotes::{create_quote, get_quote},
     token::{revoke_access_token, rotate_access_token},
-    wallet_address::{get_keys, get_wallet_address},
+    token_store::save_rotated_token,
+    wallet_address::{get_did_document, get_keys, get_wallet_address},
     Result,
 };
+use futures::{Stream, TryStreamExt};
+
+#[cfg(feature = "loopback")]
+use crate::loopback::{request_with_loopback, LoopbackOptions};
+
 pub mod authenticated {
     use super::*;
 
@@ -35,11 +46,25 @@ pub mod authenticated {
             req_body: &QuoteRequest,
             access_token: Option<&str>,
         ) -> Result<Quote> {
-            create_quote(self.client, resource_server_url, req_body, access_token).await
+            let scope = AccessScope::Quote {
+                action: QuoteAction::Create,
+            };
+            let access_token = select_token(self.client, access_token, scope).await?;
+            create_quote(
+                self.client,
+                resource_server_url,
+                req_body,
+                access_token.as_deref(),
+            )
+            .await
         }
 
         pub async fn get(&self, quote_url: &str, access_token: Option<&str>) -> Result<Quote> {
-            get_quote(self.client, quote_url, access_token).await
+            let scope = AccessScope::Quote {
+                action: QuoteAction::Read,
+            };
+            let access_token = select_token(self.client, access_token, scope).await?;
+            get_quote(self.client, quote_url, access_token.as_deref()).await
         }
     }
     pub struct IncomingPaymentResource<'a> {
@@ -57,7 +82,18 @@ pub mod authenticated {
             req_body: &IncomingPaymentRequest,
             access_token: Option<&str>,
         ) -> Result<IncomingPayment> {
-            create_incoming_payment(self.client, resource_server_url, req_body, access_token).await
+            let scope = AccessScope::IncomingPayment {
+                action: IncomingPaymentAction::Create,
+                wallet_address: Some(req_body.wallet_address.to_string()),
+            };
+            let access_token = select_token(self.client, access_token, scope).await?;
+            create_incoming_payment(
+                self.client,
+                resource_server_url,
+                req_body,
+                access_token.as_deref(),
+            )
+            .await
         }
 
         pub async fn get(
@@ -65,7 +101,12 @@ pub mod authenticated {
             payment_url: &str,
             access_token: Option<&str>,
         ) -> Result<IncomingPayment> {
-            get_incoming_payment(self.client, payment_url, access_token).await
+            let scope = AccessScope::IncomingPayment {
+                action: IncomingPaymentAction::Read,
+                wallet_address: None,
+            };
+            let access_token = select_token(self.client, access_token, scope).await?;
+            get_incoming_payment(self.client, payment_url, access_token.as_deref()).await
         }
 
         pub async fn complete(
@@ -73,29 +114,66 @@ pub mod authenticated {
             payment_url: &str,
             access_token: Option<&str>,
         ) -> Result<IncomingPayment> {
-            complete_incoming_payment(self.client, payment_url, access_token).await
+            let scope = AccessScope::IncomingPayment {
+                action: IncomingPaymentAction::Complete,
+                wallet_address: None,
+            };
+            let access_token = select_token(self.client, access_token, scope).await?;
+            complete_incoming_payment(self.client, payment_url, access_token.as_deref()).await
         }
 
         pub async fn list(
             &self,
             resource_server_url: &str,
             wallet_address: &str,
-            cursor: Option<&str>,
-            first: Option<u32>,
-            last: Option<u32>,
+            options: &ListOptions,
             access_token: Option<&str>,
         ) -> Result<ListIncomingPaymentsResponse> {
+            let scope = AccessScope::IncomingPayment {
+                action: IncomingPaymentAction::List,
+                wallet_address: Some(wallet_address.to_string()),
+            };
+            let access_token = select_token(self.client, access_token, scope).await?;
             list_incoming_payments(
                 self.client,
                 resource_server_url,
                 wallet_address,
-                cursor,
-                first,
-                last,
-                access_token,
+                options,
+                access_token.as_deref(),
             )
             .await
         }
+
+        /// Streams the incoming payments of all pages, requesting each page once the
+        /// previous one has been consumed.
+        pub fn stream(
+            &self,
+            resource_server_url: &str,
+            wallet_address: &str,
+            options: ListOptions,
+            access_token: Option<&str>,
+        ) -> impl Stream<Item = Result<IncomingPayment>> + 'a {
+            stream_incoming_payments(
+                self.client,
+                resource_server_url,
+                wallet_address,
+                options,
+                access_token,
+            )
+        }
+
+        /// Collects the incoming payments of all pages, see [`Self::stream`].
+        pub async fn list_all(
+            &self,
+            resource_server_url: &str,
+            wallet_address: &str,
+            options: ListOptions,
+            access_token: Option<&str>,
+        ) -> Result<Vec<IncomingPayment>> {
+            self.stream(resource_server_url, wallet_address, options, access_token)
+                .try_collect()
+                .await
+        }
     }
 
     pub struct OutgoingPaymentResource<'a> {
@@ -113,36 +191,90 @@ pub mod authenticated {
             req_body: &OutgoingPaymentRequest,
             access_token: Option<&str>,
         ) -> Result<OutgoingPayment> {
-            create_outgoing_payment(self.client, resource_server_url, req_body, access_token).await
+            let wallet_address = match req_body {
+                OutgoingPaymentRequest::FromQuote { wallet_address, .. }
+                | OutgoingPaymentRequest::FromIncomingPayment { wallet_address, .. } => {
+                    wallet_address
+                }
+            };
+            let scope = AccessScope::OutgoingPayment {
+                action: OutgoingPaymentAction::Create,
+                wallet_address: Some(wallet_address.to_string()),
+            };
+            let access_token = select_token(self.client, access_token, scope).await?;
+            create_outgoing_payment(
+                self.client,
+                resource_server_url,
+                req_body,
+                access_token.as_deref(),
+            )
+            .await
         }
 
         pub async fn list(
             &self,
             resource_server_url: &str,
             wallet_address: &str,
-            cursor: Option<&str>,
-            first: Option<u32>,
-            last: Option<u32>,
+            options: &ListOptions,
             access_token: Option<&str>,
         ) -> Result<ListOutgoingPaymentsResponse> {
+            let scope = AccessScope::OutgoingPayment {
+                action: OutgoingPaymentAction::List,
+                wallet_address: Some(wallet_address.to_string()),
+            };
+            let access_token = select_token(self.client, access_token, scope).await?;
             list_outgoing_payments(
                 self.client,
                 resource_server_url,
                 wallet_address,
-                cursor,
-                first,
-                last,
-                access_token,
+                options,
+                access_token.as_deref(),
             )
             .await
         }
 
+        /// Streams the outgoing payments of all pages, requesting each page once the
+        /// previous one has been consumed.
+        pub fn stream(
+            &self,
+            resource_server_url: &str,
+            wallet_address: &str,
+            options: ListOptions,
+            access_token: Option<&str>,
+        ) -> impl Stream<Item = Result<OutgoingPayment>> + 'a {
+            stream_outgoing_payments(
+                self.client,
+                resource_server_url,
+                wallet_address,
+                options,
+                access_token,
+            )
+        }
+
+        /// Collects the outgoing payments of all pages, see [`Self::stream`].
+        pub async fn list_all(
+            &self,
+            resource_server_url: &str,
+            wallet_address: &str,
+            options: ListOptions,
+            access_token: Option<&str>,
+        ) -> Result<Vec<OutgoingPayment>> {
+            self.stream(resource_server_url, wallet_address, options, access_token)
+                .try_collect()
+                .await
+        }
+
         pub async fn get(
             &self,
             payment_url: &str,
             access_token: Option<&str>,
         ) -> Result<OutgoingPayment> {
-            get_outgoing_payment(self.client, payment_url, access_token).await
+            let scope = AccessScope::OutgoingPayment {
+                action: OutgoingPaymentAction::Read,
+                wallet_address: None,
+            };
+            let access_token = select_token(self.client, access_token, scope).await?;
+            get_outgoing_payment(self.client, payment_url, access_token.as_deref()).await
         }
     }
 
@@ -159,18 +291,80 @@ pub mod authenticated {
             request_grant(self.client, auth_url, grant).await
         }
 
+        /// Requests proof that the user controls `wallet_address`.
+        ///
+        /// The grant asks for the wallet address as a `uri` subject identifier and no access
+        /// token. Once the user has interacted, the continuation response is a
+        /// [`ContinueResponse::WithSubject`] listing the identifiers the user was
+        /// authenticated as.
+        pub async fn request_subject(
+            &self,
+            auth_url: &str,
+            wallet_address: &str,
+            interact: InteractRequest,
+        ) -> Result<GrantResponse> {
+            let grant =
+                GrantRequest::for_subject(Subject::wallet_address(wallet_address), interact);
+            request_grant(self.client, auth_url, &grant).await
+        }
+
         pub async fn continue_grant(
             &self,
             continue_uri: &str,
             interact_ref: &str,
             access_token: Option<&str>,
         ) -> Result<ContinueResponse> {
-            continue_grant(self.client, continue_uri, interact_ref, access_token).await
+            continue_grant(self.client, continue_uri, Some(interact_ref), access_token).await
+        }
+
+        /// Waits for the interaction of a grant to be pushed, then continues the grant with
+        /// the received interaction reference.
+        ///
+        /// See [`PushFinishHandler`](crate::client::PushFinishHandler).
+        pub async fn continue_after_push(
+            &self,
+            pending: PendingPush,
+            continue_uri: &str,
+            access_token: Option<&str>,
+        ) -> Result<ContinueResponse> {
+            let interact_ref = pending.interact_ref().await?;
+            continue_grant(self.client, continue_uri, Some(&interact_ref), access_token).await
+        }
+
+        /// Continues a grant without an interaction reference until it is granted.
+        ///
+        /// This is used when the grant is approved out of band, e.g. with a push finish
+        /// method or without interaction. Requests are spaced by the `wait` advised by the
+        /// server, and polling stops on an error, such as the grant being denied, or once
+        /// `options.timeout` has passed. Dropping the returned future cancels polling.
+        pub async fn poll_until_granted(
+            &self,
+            continue_: &Continue,
+            options: &PollOptions,
+        ) -> Result<(AccessToken, Continue)> {
+            poll_until_granted(self.client, continue_, options).await
         }
 
         pub async fn cancel(&self, continue_uri: &str, access_token: Option<&str>) -> Result<()> {
             cancel_grant(self.client, continue_uri, access_token).await
         }
+
+        /// Requests an interactive grant and completes it through a local redirect listener.
+        ///
+        /// The `interact` of `grant` is replaced with a `redirect` start and a `redirect`
+        /// finish to a [`LoopbackListener`](crate::client::LoopbackListener). `open` is
+        /// called with the interaction URL, which the user must visit to approve the
+        /// grant. The redirect hash is verified before the grant is continued.
+        #[cfg(feature = "loopback")]
+        pub async fn request_with_loopback(
+            &self,
+            auth_url: &str,
+            grant: &GrantRequest,
+            options: &LoopbackOptions,
+            open: impl FnOnce(&str),
+        ) -> Result<(AccessToken, Continue)> {
+            request_with_loopback(self.client, auth_url, grant, options, open).await
+        }
     }
 
     pub struct Token<'a> {
@@ -187,11 +381,16 @@ pub mod authenticated {
             auth_url: &str,
             access_token: Option<&str>,
         ) -> Result<AccessTokenResponse> {
-            rotate_access_token(self.client, auth_url, access_token).await
+            let response = rotate_access_token(self.client, auth_url, access_token).await?;
+            track_rotated_token(self.client, auth_url, &response.access_token).await;
+            save_rotated_token(self.client, auth_url, &response.access_token).await?;
+            Ok(response)
         }
 
         pub async fn revoke(&self, auth_url: &str, access_token: Option<&str>) -> Result<()> {
-            revoke_access_token(self.client, auth_url, access_token).await
+            revoke_access_token(self.client, auth_url, access_token).await?;
+            untrack_token(self.client, auth_url).await;
+            Ok(())
         }
     }
 }
@@ -208,16 +407,25 @@ pub mod unauthenticated {
             Self { client }
         }
 
-        pub async fn get(&self, wallet_address_url: &str) -> Result<WalletAddress> {
-            get_wallet_address(self.client.http_client(), wallet_address_url).await
+        /// Gets a wallet address from its URL or payment pointer.
+        ///
+        /// The wallet address must use `https`, unless the client allows `http` wallet
+        /// addresses for local development.
+        pub async fn get(
+            &self,
+            wallet_address_url: impl IntoWalletAddressUrl,
+        ) -> Result<WalletAddress> {
+            let wallet_address_url = wallet_address_url
+                .into_wallet_address_url(self.client.allow_http_wallet_addresses())?;
+            get_wallet_address(self.client, &wallet_address_url).await
         }
 
         pub async fn get_keys(&self, wallet: &WalletAddress) -> Result<JsonWebKeySet> {
-            get_keys(self.client.http_client(), wallet).await
+            get_keys(self.client, wallet).await
         }
 
-        pub async fn get_did_document(&self, _wallet: &WalletAddress) -> Result<()> {
-            unimplemented!()
+        pub async fn get_did_document(&self, wallet: &WalletAddress) -> Result<DidDocument> {
+            get_did_document(self.client.http_client(), wallet).await
         }
     }
 
@@ -0,0 +1,382 @@
+//! # Authenticated Client Builder
+//!
+//! This module provides [`ClientBuilder`], a builder for
+//! [`AuthenticatedOpenPaymentsClient`] that does not require any file system access.
+//!
+//! The builder accepts:
+//!
+//! - An existing `reqwest::Client` (e.g. with a proxy, custom TLS roots or a shared connection pool)
+//! - Request and connect timeouts, a user agent and default headers for a client built by the builder
+//! - A signing key supplied directly in memory, as PEM content, or read from a PEM file
+//! - Any [`HttpMessageSigner`], for keys that must stay in an HSM, KMS or external process
+//! - Verification of signed responses against the server's JWKS
+//!
+//! ## Example
+//!
+//! ```rust
+//! use open_payments::client::AuthenticatedClient;
+//! use ed25519_dalek::SigningKey;
+//! use std::time::Duration;
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let signing_key = SigningKey::generate(&mut rand::rngs::OsRng);
+//!
+//!     let client = AuthenticatedClient::builder()
+//!         .key_id("my-key-id")
+//!         .wallet_address_url("https://rafiki.money/alice")
+//!         .signing_key(signing_key)
+//!         .timeout(Duration::from_secs(30))
+//!         .connect_timeout(Duration::from_secs(5))
+//!         .user_agent("my-app/1.0")
+//!         .build()?;
+//!     Ok(())
+//! }
+//! ```
+use crate::client::cache::WalletAddressCache;
+use crate::client::config::ClientConfig;
+use crate::client::core::AuthenticatedOpenPaymentsClient;
+use crate::error::{OpClientError, Result};
+use crate::http_signature::{
+    jwk::Jwk, load_key, load_or_generate_key, parse_private_key, Ed25519Signer, HttpMessageSigner,
+};
+use crate::response_verification::ResponseVerifier;
+use crate::retry::RetryPolicy;
+use crate::token_store::TokenStore;
+use ed25519_dalek::SigningKey;
+use reqwest::header::HeaderMap;
+use reqwest::Client as ReqwestClient;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Source of the signing key used by the client.
+enum KeySource {
+    /// A key held in memory.
+    Key(SigningKey),
+    /// PEM content held in memory.
+    Pem(String),
+    /// A PEM file that is only ever read.
+    Path(PathBuf),
+    /// A key already loaded from the given file by [`ClientBuilder::from_config`].
+    Loaded(SigningKey, PathBuf),
+    /// A signer that holds its own key and key ID.
+    Signer(Arc<dyn HttpMessageSigner>),
+}
+
+/// Builder for [`AuthenticatedOpenPaymentsClient`].
+///
+/// Unlike [`AuthenticatedOpenPaymentsClient::new`], building a client never generates
+/// keys or writes files, so it can be used in environments with read-only file systems.
+///
+/// When an existing `reqwest::Client` is supplied with [`ClientBuilder::http_client`],
+/// its configuration is used as-is. Setting a timeout, connect timeout, user agent or
+/// default headers in addition is rejected by [`ClientBuilder::build`], since they
+/// cannot be applied to an already built client.
+#[derive(Default)]
+pub struct ClientBuilder {
+    http_client: Option<ReqwestClient>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    user_agent: Option<String>,
+    default_headers: Option<HeaderMap>,
+    key: Option<KeySource>,
+    key_id: Option<String>,
+    wallet_address_url: Option<String>,
+    jwks_path: Option<PathBuf>,
+    verify_responses: bool,
+    response_jwks_url: Option<String>,
+    retry_policy: Option<RetryPolicy>,
+    token_store: Option<Arc<dyn TokenStore>>,
+    wallet_address_cache: Option<Arc<WalletAddressCache>>,
+    allow_http_wallet_addresses: bool,
+    manage_tokens: bool,
+}
+
+impl ClientBuilder {
+    /// Creates an empty builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a builder from a [`ClientConfig`], preserving the behaviour of
+    /// [`AuthenticatedOpenPaymentsClient::new`].
+    ///
+    /// This loads the private key from `private_key_path`, generating and saving a new key
+    /// if the file does not exist, and writes the JWKS to `jwks_path` if one is configured.
+    /// Use [`ClientBuilder::private_key_path`] instead to only ever read the key file.
+    pub fn from_config(config: ClientConfig) -> Result<Self> {
+        let signing_key = load_or_generate_key(&config.private_key_path).map_err(|e| {
+            OpClientError::key_load(format!("Failed to load or generate signing key: {e}"))
+        })?;
+
+        if let Some(ref jwks_path) = config.jwks_path {
+            let jwks_json = Jwk::generate_jwks_json(&signing_key, &config.key_id);
+            Jwk::save_jwks(&jwks_json, jwks_path)
+                .map_err(|e| OpClientError::key_load(format!("Failed to save JWK to file: {e}")))?;
+        }
+
+        Ok(Self {
+            key: Some(KeySource::Loaded(signing_key, config.private_key_path)),
+            key_id: Some(config.key_id),
+            wallet_address_url: Some(config.wallet_address_url),
+            jwks_path: config.jwks_path,
+            ..Self::default()
+        })
+    }
+
+    /// Uses an existing `reqwest::Client` for all requests.
+    pub fn http_client(mut self, http_client: ReqwestClient) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    /// Sets the total timeout applied to every request.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout for establishing connections.
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = Some(connect_timeout);
+        self
+    }
+
+    /// Sets the `User-Agent` header sent with every request.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Sets headers sent with every request.
+    pub fn default_headers(mut self, headers: HeaderMap) -> Self {
+        self.default_headers = Some(headers);
+        self
+    }
+
+    /// Sets the key ID advertised in HTTP message signatures.
+    pub fn key_id(mut self, key_id: impl Into<String>) -> Self {
+        self.key_id = Some(key_id.into());
+        self
+    }
+
+    /// Sets the wallet address URL used as the GNAP client identifier.
+    pub fn wallet_address_url(mut self, wallet_address_url: impl Into<String>) -> Self {
+        self.wallet_address_url = Some(wallet_address_url.into());
+        self
+    }
+
+    /// Uses a signing key held in memory.
+    pub fn signing_key(mut self, signing_key: SigningKey) -> Self {
+        self.key = Some(KeySource::Key(signing_key));
+        self
+    }
+
+    /// Uses a custom signer, e.g. one backed by an HSM, a KMS or an external process.
+    ///
+    /// The key ID is taken from [`HttpMessageSigner::key_id`], so [`ClientBuilder::key_id`]
+    /// does not need to be set.
+    pub fn signer(mut self, signer: impl HttpMessageSigner + 'static) -> Self {
+        self.key = Some(KeySource::Signer(Arc::new(signer)));
+        self
+    }
+
+    /// Uses a signing key parsed from PEM content held in memory.
+    ///
+    /// The content may be plain text or base64 encoded PEM.
+    pub fn private_key_pem(mut self, pem: impl Into<String>) -> Self {
+        self.key = Some(KeySource::Pem(pem.into()));
+        self
+    }
+
+    /// Uses a signing key read from a PEM file.
+    ///
+    /// The file is only read; building fails if it does not exist.
+    pub fn private_key_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.key = Some(KeySource::Path(path.into()));
+        self
+    }
+
+    /// Verifies the HTTP message signature of every successful response before it is
+    /// deserialized.
+    ///
+    /// The server's public keys are fetched from `{origin}/jwks.json` of the response URL
+    /// and cached by key ID. Responses without a valid signature covering `@status`, and
+    /// `content-digest` when there is a body, fail with a response signature error.
+    pub fn verify_responses(mut self) -> Self {
+        self.verify_responses = true;
+        self
+    }
+
+    /// Verifies response signatures with keys from the given JWKS URL.
+    ///
+    /// Implies [`ClientBuilder::verify_responses`].
+    pub fn response_jwks_url(mut self, jwks_url: impl Into<String>) -> Self {
+        self.verify_responses = true;
+        self.response_jwks_url = Some(jwks_url.into());
+        self
+    }
+
+    /// Retries failed requests according to the given policy.
+    ///
+    /// Requests are not retried unless a policy is set. See [`RetryPolicy`] for which
+    /// requests are retried.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Persists access tokens issued by grants and rotations in the given store.
+    ///
+    /// See [`TokenStore`] for which tokens are stored.
+    pub fn token_store(mut self, token_store: Arc<dyn TokenStore>) -> Self {
+        self.token_store = Some(token_store);
+        self
+    }
+
+    /// Caches the wallet addresses and keys fetched by the client in the given cache.
+    ///
+    /// See [`WalletAddressCache`] for how responses are cached.
+    pub fn wallet_address_cache(mut self, cache: Arc<WalletAddressCache>) -> Self {
+        self.wallet_address_cache = Some(cache);
+        self
+    }
+
+    /// Allows wallet addresses served over `http`.
+    ///
+    /// This is meant for local development against servers without TLS.
+    pub fn allow_http_wallet_addresses(mut self) -> Self {
+        self.allow_http_wallet_addresses = true;
+        self
+    }
+
+    /// Tracks the access tokens issued to the client and uses them for resource calls
+    /// made without an `access_token`.
+    ///
+    /// Tokens issued by grants and rotations of the client are tracked, and a resource
+    /// call without an `access_token` uses a tracked token granting the call's access,
+    /// rotating it shortly before it expires. The call fails with a validation error if
+    /// no tracked token grants its access. Every [`GrantManager`] of the client shares
+    /// these tokens.
+    ///
+    /// [`GrantManager`]: crate::client::GrantManager
+    pub fn manage_tokens(mut self) -> Self {
+        self.manage_tokens = true;
+        self
+    }
+
+    /// Builds the authenticated client.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `OpClientError` if required settings are missing, if the signing key
+    /// cannot be loaded, if HTTP settings are combined with an existing `reqwest::Client`,
+    /// or if the underlying HTTP client cannot be built.
+    pub fn build(self) -> Result<AuthenticatedOpenPaymentsClient> {
+        let mut missing = Vec::new();
+        let key_id = match &self.key {
+            Some(KeySource::Signer(signer)) => Some(signer.key_id().to_string()),
+            _ => self.key_id,
+        };
+        if key_id.is_none() {
+            missing.push("key_id is required".to_string());
+        }
+        if self.wallet_address_url.is_none() {
+            missing.push("wallet_address_url is required".to_string());
+        }
+        if self.key.is_none() {
+            missing.push("a signing key is required".to_string());
+        }
+        let (Some(key_id), Some(wallet_address_url), Some(key)) =
+            (key_id, self.wallet_address_url, self.key)
+        else {
+            return Err(Box::new(OpClientError::validation(
+                "Invalid client configuration",
+                missing,
+            )));
+        };
+
+        let mut private_key_path = PathBuf::new();
+        let signer: Arc<dyn HttpMessageSigner> = match key {
+            KeySource::Signer(signer) => signer,
+            KeySource::Key(signing_key) => Arc::new(Ed25519Signer::new(&key_id, signing_key)),
+            KeySource::Loaded(signing_key, path) => {
+                private_key_path = path;
+                Arc::new(Ed25519Signer::new(&key_id, signing_key))
+            }
+            KeySource::Pem(pem) => {
+                let signing_key = parse_private_key(&pem).map_err(|e| {
+                    OpClientError::key_load(format!("Failed to parse signing key: {e}"))
+                })?;
+                Arc::new(Ed25519Signer::new(&key_id, signing_key))
+            }
+            KeySource::Path(path) => {
+                let signing_key = load_key(&path).map_err(|e| {
+                    OpClientError::key_load(format!("Failed to load signing key: {e}"))
+                })?;
+                private_key_path = path;
+                Arc::new(Ed25519Signer::new(&key_id, signing_key))
+            }
+        };
+
+        let http_client = match self.http_client {
+            Some(http_client) => {
+                let mut conflicting = Vec::new();
+                if self.timeout.is_some() {
+                    conflicting.push("timeout".to_string());
+                }
+                if self.connect_timeout.is_some() {
+                    conflicting.push("connect_timeout".to_string());
+                }
+                if self.user_agent.is_some() {
+                    conflicting.push("user_agent".to_string());
+                }
+                if self.default_headers.is_some() {
+                    conflicting.push("default_headers".to_string());
+                }
+                if !conflicting.is_empty() {
+                    return Err(Box::new(OpClientError::validation(
+                        "HTTP settings cannot be applied to a provided reqwest::Client",
+                        conflicting,
+                    )));
+                }
+                http_client
+            }
+            None => {
+                let mut builder = ReqwestClient::builder();
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                if let Some(connect_timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(connect_timeout);
+                }
+                if let Some(user_agent) = self.user_agent {
+                    builder = builder.user_agent(user_agent);
+                }
+                if let Some(headers) = self.default_headers {
+                    builder = builder.default_headers(headers);
+                }
+                builder.build().map_err(OpClientError::from)?
+            }
+        };
+
+        Ok(AuthenticatedOpenPaymentsClient {
+            http_client,
+            config: ClientConfig {
+                key_id,
+                private_key_path,
+                jwks_path: self.jwks_path,
+                wallet_address_url,
+            },
+            signer,
+            response_verifier: self
+                .verify_responses
+                .then(|| Arc::new(ResponseVerifier::new(self.response_jwks_url))),
+            retry_policy: self.retry_policy,
+            token_store: self.token_store,
+            wallet_address_cache: self.wallet_address_cache,
+            allow_http_wallet_addresses: self.allow_http_wallet_addresses,
+            managed_tokens: self.manage_tokens.then(Default::default),
+        })
+    }
+}
@@ -0,0 +1,409 @@
+//! # Wallet Address Caching
+//!
+//! This module defines [`WalletAddressCache`], an optional cache for the wallet
+//! addresses and key sets fetched through
+//! [`WalletAddressResource`](crate::client::api::unauthenticated::WalletAddressResource).
+//! Services resolving the same wallet addresses over and over can use it to avoid a
+//! network round trip for every lookup.
+//!
+//! The cache follows the caching headers of the responses:
+//!
+//! - a response is fresh for the `max-age` of its `Cache-Control` header, less its `Age`
+//! - responses marked `no-store` are never cached, and `no-cache` responses are
+//!   revalidated on every use
+//! - once stale, a response with an `ETag` is revalidated with `If-None-Match`, and a
+//!   `304 Not Modified` renews it without downloading it again
+//! - `404 Not Found` responses are cached for a configurable time, so unknown wallet
+//!   addresses do not reach the server on every lookup
+//!
+//! Entries are kept in a [`CacheStore`]. [`InMemoryCacheStore`], used by default, keeps
+//! a bounded number of entries and evicts the least recently used ones. Other stores,
+//! e.g. one shared by several processes, can be plugged in with
+//! [`WalletAddressCache::with_store`].
+//!
+//! ## Example
+//!
+//! ```rust,no_run
+//! use open_payments::client::{UnauthenticatedClient, UnauthenticatedResources, WalletAddressCache};
+//! use std::sync::Arc;
+//!
+//! # async fn example() -> open_payments::client::Result<()> {
+//! let cache = Arc::new(WalletAddressCache::new());
+//! let client = UnauthenticatedClient::new().with_wallet_address_cache(cache.clone());
+//!
+//! let wallet_address = client.wallet_address().get("$ilp.rafiki.money/alice").await?;
+//! let keys = client.wallet_address().get_keys(&wallet_address).await?;
+//!
+//! // Forget the wallet address and its keys, e.g. after a key rotation was announced
+//! cache.invalidate(&wallet_address.id).await?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::request::{error_from_response, UnauthenticatedRequest};
+use crate::types::{IntoWalletAddressUrl, WalletAddress, WalletAddressUrl};
+use crate::{OpClientError, Result};
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, HeaderValue, AGE, CACHE_CONTROL, ETAG, IF_NONE_MATCH};
+use reqwest::{Client, Method, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// Number of entries kept by [`InMemoryCacheStore::default`].
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// Time a `404 Not Found` response is cached for by default.
+const DEFAULT_NEGATIVE_TTL: Duration = Duration::from_secs(60);
+
+/// Cached response to a `GET` request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheEntry {
+    /// Status of the response, `200` or `404`.
+    pub status: u16,
+    /// Body of the response.
+    pub body: String,
+    /// `ETag` of the response, used to revalidate the entry once it is stale.
+    pub etag: Option<String>,
+    /// Time until which the entry can be used without revalidation.
+    pub expires_at: SystemTime,
+}
+
+impl CacheEntry {
+    /// Returns whether the entry can be used without revalidation.
+    pub fn is_fresh(&self) -> bool {
+        SystemTime::now() < self.expires_at
+    }
+}
+
+/// Storage for [`WalletAddressCache`] entries, keyed by the URL they were fetched from.
+#[async_trait]
+pub trait CacheStore: Send + Sync {
+    /// Returns the entry stored under `key`.
+    async fn get(&self, key: &str) -> Result<Option<CacheEntry>>;
+
+    /// Stores `entry` under `key`, replacing any previous entry.
+    async fn put(&self, key: &str, entry: &CacheEntry) -> Result<()>;
+
+    /// Removes the entry stored under `key`.
+    async fn remove(&self, key: &str) -> Result<()>;
+
+    /// Removes all entries.
+    async fn clear(&self) -> Result<()>;
+}
+
+/// [`CacheStore`] keeping a bounded number of entries in memory.
+///
+/// Once full, storing an entry evicts the least recently used one.
+#[derive(Debug)]
+pub struct InMemoryCacheStore {
+    lru: Mutex<Lru<CacheEntry>>,
+}
+
+/// Map holding a bounded number of values, evicting the least recently used one.
+#[derive(Debug)]
+pub(crate) struct Lru<V> {
+    capacity: usize,
+    /// Values and the tick of their last use, by key.
+    entries: HashMap<String, (V, u64)>,
+    /// Keys by the tick of their last use.
+    order: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl<V> Lru<V> {
+    /// Creates an empty map holding at most `capacity` values.
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    /// Returns the value stored under `key`, marking it as used.
+    pub(crate) fn get(&mut self, key: &str) -> Option<&V> {
+        self.tick += 1;
+        let (value, used) = self.entries.get_mut(key)?;
+        self.order.remove(used);
+        *used = self.tick;
+        self.order.insert(self.tick, key.to_string());
+        Some(value)
+    }
+
+    /// Stores `value` under `key`, evicting the least recently used values if full.
+    pub(crate) fn insert(&mut self, key: &str, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.remove(key);
+        while self.entries.len() >= self.capacity {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+        self.tick += 1;
+        self.entries.insert(key.to_string(), (value, self.tick));
+        self.order.insert(self.tick, key.to_string());
+    }
+
+    pub(crate) fn remove(&mut self, key: &str) {
+        if let Some((_, used)) = self.entries.remove(key) {
+            self.order.remove(&used);
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+impl InMemoryCacheStore {
+    /// Creates an empty store holding at most `capacity` entries.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lru: Mutex::new(Lru::new(capacity)),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Lru<CacheEntry>> {
+        self.lru.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for InMemoryCacheStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+#[async_trait]
+impl CacheStore for InMemoryCacheStore {
+    async fn get(&self, key: &str) -> Result<Option<CacheEntry>> {
+        Ok(self.lock().get(key).cloned())
+    }
+
+    async fn put(&self, key: &str, entry: &CacheEntry) -> Result<()> {
+        self.lock().insert(key, entry.clone());
+        Ok(())
+    }
+
+    async fn remove(&self, key: &str) -> Result<()> {
+        self.lock().remove(key);
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<()> {
+        self.lock().clear();
+        Ok(())
+    }
+}
+
+/// Cache for wallet addresses and their key sets.
+///
+/// A cache is enabled with
+/// [`UnauthenticatedOpenPaymentsClient::with_wallet_address_cache`](crate::client::UnauthenticatedOpenPaymentsClient::with_wallet_address_cache)
+/// or [`ClientBuilder::wallet_address_cache`](crate::client::ClientBuilder::wallet_address_cache).
+/// It can be shared by several clients.
+pub struct WalletAddressCache {
+    store: Arc<dyn CacheStore>,
+    negative_ttl: Duration,
+}
+
+impl Default for WalletAddressCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WalletAddressCache {
+    /// Creates a cache backed by an [`InMemoryCacheStore`] with the default capacity.
+    pub fn new() -> Self {
+        Self::with_store(Arc::new(InMemoryCacheStore::default()))
+    }
+
+    /// Creates a cache backed by the given store.
+    pub fn with_store(store: Arc<dyn CacheStore>) -> Self {
+        Self {
+            store,
+            negative_ttl: DEFAULT_NEGATIVE_TTL,
+        }
+    }
+
+    /// Sets the time `404 Not Found` responses are cached for, one minute by default.
+    ///
+    /// [`Duration::ZERO`] disables negative caching.
+    pub fn negative_ttl(mut self, negative_ttl: Duration) -> Self {
+        self.negative_ttl = negative_ttl;
+        self
+    }
+
+    /// Removes a wallet address and its key set from the cache.
+    pub async fn invalidate(&self, wallet_address_url: impl IntoWalletAddressUrl) -> Result<()> {
+        let url = WalletAddressUrl::for_invalidation(wallet_address_url)?;
+        self.store.remove(url.as_str()).await?;
+        self.store.remove(&jwks_url(&url)).await
+    }
+
+    /// Removes the key set of a wallet address from the cache.
+    pub async fn invalidate_keys(&self, wallet: &WalletAddress) -> Result<()> {
+        self.store
+            .remove(&jwks_url(&wallet_address_url(wallet)?))
+            .await
+    }
+
+    /// Removes all entries from the cache.
+    pub async fn clear(&self) -> Result<()> {
+        self.store.clear().await
+    }
+
+    /// Gets the JSON resource at `url`, from the cache if it holds a fresh entry.
+    pub(crate) async fn get<T: DeserializeOwned>(&self, client: &Client, url: &str) -> Result<T> {
+        let cached = self.store.get(url).await?;
+        if let Some(entry) = cached.as_ref().filter(|entry| entry.is_fresh()) {
+            return parse_entry(entry);
+        }
+
+        let mut request = UnauthenticatedRequest::new(client, Method::GET, url.to_string());
+        if let Some(etag) = cached.as_ref().and_then(|entry| entry.etag.as_deref()) {
+            let etag = HeaderValue::from_str(etag).map_err(|e| {
+                OpClientError::header_parse(format!("Failed to parse cached ETag: {e}"))
+            })?;
+            request = request.with_header(IF_NONE_MATCH, etag);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        let headers = response.headers().clone();
+
+        match (status, cached) {
+            (StatusCode::NOT_MODIFIED, Some(mut entry)) => {
+                let policy = CachePolicy::from_headers(&headers);
+                entry.expires_at = SystemTime::now() + policy.max_age;
+                if let Some(etag) = header(&headers, ETAG) {
+                    entry.etag = Some(etag);
+                }
+                if policy.store {
+                    self.store.put(url, &entry).await?;
+                } else {
+                    self.store.remove(url).await?;
+                }
+                parse_entry(&entry)
+            }
+            (StatusCode::NOT_FOUND, _) => {
+                let body = response.text().await.unwrap_or_default();
+                if !self.negative_ttl.is_zero() {
+                    let entry = CacheEntry {
+                        status: status.as_u16(),
+                        body: body.clone(),
+                        etag: None,
+                        expires_at: SystemTime::now() + self.negative_ttl,
+                    };
+                    self.store.put(url, &entry).await?;
+                }
+                Err(Box::new(OpClientError::from_response(
+                    status, &headers, &body,
+                )))
+            }
+            (status, _) if status.is_success() => {
+                let body = response.text().await.map_err(OpClientError::from)?;
+                // Bodies that cannot be parsed are not cached
+                let value = serde_json::from_str(&body)?;
+                let policy = CachePolicy::from_headers(&headers);
+                let etag = header(&headers, ETAG);
+                // Without a lifetime or a validator, the entry could never be used
+                if policy.store && (!policy.max_age.is_zero() || etag.is_some()) {
+                    let entry = CacheEntry {
+                        status: status.as_u16(),
+                        body,
+                        etag,
+                        expires_at: SystemTime::now() + policy.max_age,
+                    };
+                    self.store.put(url, &entry).await?;
+                }
+                Ok(value)
+            }
+            _ => Err(error_from_response(response).await),
+        }
+    }
+}
+
+/// Returns the URL of the key set of the wallet address at `wallet_address_url`.
+pub(crate) fn jwks_url(wallet_address_url: &WalletAddressUrl) -> String {
+    format!(
+        "{}/jwks.json",
+        wallet_address_url.as_str().trim_end_matches('/')
+    )
+}
+
+/// Returns the URL of a wallet address, normalized like the URLs it is fetched and
+/// invalidated by, so that they all map to the same cache entries.
+pub(crate) fn wallet_address_url(wallet: &WalletAddress) -> Result<WalletAddressUrl> {
+    // The id was returned by the server, so `http` is only rejected when fetching by URL
+    WalletAddressUrl::parse_with(&wallet.id, true)
+}
+
+fn parse_entry<T: DeserializeOwned>(entry: &CacheEntry) -> Result<T> {
+    match StatusCode::from_u16(entry.status) {
+        Ok(status) if status.is_success() => Ok(serde_json::from_str(&entry.body)?),
+        Ok(status) => Err(Box::new(OpClientError::from_response(
+            status,
+            &HeaderMap::new(),
+            &entry.body,
+        ))),
+        Err(_) => Err(Box::new(OpClientError::other(format!(
+            "Invalid status {} in cache entry",
+            entry.status
+        )))),
+    }
+}
+
+fn header(headers: &HeaderMap, name: reqwest::header::HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Caching directives of a response.
+struct CachePolicy {
+    store: bool,
+    max_age: Duration,
+}
+
+impl CachePolicy {
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let mut store = true;
+        let mut max_age = None;
+        let mut no_cache = false;
+        for directive in headers
+            .get_all(CACHE_CONTROL)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+        {
+            let (name, value) = match directive.split_once('=') {
+                Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+                None => (directive.trim(), None),
+            };
+            match name.to_ascii_lowercase().as_str() {
+                "no-store" => store = false,
+                "no-cache" => no_cache = true,
+                "max-age" => max_age = value.and_then(|value| value.parse::<u64>().ok()),
+                _ => {}
+            }
+        }
+
+        let age = header(headers, AGE)
+            .and_then(|age| age.trim().parse::<u64>().ok())
+            .unwrap_or(0);
+        let max_age = match max_age {
+            Some(max_age) if !no_cache => Duration::from_secs(max_age.saturating_sub(age)),
+            _ => Duration::ZERO,
+        };
+        Self { store, max_age }
+    }
+}
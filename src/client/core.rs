@@ -1,8 +1,14 @@
+use crate::builder::ClientBuilder;
+use crate::cache::WalletAddressCache;
 use crate::config::ClientConfig;
-use crate::error::{OpClientError, Result};
-use crate::http_signature::{jwk::Jwk, load_or_generate_key};
-use ed25519_dalek::SigningKey;
+use crate::error::Result;
+use crate::grant_manager::ManagedTokens;
+use crate::http_signature::HttpMessageSigner;
+use crate::response_verification::ResponseVerifier;
+use crate::retry::RetryPolicy;
+use crate::token_store::TokenStore;
 use reqwest::{Client, Client as ReqwestClient};
+use std::sync::Arc;
 
 /// Base trait for HTTP clients that provides access to the underlying reqwest client.
 ///
@@ -11,12 +17,22 @@ use reqwest::{Client, Client as ReqwestClient};
 pub trait BaseClient {
     /// Returns a reference to the underlying reqwest HTTP client.
     fn http_client(&self) -> &ReqwestClient;
+
+    /// Returns the cache used for wallet addresses and their keys, if any.
+    fn wallet_address_cache(&self) -> Option<&WalletAddressCache> {
+        None
+    }
+
+    /// Returns whether wallet addresses may use `http`, for local development.
+    fn allow_http_wallet_addresses(&self) -> bool {
+        false
+    }
 }
 
 /// An authenticated Open Payments client that can make signed HTTP requests.
 ///
 /// This client automatically handles HTTP message signature creation for all requests
-/// using the configured private key. It's used for operations that require authentication
+/// using the configured [`HttpMessageSigner`]. It's used for operations that require authentication
 /// such as creating payments, quotes and managing access tokens.
 ///
 /// ## Example
@@ -45,19 +61,42 @@ pub struct AuthenticatedOpenPaymentsClient {
     pub http_client: ReqwestClient,
     /// Client configuration including key paths and identifiers.
     pub config: ClientConfig,
-    /// The signing key used for HTTP message signatures.
-    pub signing_key: SigningKey,
+    /// The signer used for HTTP message signatures.
+    pub(crate) signer: Arc<dyn HttpMessageSigner>,
+    /// Verifier for response signatures, if enabled.
+    pub(crate) response_verifier: Option<Arc<ResponseVerifier>>,
+    /// Policy for retrying failed requests, if enabled.
+    pub(crate) retry_policy: Option<RetryPolicy>,
+    /// Store persisting issued and rotated access tokens, if configured.
+    pub(crate) token_store: Option<Arc<dyn TokenStore>>,
+    /// Cache for wallet addresses and their keys, if configured.
+    pub(crate) wallet_address_cache: Option<Arc<WalletAddressCache>>,
+    /// Whether wallet addresses may use `http`.
+    pub(crate) allow_http_wallet_addresses: bool,
+    /// Access tokens issued to the client, if it manages its tokens.
+    pub(crate) managed_tokens: Option<Arc<ManagedTokens>>,
 }
 
 impl BaseClient for AuthenticatedOpenPaymentsClient {
     fn http_client(&self) -> &ReqwestClient {
         &self.http_client
     }
+
+    fn wallet_address_cache(&self) -> Option<&WalletAddressCache> {
+        self.wallet_address_cache.as_deref()
+    }
+
+    fn allow_http_wallet_addresses(&self) -> bool {
+        self.allow_http_wallet_addresses
+    }
 }
 
 impl AuthenticatedOpenPaymentsClient {
     /// Creates a new authenticated client with the given configuration.
     ///
+    /// This is a shorthand for [`ClientBuilder::from_config`] followed by [`ClientBuilder::build`].
+    /// Use [`AuthenticatedOpenPaymentsClient::builder`] to supply the signing key in memory instead.
+    ///
     /// This method will:
     /// 1. Load or generate the signing key from the specified path
     /// 2. Generate and save JWKS if a JWKS path is provided
@@ -80,24 +119,19 @@ impl AuthenticatedOpenPaymentsClient {
     /// - `validation_errors`: List of validation errors (if applicable)
     /// - `details`: Additional error details (if applicable)
     pub fn new(config: ClientConfig) -> Result<Self> {
-        let http_client = ReqwestClient::new();
-
-        let signing_key = load_or_generate_key(&config.private_key_path).map_err(|e| {
-            OpClientError::signature(format!("Failed to load or generate signing key: {e}"))
-        })?;
+        ClientBuilder::from_config(config)?.build()
+    }
 
-        if let Some(ref jwks_path) = config.jwks_path {
-            let jwks_json = Jwk::generate_jwks_json(&signing_key, &config.key_id);
-            Jwk::save_jwks(&jwks_json, jwks_path).map_err(|e| {
-                OpClientError::signature(format!("Failed to save JWK to file: {e}"))
-            })?;
-        }
+    /// Returns the signer used for HTTP message signatures.
+    pub fn signer(&self) -> &dyn HttpMessageSigner {
+        self.signer.as_ref()
+    }
 
-        Ok(Self {
-            http_client,
-            config,
-            signing_key,
-        })
+    /// Returns a [`ClientBuilder`] for configuring a client without touching the file system.
+    ///
+    /// See [`ClientBuilder`] for the available options.
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
     }
 }
 
@@ -123,6 +157,10 @@ impl AuthenticatedOpenPaymentsClient {
 /// ```
 pub struct UnauthenticatedOpenPaymentsClient {
     pub http_client: ReqwestClient,
+    /// Cache for wallet addresses and their keys, if configured.
+    pub(crate) wallet_address_cache: Option<Arc<WalletAddressCache>>,
+    /// Whether wallet addresses may use `http`.
+    pub(crate) allow_http_wallet_addresses: bool,
 }
 
 impl Default for UnauthenticatedOpenPaymentsClient {
@@ -139,14 +177,40 @@ impl UnauthenticatedOpenPaymentsClient {
     pub fn new() -> Self {
         Self {
             http_client: ReqwestClient::new(),
+            wallet_address_cache: None,
+            allow_http_wallet_addresses: false,
         }
     }
+
+    /// Caches the wallet addresses and keys fetched by this client in the given cache.
+    ///
+    /// See [`WalletAddressCache`] for how responses are cached.
+    pub fn with_wallet_address_cache(mut self, cache: Arc<WalletAddressCache>) -> Self {
+        self.wallet_address_cache = Some(cache);
+        self
+    }
+
+    /// Allows wallet addresses served over `http`.
+    ///
+    /// This is meant for local development against servers without TLS.
+    pub fn allow_http_wallet_addresses(mut self) -> Self {
+        self.allow_http_wallet_addresses = true;
+        self
+    }
 }
 
 impl BaseClient for UnauthenticatedOpenPaymentsClient {
     fn http_client(&self) -> &ReqwestClient {
         &self.http_client
     }
+
+    fn wallet_address_cache(&self) -> Option<&WalletAddressCache> {
+        self.wallet_address_cache.as_deref()
+    }
+
+    fn allow_http_wallet_addresses(&self) -> bool {
+        self.allow_http_wallet_addresses
+    }
 }
 
 impl BaseClient for Client {
@@ -1,9 +1,36 @@
 use crate::client::AuthenticatedOpenPaymentsClient;
+use crate::grant_manager::{track_token, ManagedToken};
 use crate::request::AuthenticatedRequest;
-use crate::types::{ContinueRequest, ContinueResponse, GrantRequest, GrantResponse};
-use crate::OpClientError;
-use crate::Result;
+use crate::token_store::save_token;
+use crate::types::{
+    AccessToken, Continue, ContinueRequest, ContinueResponse, GrantRequest, GrantResponse,
+};
+use crate::{ErrorKind, GnapErrorCode, OpClientError, Result};
 use reqwest::Method;
+use std::time::{Duration, Instant};
+
+/// Wait between continuation requests when the server does not advise one.
+///
+/// This is the default of [GNAP](https://www.rfc-editor.org/rfc/rfc9635#section-3.1).
+pub const DEFAULT_CONTINUE_WAIT: Duration = Duration::from_secs(5);
+
+/// Options for [`poll_until_granted`](crate::client::api::authenticated::Grant::poll_until_granted).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PollOptions {
+    /// Time after which polling stops with a [`ErrorKind::Timeout`] error.
+    pub timeout: Option<Duration>,
+    /// Wait between requests when the server does not advise one.
+    pub default_wait: Duration,
+}
+
+impl Default for PollOptions {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            default_wait: DEFAULT_CONTINUE_WAIT,
+        }
+    }
+}
 
 pub(crate) async fn request_grant(
     client: &AuthenticatedOpenPaymentsClient,
@@ -16,27 +43,48 @@ pub(crate) async fn request_grant(
     };
     let body = serde_json::to_string(&grant_with_client).map_err(OpClientError::from)?;
 
-    AuthenticatedRequest::new(client, Method::POST, auth_url.to_string())
-        .with_body(body)
-        .build_and_execute(None)
-        .await
+    let response: GrantResponse =
+        AuthenticatedRequest::new(client, Method::POST, auth_url.to_string())
+            .with_body(body)
+            .build_and_execute(None)
+            .await?;
+
+    if let Some(access_token) = response.access_token() {
+        let mut access_token = access_token.clone();
+        // The token grants the requested access unless the server says otherwise
+        if access_token.access.is_none() {
+            access_token.access = grant.access_token.as_ref().map(|r| r.access.clone());
+        }
+        let token = ManagedToken::new(access_token, response.continue_().cloned());
+        track_token(client, &token).await;
+        save_token(client, &token).await?;
+    }
+    Ok(response)
 }
 
 pub(crate) async fn continue_grant(
     client: &AuthenticatedOpenPaymentsClient,
     continue_uri: &str,
-    interact_ref: &str,
+    interact_ref: Option<&str>,
     access_token: Option<&str>,
 ) -> Result<ContinueResponse> {
     let body = serde_json::to_string(&ContinueRequest {
-        interact_ref: Some(interact_ref.to_string()),
+        interact_ref: interact_ref.map(str::to_string),
     })
     .map_err(OpClientError::from)?;
 
-    AuthenticatedRequest::new(client, Method::POST, continue_uri.to_string())
-        .with_body(body)
-        .build_and_execute(access_token)
-        .await
+    let response: ContinueResponse =
+        AuthenticatedRequest::new(client, Method::POST, continue_uri.to_string())
+            .with_body(body)
+            .build_and_execute(access_token)
+            .await?;
+
+    if let Some(access_token) = response.access_token() {
+        let token = ManagedToken::new(access_token.clone(), response.continue_().cloned());
+        track_token(client, &token).await;
+        save_token(client, &token).await?;
+    }
+    Ok(response)
 }
 
 pub(crate) async fn cancel_grant(
@@ -48,3 +96,70 @@ pub(crate) async fn cancel_grant(
         .build_and_execute(access_token)
         .await
 }
+
+/// Continues a grant without an interaction reference until an access token is issued.
+///
+/// Each request uses the continuation token and URI of the latest response and is sent
+/// after the `wait` advised by the previous one. A GNAP `too_fast` error doubles the
+/// wait; any other error, such as the user or server denying the grant, ends polling.
+pub(crate) async fn poll_until_granted(
+    client: &AuthenticatedOpenPaymentsClient,
+    continue_: &Continue,
+    options: &PollOptions,
+) -> Result<(AccessToken, Continue)> {
+    let deadline = options.timeout.map(|timeout| Instant::now() + timeout);
+    let mut continue_ = continue_.clone();
+    let mut wait = advised_wait(&continue_, options);
+
+    loop {
+        if deadline.is_some_and(|deadline| Instant::now() + wait > deadline) {
+            return Err(Box::new(
+                OpClientError::other("Grant was not approved before the timeout")
+                    .with_kind(ErrorKind::Timeout),
+            ));
+        }
+        tokio::time::sleep(wait).await;
+
+        let response = continue_grant(
+            client,
+            &continue_.uri,
+            None,
+            Some(&continue_.access_token.value),
+        )
+        .await;
+        match response {
+            Ok(ContinueResponse::WithToken {
+                access_token,
+                continue_,
+            }) => return Ok((access_token, continue_)),
+            Ok(ContinueResponse::WithSubject {
+                access_token: Some(access_token),
+                continue_: Some(continue_),
+                ..
+            }) => return Ok((access_token, continue_)),
+            Ok(ContinueResponse::WithSubject { .. }) => return Err(missing_access_token()),
+            Ok(ContinueResponse::Pending { continue_: next }) => {
+                continue_ = next;
+                wait = advised_wait(&continue_, options);
+            }
+            Err(error) if error.gnap_error == Some(GnapErrorCode::TooFast) => {
+                wait = wait.saturating_mul(2).max(options.default_wait);
+            }
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+/// Error for a grant finalized without the access token and continuation expected.
+pub(crate) fn missing_access_token() -> Box<OpClientError> {
+    Box::new(OpClientError::other(
+        "Grant was finalized without an access token",
+    ))
+}
+
+fn advised_wait(continue_: &Continue, options: &PollOptions) -> Duration {
+    continue_
+        .wait
+        .and_then(|wait| u64::try_from(wait).ok())
+        .map_or(options.default_wait, Duration::from_secs)
+}
@@ -0,0 +1,669 @@
+//! # Grant Management
+//!
+//! This module provides [`GrantManager`], which keeps track of the access tokens obtained
+//! from grants and hands out a current token for each resource call.
+//!
+//! Tokens are selected by [`AccessScope`]: the kind of resource, the action and
+//! optionally the wallet address the resource belongs to. A token is rotated with its
+//! `manage` URL shortly before it expires, so callers never have to handle expiry or
+//! pass `access_token` arguments themselves.
+//!
+//! A client built with [`ClientBuilder::manage_tokens`](crate::client::ClientBuilder::manage_tokens)
+//! tracks the tokens of its grants itself, and its resource calls made without an
+//! `access_token` pick a token the same way.
+//!
+//! ## Example
+//!
+//! ```rust,no_run
+//! use open_payments::client::{AuthenticatedClient, GrantManager};
+//! use open_payments::types::{
+//!     AccessItem, AccessTokenRequest, CreateIncomingPaymentRequest, GrantRequest,
+//!     IncomingPaymentAction,
+//! };
+//!
+//! async fn create_payment(client: &AuthenticatedClient) -> open_payments::client::Result<()> {
+//!     let manager = GrantManager::new(client);
+//!     let grant = GrantRequest::new(
+//!         AccessTokenRequest {
+//!             access: vec![AccessItem::IncomingPayment {
+//!                 actions: vec![IncomingPaymentAction::Create, IncomingPaymentAction::Read],
+//!                 identifier: None,
+//!             }],
+//!         },
+//!         None,
+//!     );
+//!     manager.request("https://auth.rafiki.money", &grant).await?;
+//!
+//!     let payment = manager
+//!         .create_incoming_payment(
+//!             "https://ilp.rafiki.money",
+//!             &CreateIncomingPaymentRequest {
+//!                 wallet_address: "https://ilp.rafiki.money/alice".parse()?,
+//!                 incoming_amount: None,
+//!                 expires_at: None,
+//!                 metadata: None,
+//!             },
+//!         )
+//!         .await?;
+//!     println!("Created {}", payment.id);
+//!     Ok(())
+//! }
+//! ```
+use crate::client::AuthenticatedOpenPaymentsClient;
+use crate::grant::{continue_grant, poll_until_granted, request_grant, PollOptions};
+use crate::pagination::ListOptions;
+use crate::payments::{
+    complete_incoming_payment, create_incoming_payment, create_outgoing_payment,
+    get_incoming_payment, get_outgoing_payment, list_incoming_payments, list_outgoing_payments,
+};
+use crate::quotes::{create_quote, get_quote};
+use crate::token::rotate_access_token;
+use crate::token_store::{remove_token, save_token, TokenKey};
+use crate::types::{
+    AccessItem, AccessToken, Continue, ContinueResponse, GrantRequest, GrantResponse,
+    IncomingPayment, IncomingPaymentAction, IncomingPaymentRequest, ListIncomingPaymentsResponse,
+    ListOutgoingPaymentsResponse, OutgoingPayment, OutgoingPaymentAction, OutgoingPaymentRequest,
+    Quote, QuoteAction, QuoteRequest,
+};
+use crate::{OpClientError, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// Default time before expiry at which a token is rotated.
+pub const DEFAULT_REFRESH_MARGIN: Duration = Duration::from_secs(30);
+
+/// Access required by a resource call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccessScope {
+    IncomingPayment {
+        action: IncomingPaymentAction,
+        /// Wallet address the payment belongs to, if known.
+        wallet_address: Option<String>,
+    },
+    OutgoingPayment {
+        action: OutgoingPaymentAction,
+        /// Wallet address the payment belongs to, if known.
+        wallet_address: Option<String>,
+    },
+    Quote {
+        action: QuoteAction,
+    },
+}
+
+impl AccessScope {
+    /// Returns `true` if `item` grants this access.
+    ///
+    /// The `read-all` and `list-all` actions grant `read` and `list`. A wallet address
+    /// is only compared if both the scope and the access item specify one.
+    pub fn is_granted_by(&self, item: &AccessItem) -> bool {
+        fn same_wallet(required: &Option<String>, granted: Option<&String>) -> bool {
+            match (required, granted) {
+                (Some(required), Some(granted)) => required == granted,
+                _ => true,
+            }
+        }
+
+        match (self, item) {
+            (
+                AccessScope::IncomingPayment {
+                    action,
+                    wallet_address,
+                },
+                AccessItem::IncomingPayment {
+                    actions,
+                    identifier,
+                },
+            ) => {
+                let implied = match action {
+                    IncomingPaymentAction::Read => Some(IncomingPaymentAction::ReadAll),
+                    IncomingPaymentAction::List => Some(IncomingPaymentAction::ListAll),
+                    _ => None,
+                };
+                (actions.contains(action) || implied.is_some_and(|a| actions.contains(&a)))
+                    && same_wallet(wallet_address, identifier.as_ref())
+            }
+            (
+                AccessScope::OutgoingPayment {
+                    action,
+                    wallet_address,
+                },
+                AccessItem::OutgoingPayment {
+                    actions,
+                    identifier,
+                    ..
+                },
+            ) => {
+                let implied = match action {
+                    OutgoingPaymentAction::Read => Some(OutgoingPaymentAction::ReadAll),
+                    OutgoingPaymentAction::List => Some(OutgoingPaymentAction::ListAll),
+                    _ => None,
+                };
+                (actions.contains(action) || implied.is_some_and(|a| actions.contains(&a)))
+                    && same_wallet(wallet_address, Some(identifier))
+            }
+            (AccessScope::Quote { action }, AccessItem::Quote { actions }) => {
+                actions.contains(action)
+                    || (*action == QuoteAction::Read && actions.contains(&QuoteAction::ReadAll))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// An access token tracked by a [`GrantManager`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManagedToken {
+    /// The current access token, including its `manage` URL and granted access.
+    pub access_token: AccessToken,
+    /// Continuation information of the grant the token was issued for.
+    pub continue_: Option<Continue>,
+    /// When the token expires, if the server set an expiry.
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl ManagedToken {
+    /// Tracks `access_token`, computing its expiry from `expires_in`.
+    pub fn new(access_token: AccessToken, continue_: Option<Continue>) -> Self {
+        let expires_at = access_token
+            .expires_in
+            .map(|seconds| Utc::now() + chrono::Duration::seconds(seconds));
+        Self {
+            access_token,
+            continue_,
+            expires_at,
+        }
+    }
+
+    /// Returns `true` if the token grants `scope`.
+    ///
+    /// Tokens issued without an `access` list are assumed to grant any scope.
+    pub fn grants(&self, scope: &AccessScope) -> bool {
+        match &self.access_token.access {
+            Some(access) => access.iter().any(|item| scope.is_granted_by(item)),
+            None => true,
+        }
+    }
+
+    fn expires_within(&self, margin: Duration) -> bool {
+        let margin = chrono::Duration::from_std(margin).unwrap_or(chrono::Duration::MAX);
+        self.expires_at
+            .is_some_and(|expires_at| expires_at - Utc::now() <= margin)
+    }
+}
+
+/// Tracks access tokens and supplies a current token to resource calls.
+///
+/// Tokens are added from grant responses by [`GrantManager::request`] and
+/// [`GrantManager::continue_grant`], directly with [`GrantManager::insert`], or from the
+/// client's [`TokenStore`](crate::client::TokenStore) with [`GrantManager::restore`].
+/// Rotated tokens are written to the store. Each token is rotated by one call at a time:
+/// concurrent calls needing the same token wait for its rotation and use the new token,
+/// so they never use a token that the rotation has already invalidated.
+///
+/// If the client was built with [`ClientBuilder::manage_tokens`], all managers of the
+/// client share the tokens the client tracks.
+///
+/// [`ClientBuilder::manage_tokens`]: crate::client::ClientBuilder::manage_tokens
+pub struct GrantManager<'a> {
+    client: &'a AuthenticatedOpenPaymentsClient,
+    tokens: Arc<ManagedTokens>,
+    refresh_margin: Duration,
+}
+
+/// Tokens tracked by [`GrantManager`]s, shared by those of a client managing its tokens.
+#[derive(Default)]
+pub(crate) struct ManagedTokens {
+    tokens: Mutex<Vec<TrackedToken>>,
+}
+
+struct TrackedToken {
+    token: ManagedToken,
+    /// Held while the token is rotated, without holding the lock of all tokens.
+    rotation: Arc<Mutex<()>>,
+}
+
+impl ManagedTokens {
+    async fn lock(&self) -> tokio::sync::MutexGuard<'_, Vec<TrackedToken>> {
+        self.tokens.lock().await
+    }
+
+    /// Tracks `token`, replacing the token with the same `manage` URL.
+    async fn track(&self, token: ManagedToken) {
+        let mut tokens = self.lock().await;
+        tokens.retain(|t| t.token.access_token.manage != token.access_token.manage);
+        tokens.push(TrackedToken {
+            token,
+            rotation: Arc::new(Mutex::new(())),
+        });
+    }
+
+    /// Replaces the token managed at `manage` with `access_token`, obtained by rotating it.
+    ///
+    /// The rotated token keeps the continuation information of the replaced token, and its
+    /// granted access if the server does not repeat it.
+    async fn track_rotated(&self, manage: &str, access_token: &AccessToken) {
+        let mut tokens = self.lock().await;
+        let mut token = ManagedToken::new(access_token.clone(), None);
+        if let Some(index) = tokens
+            .iter()
+            .position(|t| t.token.access_token.manage == manage)
+        {
+            let previous = tokens.remove(index).token;
+            token.continue_ = previous.continue_;
+            if token.access_token.access.is_none() {
+                token.access_token.access = previous.access_token.access;
+            }
+        }
+        tokens.retain(|t| t.token.access_token.manage != token.access_token.manage);
+        tokens.push(TrackedToken {
+            token,
+            rotation: Arc::new(Mutex::new(())),
+        });
+    }
+
+    async fn untrack(&self, manage: &str) {
+        self.lock()
+            .await
+            .retain(|t| t.token.access_token.manage != manage);
+    }
+}
+
+impl<'a> GrantManager<'a> {
+    /// Creates a manager without any tokens, or using the client's tokens if the client
+    /// manages its tokens.
+    pub fn new(client: &'a AuthenticatedOpenPaymentsClient) -> Self {
+        Self {
+            client,
+            tokens: client.managed_tokens.clone().unwrap_or_default(),
+            refresh_margin: DEFAULT_REFRESH_MARGIN,
+        }
+    }
+
+    /// Sets how long before expiry a token is rotated by this manager.
+    ///
+    /// Resource calls picking a token from the client's tokens use
+    /// [`DEFAULT_REFRESH_MARGIN`].
+    pub fn with_refresh_margin(mut self, refresh_margin: Duration) -> Self {
+        self.refresh_margin = refresh_margin;
+        self
+    }
+
+    /// Requests a grant, tracking the access token if one is issued immediately.
+    ///
+    /// Grants requiring interaction are returned as-is; pass the interaction reference to
+    /// [`GrantManager::continue_grant`] once the user has approved the grant.
+    pub async fn request(&self, auth_url: &str, grant: &GrantRequest) -> Result<GrantResponse> {
+        let response = request_grant(self.client, auth_url, grant).await?;
+        if let Some(access_token) = response.access_token() {
+            let mut access_token = access_token.clone();
+            if access_token.access.is_none() {
+                access_token.access = grant.access_token.as_ref().map(|r| r.access.clone());
+            }
+            self.track(ManagedToken::new(
+                access_token,
+                response.continue_().cloned(),
+            ))
+            .await;
+        }
+        Ok(response)
+    }
+
+    /// Continues a grant after interaction, tracking the access token if one is issued.
+    pub async fn continue_grant(
+        &self,
+        continue_: &Continue,
+        interact_ref: &str,
+    ) -> Result<ContinueResponse> {
+        let response = continue_grant(
+            self.client,
+            &continue_.uri,
+            Some(interact_ref),
+            Some(&continue_.access_token.value),
+        )
+        .await?;
+        if let Some(access_token) = response.access_token() {
+            self.track(ManagedToken::new(
+                access_token.clone(),
+                response.continue_().cloned(),
+            ))
+            .await;
+        }
+        Ok(response)
+    }
+
+    /// Polls a grant until it is granted, tracking the issued access token.
+    ///
+    /// See [`Grant::poll_until_granted`](crate::client::api::authenticated::Grant::poll_until_granted).
+    pub async fn poll_until_granted(
+        &self,
+        continue_: &Continue,
+        options: &PollOptions,
+    ) -> Result<AccessToken> {
+        let (access_token, continue_) = poll_until_granted(self.client, continue_, options).await?;
+        self.track(ManagedToken::new(access_token.clone(), Some(continue_)))
+            .await;
+        Ok(access_token)
+    }
+
+    /// Tracks an access token obtained elsewhere, writing it to the client's token store.
+    ///
+    /// A token with the same `manage` URL is replaced. The token is tracked even if it
+    /// cannot be written to the store, in which case the store error is returned.
+    pub async fn insert(
+        &self,
+        access_token: AccessToken,
+        continue_: Option<Continue>,
+    ) -> Result<()> {
+        let token = ManagedToken::new(access_token, continue_);
+        self.track(token.clone()).await;
+        save_token(self.client, &token).await
+    }
+
+    /// Tracks the token stored for `access` in the client's token store.
+    ///
+    /// Returns `false` if the client has no token store or no token is stored for
+    /// `access`. Use this after a restart to avoid requesting the grant again.
+    pub async fn restore(&self, access: &[AccessItem]) -> Result<bool> {
+        let Some(store) = &self.client.token_store else {
+            return Ok(false);
+        };
+        let key = TokenKey::new(&self.client.config.wallet_address_url, access);
+        match store.get(&key).await? {
+            Some(token) => {
+                self.track(token).await;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn track(&self, token: ManagedToken) {
+        self.tokens.track(token).await;
+    }
+
+    /// Returns a snapshot of the tracked tokens.
+    pub async fn tokens(&self) -> Vec<ManagedToken> {
+        let tokens = self.tokens.lock().await;
+        tokens.iter().map(|t| t.token.clone()).collect()
+    }
+
+    /// Returns the tracked token whose rotation is guarded by `rotation`, if still tracked.
+    fn find<'t>(
+        tokens: &'t mut [TrackedToken],
+        rotation: &Arc<Mutex<()>>,
+    ) -> Option<&'t mut TrackedToken> {
+        tokens
+            .iter_mut()
+            .find(|t| Arc::ptr_eq(&t.rotation, rotation))
+    }
+
+    /// Returns a current access token granting `scope`.
+    ///
+    /// A token expiring within the refresh margin is rotated first. Tokens the server
+    /// refuses to rotate are dropped, also from the token store, and the next matching
+    /// token is tried.
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error if no tracked token grants `scope`, or the rotation
+    /// error if a matching token could not be rotated.
+    pub async fn token(&self, scope: &AccessScope) -> Result<String> {
+        let mut rotation_error = None;
+
+        loop {
+            let rotation = {
+                let tokens = self.tokens.lock().await;
+                let Some(tracked) = tokens.iter().find(|t| t.token.grants(scope)) else {
+                    break;
+                };
+                if !tracked.token.expires_within(self.refresh_margin) {
+                    return Ok(tracked.token.access_token.value.clone());
+                }
+                tracked.rotation.clone()
+            };
+
+            // Only one call rotates a token; the others wait here and use its result
+            let _rotating = rotation.lock().await;
+            let current = {
+                let mut tokens = self.tokens.lock().await;
+                let Some(tracked) = Self::find(&mut tokens, &rotation) else {
+                    // Dropped by a failed rotation in the meantime
+                    continue;
+                };
+                if !tracked.token.expires_within(self.refresh_margin) {
+                    return Ok(tracked.token.access_token.value.clone());
+                }
+                tracked.token.clone()
+            };
+
+            let access_token = &current.access_token;
+            match rotate_access_token(self.client, &access_token.manage, Some(&access_token.value))
+                .await
+            {
+                Ok(response) => {
+                    let mut rotated = response.access_token;
+                    // Keep the granted access if the server does not repeat it
+                    if rotated.access.is_none() {
+                        rotated.access = current.access_token.access;
+                    }
+                    let token = ManagedToken::new(rotated, current.continue_);
+                    if let Some(tracked) = Self::find(&mut self.tokens.lock().await, &rotation) {
+                        tracked.token = token.clone();
+                    }
+                    save_token(self.client, &token).await?;
+                    return Ok(token.access_token.value);
+                }
+                // The token may still be valid, so keep it for the next attempt
+                Err(error) if error.is_retryable() => return Err(error),
+                Err(error) => {
+                    log::warn!("Failed to rotate access token: {error}");
+                    self.tokens
+                        .lock()
+                        .await
+                        .retain(|t| !Arc::ptr_eq(&t.rotation, &rotation));
+                    remove_token(self.client, &current).await?;
+                    rotation_error = Some(error);
+                }
+            }
+        }
+
+        Err(rotation_error.unwrap_or_else(|| {
+            Box::new(OpClientError::validation(
+                "No access token grants the requested access",
+                vec![format!("{scope:?}")],
+            ))
+        }))
+    }
+
+    /// Creates an incoming payment using a token granting `create`.
+    pub async fn create_incoming_payment(
+        &self,
+        resource_server_url: &str,
+        req_body: &IncomingPaymentRequest,
+    ) -> Result<IncomingPayment> {
+        let token = self
+            .token(&AccessScope::IncomingPayment {
+                action: IncomingPaymentAction::Create,
+                wallet_address: Some(req_body.wallet_address.to_string()),
+            })
+            .await?;
+        create_incoming_payment(self.client, resource_server_url, req_body, Some(&token)).await
+    }
+
+    /// Gets an incoming payment of `wallet_address` using a token granting `read`.
+    pub async fn get_incoming_payment(
+        &self,
+        payment_url: &str,
+        wallet_address: &str,
+    ) -> Result<IncomingPayment> {
+        let token = self
+            .token(&AccessScope::IncomingPayment {
+                action: IncomingPaymentAction::Read,
+                wallet_address: Some(wallet_address.to_string()),
+            })
+            .await?;
+        get_incoming_payment(self.client, payment_url, Some(&token)).await
+    }
+
+    /// Completes an incoming payment of `wallet_address` using a token granting `complete`.
+    pub async fn complete_incoming_payment(
+        &self,
+        payment_url: &str,
+        wallet_address: &str,
+    ) -> Result<IncomingPayment> {
+        let token = self
+            .token(&AccessScope::IncomingPayment {
+                action: IncomingPaymentAction::Complete,
+                wallet_address: Some(wallet_address.to_string()),
+            })
+            .await?;
+        complete_incoming_payment(self.client, payment_url, Some(&token)).await
+    }
+
+    /// Lists incoming payments using a token granting `list`.
+    pub async fn list_incoming_payments(
+        &self,
+        resource_server_url: &str,
+        wallet_address: &str,
+        options: &ListOptions,
+    ) -> Result<ListIncomingPaymentsResponse> {
+        let token = self
+            .token(&AccessScope::IncomingPayment {
+                action: IncomingPaymentAction::List,
+                wallet_address: Some(wallet_address.to_string()),
+            })
+            .await?;
+        list_incoming_payments(
+            self.client,
+            resource_server_url,
+            wallet_address,
+            options,
+            Some(&token),
+        )
+        .await
+    }
+
+    /// Creates an outgoing payment using a token granting `create`.
+    pub async fn create_outgoing_payment(
+        &self,
+        resource_server_url: &str,
+        req_body: &OutgoingPaymentRequest,
+    ) -> Result<OutgoingPayment> {
+        let wallet_address = match req_body {
+            OutgoingPaymentRequest::FromQuote { wallet_address, .. }
+            | OutgoingPaymentRequest::FromIncomingPayment { wallet_address, .. } => wallet_address,
+        };
+        let token = self
+            .token(&AccessScope::OutgoingPayment {
+                action: OutgoingPaymentAction::Create,
+                wallet_address: Some(wallet_address.to_string()),
+            })
+            .await?;
+        create_outgoing_payment(self.client, resource_server_url, req_body, Some(&token)).await
+    }
+
+    /// Gets an outgoing payment of `wallet_address` using a token granting `read`.
+    pub async fn get_outgoing_payment(
+        &self,
+        payment_url: &str,
+        wallet_address: &str,
+    ) -> Result<OutgoingPayment> {
+        let token = self
+            .token(&AccessScope::OutgoingPayment {
+                action: OutgoingPaymentAction::Read,
+                wallet_address: Some(wallet_address.to_string()),
+            })
+            .await?;
+        get_outgoing_payment(self.client, payment_url, Some(&token)).await
+    }
+
+    /// Lists outgoing payments using a token granting `list`.
+    pub async fn list_outgoing_payments(
+        &self,
+        resource_server_url: &str,
+        wallet_address: &str,
+        options: &ListOptions,
+    ) -> Result<ListOutgoingPaymentsResponse> {
+        let token = self
+            .token(&AccessScope::OutgoingPayment {
+                action: OutgoingPaymentAction::List,
+                wallet_address: Some(wallet_address.to_string()),
+            })
+            .await?;
+        list_outgoing_payments(
+            self.client,
+            resource_server_url,
+            wallet_address,
+            options,
+            Some(&token),
+        )
+        .await
+    }
+
+    /// Creates a quote using a token granting `create`.
+    pub async fn create_quote(
+        &self,
+        resource_server_url: &str,
+        req_body: &QuoteRequest,
+    ) -> Result<Quote> {
+        let token = self
+            .token(&AccessScope::Quote {
+                action: QuoteAction::Create,
+            })
+            .await?;
+        create_quote(self.client, resource_server_url, req_body, Some(&token)).await
+    }
+
+    /// Gets a quote using a token granting `read`.
+    pub async fn get_quote(&self, quote_url: &str) -> Result<Quote> {
+        let token = self
+            .token(&AccessScope::Quote {
+                action: QuoteAction::Read,
+            })
+            .await?;
+        get_quote(self.client, quote_url, Some(&token)).await
+    }
+}
+
+/// Returns `access_token`, or else a token granting `scope` from the client's tokens if
+/// the client manages its tokens.
+pub(crate) async fn select_token(
+    client: &AuthenticatedOpenPaymentsClient,
+    access_token: Option<&str>,
+    scope: AccessScope,
+) -> Result<Option<String>> {
+    match (access_token, &client.managed_tokens) {
+        (Some(access_token), _) => Ok(Some(access_token.to_string())),
+        (None, Some(_)) => GrantManager::new(client).token(&scope).await.map(Some),
+        (None, None) => Ok(None),
+    }
+}
+
+/// Tracks a token issued to the client if the client manages its tokens.
+pub(crate) async fn track_token(client: &AuthenticatedOpenPaymentsClient, token: &ManagedToken) {
+    if let Some(tokens) = &client.managed_tokens {
+        tokens.track(token.clone()).await;
+    }
+}
+
+/// Replaces the token managed at `manage` with its rotated `access_token` if the client
+/// manages its tokens.
+pub(crate) async fn track_rotated_token(
+    client: &AuthenticatedOpenPaymentsClient,
+    manage: &str,
+    access_token: &AccessToken,
+) {
+    if let Some(tokens) = &client.managed_tokens {
+        tokens.track_rotated(manage, access_token).await;
+    }
+}
+
+/// Stops tracking the token managed at `manage` if the client manages its tokens.
+pub(crate) async fn untrack_token(client: &AuthenticatedOpenPaymentsClient, manage: &str) {
+    if let Some(tokens) = &client.managed_tokens {
+        tokens.untrack(manage).await;
+    }
+}
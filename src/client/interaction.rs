@@ -0,0 +1,190 @@
+//! # Interaction Finish
+//!
+//! When a grant requires interaction, the client sends a nonce in
+//! [`InteractFinish`](crate::types::InteractFinish) and receives a second nonce in
+//! [`InteractResponse::finish`](crate::types::InteractResponse). Once the user has
+//! approved the grant, the authorization server redirects to the finish URI with an
+//! `interact_ref` and a `hash` binding both nonces, the reference and the grant endpoint
+//! together, as defined by
+//! [GNAP](https://www.rfc-editor.org/rfc/rfc9635#section-4.2.3).
+//!
+//! With the `push` finish method, the same values are instead sent by the authorization
+//! server in an [`InteractPush`] request to the finish URI (see [`mod@crate::client::push`]).
+//!
+//! This module parses the redirect and verifies its hash, so that a forged redirect or
+//! push is rejected before the `interact_ref` is used to continue the grant.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use open_payments::client::interaction::{interaction_hash, InteractRedirect};
+//! use open_payments::http_signature::DigestAlgorithm;
+//!
+//! let grant_endpoint = "https://auth.rafiki.money/";
+//! let hash = interaction_hash("client-nonce", "finish-nonce", "ref", grant_endpoint, DigestAlgorithm::Sha256);
+//! let url = format!("https://app.example/callback?hash={hash}&interact_ref=ref");
+//!
+//! let redirect = InteractRedirect::parse(&url).unwrap();
+//! redirect.verify("client-nonce", "finish-nonce", grant_endpoint, DigestAlgorithm::Sha256).unwrap();
+//! assert!(redirect.verify("other-nonce", "finish-nonce", grant_endpoint, DigestAlgorithm::Sha256).is_err());
+//! ```
+use crate::http_signature::content_digest::constant_time_eq;
+use crate::http_signature::DigestAlgorithm;
+use crate::types::InteractPush;
+use crate::{OpClientError, Result};
+use base64::engine::general_purpose::{STANDARD_NO_PAD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use url::Url;
+
+/// Parameters of the redirect to the interaction finish URI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InteractRedirect {
+    /// Interaction hash computed by the authorization server.
+    pub hash: String,
+    /// Reference to send when continuing the grant.
+    pub interact_ref: String,
+}
+
+impl InteractRedirect {
+    /// Parses the `hash` and `interact_ref` query parameters of the URL the user was
+    /// redirected to.
+    pub fn parse(url: &str) -> Result<Self> {
+        let url = Url::parse(url)?;
+        let param = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.into_owned())
+                .filter(|value| !value.is_empty())
+        };
+
+        match (param("hash"), param("interact_ref")) {
+            (Some(hash), Some(interact_ref)) => Ok(Self { hash, interact_ref }),
+            (hash, interact_ref) => {
+                let mut errors = Vec::new();
+                if hash.is_none() {
+                    errors.push("missing hash parameter".to_string());
+                }
+                if interact_ref.is_none() {
+                    errors.push("missing interact_ref parameter".to_string());
+                }
+                // Rafiki reports a rejected or failed interaction in `result`
+                if let Some(result) = param("result") {
+                    errors.push(format!("interaction result: {result}"));
+                }
+                Err(Box::new(OpClientError::validation(
+                    "Invalid interaction redirect",
+                    errors,
+                )))
+            }
+        }
+    }
+
+    /// Verifies the hash of the redirect, see [`verify_interaction_hash`].
+    pub fn verify(
+        &self,
+        client_nonce: &str,
+        finish_nonce: &str,
+        grant_endpoint: &str,
+        algorithm: DigestAlgorithm,
+    ) -> Result<()> {
+        verify_interaction_hash(
+            client_nonce,
+            finish_nonce,
+            &self.interact_ref,
+            grant_endpoint,
+            &self.hash,
+            algorithm,
+        )
+    }
+}
+
+impl InteractPush {
+    /// Verifies the hash of the pushed interaction, see [`verify_interaction_hash`].
+    pub fn verify(
+        &self,
+        client_nonce: &str,
+        finish_nonce: &str,
+        grant_endpoint: &str,
+        algorithm: DigestAlgorithm,
+    ) -> Result<()> {
+        verify_interaction_hash(
+            client_nonce,
+            finish_nonce,
+            &self.interact_ref,
+            grant_endpoint,
+            &self.hash,
+            algorithm,
+        )
+    }
+}
+
+fn hash_base(
+    client_nonce: &str,
+    finish_nonce: &str,
+    interact_ref: &str,
+    grant_endpoint: &str,
+) -> String {
+    format!("{client_nonce}\n{finish_nonce}\n{interact_ref}\n{grant_endpoint}")
+}
+
+/// Computes the interaction hash for the given nonces, reference and grant endpoint URL.
+///
+/// The hash is encoded with URL-safe base64 without padding.
+pub fn interaction_hash(
+    client_nonce: &str,
+    finish_nonce: &str,
+    interact_ref: &str,
+    grant_endpoint: &str,
+    algorithm: DigestAlgorithm,
+) -> String {
+    let base = hash_base(client_nonce, finish_nonce, interact_ref, grant_endpoint);
+    URL_SAFE_NO_PAD.encode(algorithm.digest(base.as_bytes()))
+}
+
+/// Verifies an interaction hash received on the finish URI.
+///
+/// `client_nonce` is the nonce sent in [`InteractFinish`](crate::types::InteractFinish),
+/// `finish_nonce` the one returned in [`InteractResponse`](crate::types::InteractResponse)
+/// and `grant_endpoint` the URL the grant request was sent to.
+///
+/// `algorithm` is the `hash_method` the client requested, `sha-256` if it sent none. A
+/// hash computed with any other algorithm is rejected. Both the URL-safe encoding of
+/// GNAP and the standard base64 encoding used by Rafiki are accepted. The hashes are
+/// compared in constant time.
+pub fn verify_interaction_hash(
+    client_nonce: &str,
+    finish_nonce: &str,
+    interact_ref: &str,
+    grant_endpoint: &str,
+    hash: &str,
+    algorithm: DigestAlgorithm,
+) -> Result<()> {
+    // A `+` left unencoded in the query reads as a space
+    let normalized: String = hash
+        .trim_end_matches('=')
+        .chars()
+        .map(|c| match c {
+            '-' | ' ' => '+',
+            '_' => '/',
+            c => c,
+        })
+        .collect();
+    let received = STANDARD_NO_PAD
+        .decode(normalized)
+        .map_err(|_| OpClientError::interaction_hash("hash is not valid base64"))?;
+    let base = hash_base(client_nonce, finish_nonce, interact_ref, grant_endpoint);
+    let expected = algorithm.digest(base.as_bytes());
+    if received.len() != expected.len() {
+        return Err(Box::new(OpClientError::interaction_hash(format!(
+            "expected a {algorithm} hash of {} bytes, got {} bytes",
+            expected.len(),
+            received.len()
+        ))));
+    }
+    if !constant_time_eq(&expected, &received) {
+        return Err(Box::new(OpClientError::interaction_hash(
+            "hash does not match the grant",
+        )));
+    }
+    Ok(())
+}
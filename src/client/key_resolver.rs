@@ -0,0 +1,255 @@
+//! # Remote Key Resolution
+//!
+//! This module provides [`JwksKeyResolver`], which resolves the keys of Open Payments
+//! clients for servers validating their signatures. The keys of a client are published
+//! in the JWKS of its wallet address, at `{wallet_address}/jwks.json`, and fetched through
+//! [`WalletAddressResource::get_keys`](crate::client::api::unauthenticated::WalletAddressResource::get_keys).
+//!
+//! A server learns which wallet address signed a request from the request itself, e.g.
+//! the `client` of a grant request, so the wallet address is given for each validation
+//! with [`JwksKeyResolver::for_wallet_address`], which returns a [`KeyResolver`].
+//!
+//! The fetched keys are cached by wallet address for a configurable time. A `keyid`
+//! missing from the cached keys triggers a refetch, so keys added by a client rotating
+//! its keys are picked up before the cache expires.
+//!
+//! ## Example
+//!
+//! ```rust,no_run
+//! use open_payments::client::{JwksKeyResolver, UnauthenticatedClient};
+//! use open_payments::http_signature::{validate_signature_with_resolver, ValidationPolicy};
+//! use open_payments::types::GrantRequest;
+//!
+//! # async fn example(
+//! #     resolver: &JwksKeyResolver<UnauthenticatedClient>,
+//! #     request: http::Request<Option<String>>,
+//! # ) -> Result<(), Box<dyn std::error::Error>> {
+//! // The resolver is created once and shared by all requests
+//! let grant: GrantRequest = serde_json::from_str(request.body().as_deref().unwrap_or("{}"))?;
+//!
+//! validate_signature_with_resolver(
+//!     &request,
+//!     request.headers(),
+//!     &resolver.for_wallet_address(grant.client())?,
+//!     ValidationPolicy::default(),
+//! )
+//! .await?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::cache::Lru;
+use crate::client::api::UnauthenticatedResources;
+use crate::client::BaseClient;
+use crate::http_signature::{HttpSignatureError, KeyResolver};
+use crate::types::wallet_address::JsonWebKeySet;
+use crate::types::{IntoWalletAddressUrl, WalletAddressUrl};
+use async_trait::async_trait;
+use ed25519_dalek::VerifyingKey;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Time the keys of a wallet address are cached for by default.
+const DEFAULT_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Minimum time between two fetches triggered by unknown key IDs, by default.
+const DEFAULT_REFETCH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Number of wallet addresses whose keys are cached by default.
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// Resolves the keys of clients from the JWKS of their wallet addresses.
+///
+/// Keys are cached for a bounded number of wallet addresses, evicting the least recently
+/// used ones. Wallet addresses whose keys could not be fetched are not cached, so
+/// requests naming made-up wallet addresses do not fill the cache.
+pub struct JwksKeyResolver<C> {
+    client: C,
+    ttl: Duration,
+    refetch_interval: Duration,
+    /// Cached keys by wallet address URL.
+    keys: std::sync::Mutex<Lru<Arc<KeyCache>>>,
+}
+
+impl<C: BaseClient + Send + Sync> JwksKeyResolver<C> {
+    /// Creates a resolver fetching wallet addresses and their keys with `client`.
+    pub fn new(client: C) -> Self {
+        Self {
+            client,
+            ttl: DEFAULT_TTL,
+            refetch_interval: DEFAULT_REFETCH_INTERVAL,
+            keys: std::sync::Mutex::new(Lru::new(DEFAULT_CAPACITY)),
+        }
+    }
+
+    /// Sets the number of wallet addresses whose keys are cached, 1024 by default.
+    pub fn capacity(self, capacity: usize) -> Self {
+        Self {
+            keys: std::sync::Mutex::new(Lru::new(capacity)),
+            ..self
+        }
+    }
+
+    /// Sets the time fetched keys are used for, five minutes by default.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Sets the minimum time between two fetches triggered by unknown key IDs, ten
+    /// seconds by default.
+    ///
+    /// This keeps signatures with made-up key IDs from causing a fetch each.
+    pub fn refetch_interval(mut self, refetch_interval: Duration) -> Self {
+        self.refetch_interval = refetch_interval;
+        self
+    }
+
+    /// Returns a [`KeyResolver`] for the keys of the wallet address at
+    /// `wallet_address_url`, e.g. the `client` of a grant request.
+    ///
+    /// The URL must use `https`, unless the client allows `http` wallet addresses.
+    pub fn for_wallet_address(
+        &self,
+        wallet_address_url: impl IntoWalletAddressUrl,
+    ) -> crate::Result<WalletAddressKeys<'_, C>> {
+        let wallet_address_url = wallet_address_url
+            .into_wallet_address_url(self.client.allow_http_wallet_addresses())?;
+        let cached = self.lock().get(wallet_address_url.as_str()).cloned();
+        Ok(WalletAddressKeys {
+            resolver: self,
+            wallet_address_url,
+            is_cached: cached.is_some(),
+            keys: cached.unwrap_or_else(|| {
+                Arc::new(KeyCache {
+                    ttl: self.ttl,
+                    refetch_interval: self.refetch_interval,
+                    keys: Mutex::new(None),
+                })
+            }),
+        })
+    }
+
+    /// Forgets the cached keys of a wallet address, so the next resolution fetches them
+    /// again.
+    pub async fn invalidate(
+        &self,
+        wallet_address_url: impl IntoWalletAddressUrl,
+    ) -> crate::Result<()> {
+        let wallet_address_url = WalletAddressUrl::for_invalidation(wallet_address_url)?;
+        self.lock().remove(wallet_address_url.as_str());
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Lru<Arc<KeyCache>>> {
+        self.keys.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// [`KeyResolver`] for the keys of one wallet address, returned by
+/// [`JwksKeyResolver::for_wallet_address`].
+pub struct WalletAddressKeys<'a, C> {
+    resolver: &'a JwksKeyResolver<C>,
+    wallet_address_url: WalletAddressUrl,
+    /// Whether `keys` is held by the resolver's cache.
+    is_cached: bool,
+    keys: Arc<KeyCache>,
+}
+
+impl<C> WalletAddressKeys<'_, C> {
+    /// Returns the URL of the wallet address whose keys are resolved.
+    pub fn wallet_address_url(&self) -> &WalletAddressUrl {
+        &self.wallet_address_url
+    }
+}
+
+#[async_trait]
+impl<C: BaseClient + Send + Sync> KeyResolver for WalletAddressKeys<'_, C> {
+    async fn resolve(&self, keyid: &str) -> Result<VerifyingKey, HttpSignatureError> {
+        let client = &self.resolver.client;
+        let key = self
+            .keys
+            .get(keyid, || async {
+                let wallet_address = client.wallet_address();
+                let wallet = wallet_address.get(&self.wallet_address_url).await?;
+                wallet_address.get_keys(&wallet).await
+            })
+            .await
+            .map_err(|e| HttpSignatureError::Other(format!("Failed to fetch JWKS: {e}")))?;
+
+        // The keys were fetched, so the wallet address exists and is worth caching
+        if !self.is_cached {
+            let mut cache = self.resolver.lock();
+            if cache.get(self.wallet_address_url.as_str()).is_none() {
+                cache.insert(self.wallet_address_url.as_str(), self.keys.clone());
+            }
+        }
+        key.ok_or_else(|| HttpSignatureError::UnknownKey(keyid.to_string()))
+    }
+}
+
+/// Verifying keys of one JWKS, refetched when they expire or an unknown key ID is seen.
+///
+/// Shared by [`JwksKeyResolver`] and the response verifier, so both throttle refetches
+/// the same way.
+pub(crate) struct KeyCache {
+    ttl: Duration,
+    refetch_interval: Duration,
+    keys: Mutex<Option<CachedKeys>>,
+}
+
+struct CachedKeys {
+    keys: HashMap<String, VerifyingKey>,
+    fetched_at: Instant,
+}
+
+impl KeyCache {
+    pub(crate) fn new() -> Self {
+        Self {
+            ttl: DEFAULT_TTL,
+            refetch_interval: DEFAULT_REFETCH_INTERVAL,
+            keys: Mutex::new(None),
+        }
+    }
+
+    /// Returns the key with the given ID, calling `fetch` for the JWKS if the cached keys
+    /// expired or do not contain `keyid` and were not fetched within the refetch interval.
+    pub(crate) async fn get<F, Fut, E>(
+        &self,
+        keyid: &str,
+        fetch: F,
+    ) -> Result<Option<VerifyingKey>, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<JsonWebKeySet, E>>,
+    {
+        // Holding the lock while fetching lets concurrent lookups share one fetch
+        let mut cached = self.keys.lock().await;
+        let refetch = match cached.as_ref() {
+            None => true,
+            Some(keys) => {
+                let age = keys.fetched_at.elapsed();
+                age >= self.ttl || (!keys.keys.contains_key(keyid) && age >= self.refetch_interval)
+            }
+        };
+        if refetch {
+            let jwks = fetch().await?;
+            // Keys that cannot be used for Ed25519 verification are skipped
+            let keys = jwks
+                .keys
+                .iter()
+                .filter_map(|jwk| Some((jwk.kid.clone(), VerifyingKey::try_from(jwk).ok()?)))
+                .collect();
+            *cached = Some(CachedKeys {
+                keys,
+                fetched_at: Instant::now(),
+            });
+        }
+
+        Ok(cached
+            .as_ref()
+            .and_then(|keys| keys.keys.get(keyid).copied()))
+    }
+}
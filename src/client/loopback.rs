@@ -0,0 +1,270 @@
+//! # Loopback Redirects
+//!
+//! This module, enabled with the `loopback` feature, receives the interaction finish
+//! redirect of an interactive grant on a one-shot HTTP listener bound to an ephemeral
+//! port of `127.0.0.1`. It lets a CLI or desktop application run an interactive grant,
+//! such as one for outgoing payments, with a single call to
+//! [`Grant::request_with_loopback`](crate::client::api::authenticated::Grant::request_with_loopback):
+//!
+//! 1. a [`LoopbackListener`] is bound and the grant is requested with a `redirect`
+//!    finish pointing to it and a fresh nonce
+//! 2. the caller opens the interaction URL, e.g. in the user's browser
+//! 3. the redirect is received and its hash verified (see [`mod@crate::client::interaction`])
+//! 4. the grant is continued with the received `interact_ref`
+//!
+//! ## Example
+//!
+//! ```rust,no_run
+//! use open_payments::client::{AuthenticatedClient, AuthenticatedResources, LoopbackOptions};
+//! use open_payments::types::GrantRequest;
+//!
+//! async fn authorize(client: &AuthenticatedClient, auth_server: &str, grant: &GrantRequest) -> open_payments::client::Result<()> {
+//!     let (access_token, _continue) = client
+//!         .grant()
+//!         .request_with_loopback(auth_server, grant, &LoopbackOptions::default(), |url| {
+//!             println!("Open {url} to approve the payment");
+//!         })
+//!         .await?;
+//!     println!("{}", access_token.value);
+//!     Ok(())
+//! }
+//! ```
+use crate::client::AuthenticatedOpenPaymentsClient;
+use crate::grant::{
+    continue_grant, missing_access_token, poll_until_granted, request_grant, PollOptions,
+};
+use crate::http_signature::DigestAlgorithm;
+use crate::interaction::InteractRedirect;
+use crate::types::{
+    AccessToken, Continue, ContinueResponse, FinishMethod, GrantRequest, GrantResponse,
+    InteractFinish, InteractRequest, InteractStart,
+};
+use crate::{ErrorKind, OpClientError, Result};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Path the listener expects the redirect on.
+const CALLBACK_PATH: &str = "/callback";
+
+/// Maximum size of the request head read from a connection.
+const MAX_REQUEST_HEAD: usize = 8 * 1024;
+
+/// Time a connection is given to send its request head.
+const REQUEST_READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Options for [`Grant::request_with_loopback`](crate::client::api::authenticated::Grant::request_with_loopback).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoopbackOptions {
+    /// Time to wait for the user to finish the interaction.
+    pub timeout: Option<Duration>,
+    /// Options for polling the grant if it is still pending after the interaction.
+    pub poll: PollOptions,
+}
+
+impl Default for LoopbackOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Some(Duration::from_secs(300)),
+            poll: PollOptions::default(),
+        }
+    }
+}
+
+/// One-shot HTTP listener receiving an interaction finish redirect.
+#[derive(Debug)]
+pub struct LoopbackListener {
+    listener: TcpListener,
+    finish: InteractFinish,
+}
+
+impl LoopbackListener {
+    /// Binds a listener to an ephemeral port of `127.0.0.1` and generates the nonce of
+    /// its finish method.
+    pub async fn bind() -> Result<Self> {
+        let listener = TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, 0))).await?;
+        let uri = format!("http://{}{CALLBACK_PATH}", listener.local_addr()?);
+        Ok(Self {
+            listener,
+            finish: InteractFinish::new(FinishMethod::Redirect, uri),
+        })
+    }
+
+    /// Returns the `redirect` finish method to send in the grant request.
+    pub fn interact_finish(&self) -> InteractFinish {
+        self.finish.clone()
+    }
+
+    /// Waits for the redirect and verifies its hash.
+    ///
+    /// `finish_nonce` is [`InteractResponse::finish`](crate::types::InteractResponse) and
+    /// `grant_endpoint` the URL the grant request was sent to. Requests for other paths,
+    /// such as a browser asking for a favicon, are answered with `404` and ignored.
+    /// Redirects with a missing or wrong hash are answered with `400` and ignored too, so
+    /// a forged request cannot end the interaction. This includes redirects reporting a
+    /// rejected or failed interaction in a `result` parameter, which carry no hash that
+    /// could prove they come from the authorization server. Waiting ends with the first
+    /// verified redirect, so callers should bound it with a timeout. The browser is shown
+    /// a short page saying whether the redirect was accepted.
+    pub async fn wait_for_redirect(
+        self,
+        finish_nonce: &str,
+        grant_endpoint: &str,
+    ) -> Result<InteractRedirect> {
+        loop {
+            let (mut stream, _) = self.listener.accept().await?;
+            let target = match read_request_target(&mut stream).await {
+                Ok(Some(target)) => target,
+                Ok(None) => {
+                    respond(&mut stream, "404 Not Found", "Not found").await;
+                    continue;
+                }
+                // A stalled or broken connection does not end the interaction
+                Err(_) => continue,
+            };
+
+            let url = format!("{}{target}", self.finish.uri);
+            let result = InteractRedirect::parse(&url).and_then(|redirect| {
+                // No `hash_method` is sent, so GNAP's default applies
+                redirect.verify(
+                    &self.finish.nonce,
+                    finish_nonce,
+                    grant_endpoint,
+                    DigestAlgorithm::Sha256,
+                )?;
+                Ok(redirect)
+            });
+            match result {
+                Ok(redirect) => {
+                    respond(
+                        &mut stream,
+                        "200 OK",
+                        "The grant was approved. You can close this window.",
+                    )
+                    .await;
+                    return Ok(redirect);
+                }
+                Err(error) => {
+                    log::warn!("Ignoring interaction redirect: {error}");
+                    respond(
+                        &mut stream,
+                        "400 Bad Request",
+                        "Invalid interaction redirect",
+                    )
+                    .await;
+                }
+            }
+        }
+    }
+}
+
+/// Reads the head of a request and returns its target if it is a `GET` of the callback path.
+///
+/// Fails if the head is not received within [`REQUEST_READ_TIMEOUT`].
+async fn read_request_target(stream: &mut TcpStream) -> Result<Option<String>> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    let read_head = async {
+        while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < MAX_REQUEST_HEAD {
+            let read = stream.read(&mut buf).await?;
+            if read == 0 {
+                break;
+            }
+            head.extend_from_slice(&buf[..read]);
+        }
+        Ok::<_, std::io::Error>(())
+    };
+    tokio::time::timeout(REQUEST_READ_TIMEOUT, read_head)
+        .await
+        .map_err(|_| {
+            OpClientError::other("Timed out reading the redirect request")
+                .with_kind(ErrorKind::Timeout)
+        })??;
+
+    let head = String::from_utf8_lossy(&head);
+    let mut request_line = head.lines().next().unwrap_or_default().split(' ');
+    let (Some("GET"), Some(target)) = (request_line.next(), request_line.next()) else {
+        return Ok(None);
+    };
+    let path = target.split('?').next().unwrap_or_default();
+    Ok((path == CALLBACK_PATH).then(|| target.trim_start_matches(CALLBACK_PATH).to_string()))
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, body: &str) {
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    // The browser going away does not affect the outcome of the interaction
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// Requests an interactive grant and completes it through a [`LoopbackListener`].
+pub(crate) async fn request_with_loopback(
+    client: &AuthenticatedOpenPaymentsClient,
+    auth_url: &str,
+    grant: &GrantRequest,
+    options: &LoopbackOptions,
+    open: impl FnOnce(&str),
+) -> Result<(AccessToken, Continue)> {
+    let listener = LoopbackListener::bind().await?;
+    let grant = GrantRequest {
+        interact: Some(InteractRequest {
+            start: vec![InteractStart::Redirect],
+            finish: Some(listener.interact_finish()),
+        }),
+        ..grant.clone()
+    };
+
+    let (interact, continue_) = match request_grant(client, auth_url, &grant).await? {
+        GrantResponse::WithToken {
+            access_token,
+            continue_,
+        } => return Ok((access_token, continue_)),
+        GrantResponse::WithInteraction {
+            interact,
+            continue_,
+        } => (interact, continue_),
+        GrantResponse::WithSubject {
+            access_token: Some(access_token),
+            continue_: Some(continue_),
+            ..
+        } => return Ok((access_token, continue_)),
+        GrantResponse::WithSubject { .. } => return Err(missing_access_token()),
+    };
+
+    open(interact.redirect.as_str());
+    let wait = listener.wait_for_redirect(&interact.finish, auth_url);
+    let redirect = match options.timeout {
+        Some(timeout) => tokio::time::timeout(timeout, wait).await.map_err(|_| {
+            OpClientError::other("Interaction was not finished before the timeout")
+                .with_kind(ErrorKind::Timeout)
+        })??,
+        None => wait.await?,
+    };
+
+    match continue_grant(
+        client,
+        &continue_.uri,
+        Some(&redirect.interact_ref),
+        Some(&continue_.access_token.value),
+    )
+    .await?
+    {
+        ContinueResponse::WithToken {
+            access_token,
+            continue_,
+        } => Ok((access_token, continue_)),
+        ContinueResponse::WithSubject {
+            access_token: Some(access_token),
+            continue_: Some(continue_),
+            ..
+        } => Ok((access_token, continue_)),
+        ContinueResponse::WithSubject { .. } => Err(missing_access_token()),
+        ContinueResponse::Pending { continue_ } => {
+            poll_until_granted(client, &continue_, &options.poll).await
+        }
+    }
+}
@@ -12,6 +12,7 @@
 //! ## Configuration
 //!
 //! - [`ClientConfig`] - Configuration for authenticated clients including private key and key ID
+//! - [`ClientBuilder`] - Builder for authenticated clients with in-memory keys and custom HTTP settings
 //!
 //! ## Resource APIs
 //!
@@ -64,7 +65,7 @@
 //!     let resource_server = "https://ilp.rafiki.money";
 //!     let access_token = "your-access-token";
 //!     let payment_request = CreateIncomingPaymentRequest {
-//!         wallet_address: wallet_address.id,
+//!         wallet_address: wallet_address.id.parse()?,
 //!         incoming_amount: None,
 //!         expires_at: None,
 //!         metadata: None,
@@ -86,19 +87,43 @@
 //! detailed error information for different failure scenarios.
 
 pub mod api;
+pub mod builder;
+pub mod cache;
 pub mod config;
 pub mod core;
-pub mod error;
+pub use crate::error;
 pub mod grant;
+pub mod grant_manager;
+pub mod interaction;
+pub mod key_resolver;
+#[cfg(feature = "loopback")]
+pub mod loopback;
+pub mod pagination;
 pub mod payments;
+pub mod push;
 pub mod quotes;
 pub mod request;
+pub mod response_verification;
+pub mod retry;
 pub mod token;
+pub mod token_store;
 pub mod utils;
 pub mod wallet_address;
 
 pub use api::{AuthenticatedResources, UnauthenticatedResources};
+pub use builder::ClientBuilder;
+pub use cache::{CacheEntry, CacheStore, InMemoryCacheStore, WalletAddressCache};
 pub use config::ClientConfig;
 pub use core::{AuthenticatedClient, UnauthenticatedClient};
 pub use core::{AuthenticatedOpenPaymentsClient, BaseClient, UnauthenticatedOpenPaymentsClient};
-pub use error::{OpClientError, Result};
+pub use error::{ErrorKind, GnapErrorCode, OpClientError, Result};
+pub use grant::PollOptions;
+pub use grant_manager::{AccessScope, GrantManager, ManagedToken};
+pub use interaction::{interaction_hash, verify_interaction_hash, InteractRedirect};
+pub use key_resolver::{JwksKeyResolver, WalletAddressKeys};
+#[cfg(feature = "loopback")]
+pub use loopback::{LoopbackListener, LoopbackOptions};
+pub use pagination::{ListOptions, PageDirection};
+pub use push::{PendingPush, PushFinishHandler};
+pub use retry::RetryPolicy;
+pub use token_store::{EncryptedFileTokenStore, InMemoryTokenStore, TokenKey, TokenStore};
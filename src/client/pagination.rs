@@ -0,0 +1,280 @@
+//! # Pagination
+//!
+//! This module defines [`ListOptions`], the query of the cursor-based list endpoints of
+//! the resource server, and turns those endpoints into streams of items. The streams are returned by the `stream` methods of
+//! [`IncomingPaymentResource`](crate::client::api::authenticated::IncomingPaymentResource)
+//! and [`OutgoingPaymentResource`](crate::client::api::authenticated::OutgoingPaymentResource),
+//! which request the next page only once the items of the current one have been consumed.
+//!
+//! ## Example
+//!
+//! ```rust,no_run
+//! use futures::StreamExt;
+//! use open_payments::client::{AuthenticatedClient, AuthenticatedResources, ListOptions};
+//!
+//! async fn print_payments(client: &AuthenticatedClient) -> open_payments::client::Result<()> {
+//!     let options = ListOptions::new().first(100).max_items(1_000);
+//!     let payments = client.incoming_payments().stream(
+//!         "https://ilp.rafiki.money",
+//!         "https://ilp.rafiki.money/alice",
+//!         options,
+//!         Some("access-token"),
+//!     );
+//!     let mut payments = std::pin::pin!(payments);
+//!     while let Some(payment) = payments.next().await {
+//!         println!("{}", payment?.id);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+use crate::types::PaginatedResponse;
+use crate::{OpClientError, Result};
+use futures::stream::{self, Stream};
+use std::collections::VecDeque;
+use std::future::Future;
+use url::Url;
+
+/// Direction in which pages are followed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PageDirection {
+    /// Follows `end_cursor` while `has_next_page` is set, requesting pages with `first`.
+    #[default]
+    Forward,
+    /// Follows `start_cursor` while `has_previous_page` is set, requesting pages with `last`.
+    ///
+    /// A starting cursor is required. Without a page size, pages of [`MAX_PAGE_SIZE`]
+    /// items are requested, since the server only pages backward when `last` is sent.
+    Backward,
+}
+
+/// Maximum number of items the resource server returns per page.
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+/// Query parameters set by [`ListOptions`] itself, which cannot be used as filters.
+const RESERVED_PARAMS: &[&str] = &["wallet-address", "cursor", "first", "last"];
+
+/// Options for listing incoming or outgoing payments.
+///
+/// Pages are requested forward with `first` by default, or backward from a cursor with
+/// `last`. The options are validated before any request is sent.
+///
+/// ```rust
+/// use open_payments::client::ListOptions;
+///
+/// let options = ListOptions::new()
+///     .first(20)
+///     .filter("state", "completed");
+/// assert!(options.validate().is_ok());
+///
+/// // Paging backward requires a cursor
+/// assert!(ListOptions::new().last(20).validate().is_err());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListOptions {
+    direction: PageDirection,
+    page_size: Option<u32>,
+    cursor: Option<String>,
+    filters: Vec<(String, String)>,
+    max_items: Option<usize>,
+}
+
+impl ListOptions {
+    /// Creates options requesting the first page with the server's default page size.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests pages of `page_size` items forward from the cursor.
+    pub fn first(mut self, page_size: u32) -> Self {
+        self.direction = PageDirection::Forward;
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Requests pages of `page_size` items backward from the cursor.
+    pub fn last(mut self, page_size: u32) -> Self {
+        self.direction = PageDirection::Backward;
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Sets the direction in which pages are requested.
+    pub fn direction(mut self, direction: PageDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Starts listing at the given cursor.
+    pub fn cursor(mut self, cursor: impl Into<String>) -> Self {
+        self.cursor = Some(cursor.into());
+        self
+    }
+
+    /// Adds a query parameter accepted by the resource server.
+    pub fn filter(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.filters.push((name.into(), value.into()));
+        self
+    }
+
+    /// Limits the total number of items yielded when streaming all pages.
+    ///
+    /// This only applies to the `stream` and `list_all` methods. The single page `list`
+    /// methods reject options setting it rather than ignoring it.
+    pub fn max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    /// Returns the direction in which pages are requested.
+    pub fn page_direction(&self) -> PageDirection {
+        self.direction
+    }
+
+    /// Returns the cursor to start listing at.
+    pub fn start_cursor(&self) -> Option<&str> {
+        self.cursor.as_deref()
+    }
+
+    /// Checks that the options can be sent to the resource server.
+    ///
+    /// Paging backward requires a cursor, the page size must be between 1 and
+    /// [`MAX_PAGE_SIZE`], and filters cannot set the pagination parameters.
+    pub fn validate(&self) -> Result<()> {
+        let mut errors = Vec::new();
+        if self.direction == PageDirection::Backward && self.cursor.is_none() {
+            errors.push("a cursor is required to page backward".to_string());
+        }
+        if let Some(page_size) = self.page_size {
+            if !(1..=MAX_PAGE_SIZE).contains(&page_size) {
+                errors.push(format!(
+                    "page size must be between 1 and {MAX_PAGE_SIZE}, got {page_size}"
+                ));
+            }
+        }
+        for (name, _) in &self.filters {
+            if RESERVED_PARAMS.contains(&name.as_str()) {
+                errors.push(format!("{name:?} cannot be used as a filter"));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Box::new(OpClientError::validation(
+                "Invalid list options",
+                errors,
+            )))
+        }
+    }
+
+    /// Validates the options and appends them to the query of `url`.
+    ///
+    /// Streams remove `max_items` before requesting a page, so options still setting it
+    /// were passed to a single page request and are rejected.
+    pub(crate) fn apply(&self, url: &mut Url) -> Result<()> {
+        self.validate()?;
+        if self.max_items.is_some() {
+            return Err(Box::new(OpClientError::validation(
+                "Invalid list options",
+                vec!["max_items only applies to streams of all pages".to_string()],
+            )));
+        }
+
+        let mut query = url.query_pairs_mut();
+        if let Some(cursor) = &self.cursor {
+            query.append_pair("cursor", cursor);
+        }
+        match (self.direction, self.page_size) {
+            (PageDirection::Forward, Some(page_size)) => {
+                query.append_pair("first", &page_size.to_string());
+            }
+            (PageDirection::Forward, None) => {}
+            (PageDirection::Backward, page_size) => {
+                let page_size = page_size.unwrap_or(MAX_PAGE_SIZE);
+                query.append_pair("last", &page_size.to_string());
+            }
+        }
+        for (name, value) in &self.filters {
+            query.append_pair(name, value);
+        }
+        Ok(())
+    }
+}
+
+struct PageState<T, F> {
+    fetch: F,
+    options: ListOptions,
+    items: VecDeque<T>,
+    has_more: bool,
+    remaining: Option<usize>,
+    /// Validation error yielded instead of the first page.
+    invalid: Option<Box<OpClientError>>,
+}
+
+/// Streams the items of all pages returned by `fetch`.
+///
+/// `fetch` is called with the options for the page to request. The stream ends after the
+/// last page, after `max_items` items, after an empty page, or after the first error.
+pub(crate) fn paginate<'a, T, F, Fut>(
+    options: ListOptions,
+    fetch: F,
+) -> impl Stream<Item = Result<T>> + 'a
+where
+    T: 'a,
+    F: FnMut(ListOptions) -> Fut + 'a,
+    Fut: Future<Output = Result<PaginatedResponse<T>>> + 'a,
+{
+    let mut options = options;
+    let state = PageState {
+        fetch,
+        items: VecDeque::new(),
+        has_more: true,
+        remaining: options.max_items.take(),
+        invalid: options.validate().err(),
+        options,
+    };
+
+    stream::unfold(state, |mut state| async move {
+        if let Some(error) = state.invalid.take() {
+            state.has_more = false;
+            return Some((Err(error), state));
+        }
+
+        loop {
+            if state.remaining == Some(0) {
+                return None;
+            }
+            if let Some(item) = state.items.pop_front() {
+                state.remaining = state.remaining.map(|remaining| remaining - 1);
+                return Some((Ok(item), state));
+            }
+            if !state.has_more {
+                return None;
+            }
+
+            let page = match (state.fetch)(state.options.clone()).await {
+                Ok(page) => page,
+                Err(error) => {
+                    state.has_more = false;
+                    return Some((Err(error), state));
+                }
+            };
+            let (has_more, cursor) = match state.options.direction {
+                PageDirection::Forward => {
+                    (page.pagination.has_next_page, page.pagination.end_cursor)
+                }
+                PageDirection::Backward => (
+                    page.pagination.has_previous_page,
+                    page.pagination.start_cursor,
+                ),
+            };
+            // Stop rather than request the same page again if the server does not advance
+            state.has_more = has_more
+                && !page.result.is_empty()
+                && cursor.is_some()
+                && cursor != state.options.cursor;
+            state.options.cursor = cursor;
+            state.items.extend(page.result);
+        }
+    })
+}
@@ -1,12 +1,16 @@
 use crate::client::{AuthenticatedOpenPaymentsClient, BaseClient};
+use crate::grant_manager::{select_token, AccessScope};
+use crate::pagination::{paginate, ListOptions};
 use crate::request::{AuthenticatedRequest, UnauthenticatedRequest};
 use crate::types::{
-    IncomingPayment, IncomingPaymentRequest, ListIncomingPaymentsResponse,
-    ListOutgoingPaymentsResponse, OutgoingPayment, OutgoingPaymentRequest, PublicIncomingPayment,
+    IncomingPayment, IncomingPaymentAction, IncomingPaymentRequest, ListIncomingPaymentsResponse,
+    ListOutgoingPaymentsResponse, OutgoingPayment, OutgoingPaymentAction, OutgoingPaymentRequest,
+    PublicIncomingPayment,
 };
 use crate::utils::join_url_paths;
 use crate::OpClientError;
 use crate::Result;
+use futures::Stream;
 use reqwest::Method;
 use url::Url;
 
@@ -42,7 +46,9 @@ pub(crate) async fn complete_incoming_payment(
 ) -> Result<IncomingPayment> {
     let url = join_url_paths(payment_url, "complete")?;
 
+    // Completing an already completed incoming payment leaves it unchanged
     AuthenticatedRequest::new(client, Method::POST, url)
+        .idempotent()
         .build_and_execute(access_token)
         .await
 }
@@ -51,32 +57,55 @@ pub(crate) async fn list_incoming_payments(
     client: &AuthenticatedOpenPaymentsClient,
     resource_server_url: &str,
     wallet_address: &str,
-    cursor: Option<&str>,
-    first: Option<u32>,
-    last: Option<u32>,
+    options: &ListOptions,
     access_token: Option<&str>,
 ) -> Result<ListIncomingPaymentsResponse> {
-    let mut url = Url::parse(&join_url_paths(resource_server_url, "incoming-payments")?)?;
-
-    url.query_pairs_mut()
-        .append_pair("wallet-address", wallet_address);
-
-    if let Some(cursor) = cursor {
-        url.query_pairs_mut().append_pair("cursor", cursor);
-    }
-    if let Some(first) = first {
-        url.query_pairs_mut()
-            .append_pair("first", &first.to_string());
-    }
-    if let Some(last) = last {
-        url.query_pairs_mut().append_pair("last", &last.to_string());
-    }
-
-    AuthenticatedRequest::new(client, Method::GET, url.to_string())
+    let url = list_url(
+        resource_server_url,
+        "incoming-payments",
+        wallet_address,
+        options,
+    )?;
+
+    AuthenticatedRequest::new(client, Method::GET, url)
         .build_and_execute(access_token)
         .await
 }
 
+pub(crate) fn stream_incoming_payments<'a>(
+    client: &'a AuthenticatedOpenPaymentsClient,
+    resource_server_url: &str,
+    wallet_address: &str,
+    options: ListOptions,
+    access_token: Option<&str>,
+) -> impl Stream<Item = Result<IncomingPayment>> + 'a {
+    let resource_server_url = resource_server_url.to_string();
+    let wallet_address = wallet_address.to_string();
+    let access_token = access_token.map(str::to_string);
+
+    paginate(options, move |options| {
+        let resource_server_url = resource_server_url.clone();
+        let wallet_address = wallet_address.clone();
+        let access_token = access_token.clone();
+        async move {
+            // Selected for each page, so a managed token expiring while streaming is rotated
+            let scope = AccessScope::IncomingPayment {
+                action: IncomingPaymentAction::List,
+                wallet_address: Some(wallet_address.clone()),
+            };
+            let access_token = select_token(client, access_token.as_deref(), scope).await?;
+            list_incoming_payments(
+                client,
+                &resource_server_url,
+                &wallet_address,
+                &options,
+                access_token.as_deref(),
+            )
+            .await
+        }
+    })
+}
+
 pub(crate) async fn create_outgoing_payment(
     client: &AuthenticatedOpenPaymentsClient,
     resource_server_url: &str,
@@ -106,32 +135,55 @@ pub(crate) async fn list_outgoing_payments(
     client: &AuthenticatedOpenPaymentsClient,
     resource_server_url: &str,
     wallet_address: &str,
-    cursor: Option<&str>,
-    first: Option<u32>,
-    last: Option<u32>,
+    options: &ListOptions,
     access_token: Option<&str>,
 ) -> Result<ListOutgoingPaymentsResponse> {
-    let mut url = Url::parse(&join_url_paths(resource_server_url, "outgoing-payments")?)?;
-
-    url.query_pairs_mut()
-        .append_pair("wallet-address", wallet_address);
-
-    if let Some(cursor) = cursor {
-        url.query_pairs_mut().append_pair("cursor", cursor);
-    }
-    if let Some(first) = first {
-        url.query_pairs_mut()
-            .append_pair("first", &first.to_string());
-    }
-    if let Some(last) = last {
-        url.query_pairs_mut().append_pair("last", &last.to_string());
-    }
-
-    AuthenticatedRequest::new(client, Method::GET, url.to_string())
+    let url = list_url(
+        resource_server_url,
+        "outgoing-payments",
+        wallet_address,
+        options,
+    )?;
+
+    AuthenticatedRequest::new(client, Method::GET, url)
         .build_and_execute(access_token)
         .await
 }
 
+pub(crate) fn stream_outgoing_payments<'a>(
+    client: &'a AuthenticatedOpenPaymentsClient,
+    resource_server_url: &str,
+    wallet_address: &str,
+    options: ListOptions,
+    access_token: Option<&str>,
+) -> impl Stream<Item = Result<OutgoingPayment>> + 'a {
+    let resource_server_url = resource_server_url.to_string();
+    let wallet_address = wallet_address.to_string();
+    let access_token = access_token.map(str::to_string);
+
+    paginate(options, move |options| {
+        let resource_server_url = resource_server_url.clone();
+        let wallet_address = wallet_address.clone();
+        let access_token = access_token.clone();
+        async move {
+            // Selected for each page, so a managed token expiring while streaming is rotated
+            let scope = AccessScope::OutgoingPayment {
+                action: OutgoingPaymentAction::List,
+                wallet_address: Some(wallet_address.clone()),
+            };
+            let access_token = select_token(client, access_token.as_deref(), scope).await?;
+            list_outgoing_payments(
+                client,
+                &resource_server_url,
+                &wallet_address,
+                &options,
+                access_token.as_deref(),
+            )
+            .await
+        }
+    })
+}
+
 pub(crate) async fn get_public_incoming_payment<C: BaseClient>(
     client: &C,
     payment_url: &str,
@@ -140,3 +192,17 @@ pub(crate) async fn get_public_incoming_payment<C: BaseClient>(
         .build_and_execute()
         .await
 }
+
+/// Builds the URL of a list endpoint for the given wallet address and options.
+fn list_url(
+    resource_server_url: &str,
+    path: &str,
+    wallet_address: &str,
+    options: &ListOptions,
+) -> Result<String> {
+    let mut url = Url::parse(&join_url_paths(resource_server_url, path)?)?;
+    url.query_pairs_mut()
+        .append_pair("wallet-address", wallet_address);
+    options.apply(&mut url)?;
+    Ok(url.to_string())
+}
@@ -0,0 +1,163 @@
+//! # Push Finish
+//!
+//! With the GNAP `push` finish method, the authorization server does not redirect the
+//! user back to the client once the interaction has finished. It sends a `POST` request
+//! with an [`InteractPush`] body to the finish URI instead, which suits server-side
+//! integrations that cannot receive browser redirects.
+//!
+//! [`PushFinishHandler`] keeps track of the grants waiting for such a request. It is
+//! framework-agnostic: the route serving the finish URI passes the incoming
+//! [`http::Request`] to [`PushFinishHandler::handle`] and returns the response it
+//! produces. A verified push resolves the matching [`PendingPush`], which
+//! [`Grant::continue_after_push`](crate::client::api::authenticated::Grant::continue_after_push)
+//! waits on before continuing the grant.
+//!
+//! ## Example
+//!
+//! ```rust,no_run
+//! use open_payments::client::{AuthenticatedClient, AuthenticatedResources, PushFinishHandler};
+//! use open_payments::types::{
+//!     FinishMethod, GrantRequest, GrantResponse, InteractFinish, InteractRequest, InteractStart,
+//! };
+//!
+//! async fn authorize(
+//!     client: &AuthenticatedClient,
+//!     handler: &PushFinishHandler,
+//!     auth_server: &str,
+//!     mut grant: GrantRequest,
+//! ) -> open_payments::client::Result<()> {
+//!     let finish = InteractFinish::new(FinishMethod::Push, "https://app.example/gnap/push");
+//!     grant.interact = Some(InteractRequest {
+//!         start: vec![InteractStart::Redirect],
+//!         finish: Some(finish.clone()),
+//!     });
+//!
+//!     if let GrantResponse::WithInteraction { interact, continue_ } =
+//!         client.grant().request(auth_server, &grant).await?
+//!     {
+//!         let pending = handler.expect(&finish, &interact, auth_server);
+//!         println!("Send the user to {}", interact.redirect);
+//!
+//!         // Meanwhile, the route serving https://app.example/gnap/push responds with
+//!         // `handler.handle(&request)`
+//!         let response = client
+//!             .grant()
+//!             .continue_after_push(pending, &continue_.uri, Some(&continue_.access_token.value))
+//!             .await?;
+//!     }
+//!     Ok(())
+//! }
+//! ```
+use crate::http_signature::DigestAlgorithm;
+use crate::types::{InteractFinish, InteractPush, InteractResponse};
+use crate::{OpClientError, Result};
+use http::{Method, Request, Response, StatusCode};
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+/// Grant waiting for its interaction to be pushed.
+struct Expected {
+    client_nonce: String,
+    finish_nonce: String,
+    grant_endpoint: String,
+    sender: oneshot::Sender<String>,
+}
+
+/// Receives the verified pushes sent to a finish URI and hands their `interact_ref` to
+/// the grants waiting for them.
+///
+/// Clones share the same pending grants, so one clone can be given to the web server
+/// while another is used to register grants.
+#[derive(Clone, Default)]
+pub struct PushFinishHandler {
+    expected: Arc<Mutex<Vec<Expected>>>,
+}
+
+/// Interaction reference of a grant, available once its push has been received.
+///
+/// Dropping it stops waiting for the push.
+#[derive(Debug)]
+pub struct PendingPush {
+    receiver: oneshot::Receiver<String>,
+}
+
+impl PendingPush {
+    /// Waits for the push and returns its `interact_ref`.
+    pub async fn interact_ref(self) -> Result<String> {
+        self.receiver.await.map_err(|_| {
+            Box::new(OpClientError::other(
+                "Push finish handler was dropped before the interaction was finished",
+            ))
+        })
+    }
+}
+
+impl PushFinishHandler {
+    /// Creates a handler without pending grants.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Expected>> {
+        self.expected.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Registers a grant waiting for its interaction to be pushed.
+    ///
+    /// `finish` is the finish method sent in the grant request, `interact` the interaction
+    /// returned for it and `grant_endpoint` the URL the grant request was sent to.
+    pub fn expect(
+        &self,
+        finish: &InteractFinish,
+        interact: &InteractResponse,
+        grant_endpoint: &str,
+    ) -> PendingPush {
+        let (sender, receiver) = oneshot::channel();
+        let mut expected = self.lock();
+        expected.retain(|e| !e.sender.is_closed());
+        expected.push(Expected {
+            client_nonce: finish.nonce.clone(),
+            finish_nonce: interact.finish.clone(),
+            grant_endpoint: grant_endpoint.to_string(),
+            sender,
+        });
+        PendingPush { receiver }
+    }
+
+    /// Handles a request sent to the finish URI.
+    ///
+    /// A `POST` with an [`InteractPush`] body whose hash matches a pending grant resolves
+    /// that grant and is answered with `200`. Other methods are answered with `405`, and
+    /// malformed bodies or hashes matching no pending grant with `400`.
+    pub fn handle<B: AsRef<[u8]>>(&self, request: &Request<B>) -> Response<String> {
+        if request.method() != Method::POST {
+            return response(StatusCode::METHOD_NOT_ALLOWED);
+        }
+        let Ok(push) = serde_json::from_slice::<InteractPush>(request.body().as_ref()) else {
+            return response(StatusCode::BAD_REQUEST);
+        };
+
+        let mut expected = self.lock();
+        let Some(index) = expected.iter().position(|e| {
+            // `InteractFinish` sends no `hash_method`, so GNAP's default applies
+            push.verify(
+                &e.client_nonce,
+                &e.finish_nonce,
+                &e.grant_endpoint,
+                DigestAlgorithm::Sha256,
+            )
+            .is_ok()
+        }) else {
+            return response(StatusCode::BAD_REQUEST);
+        };
+        // A grant that stopped waiting is still removed, so its push is not replayed
+        let _ = expected.swap_remove(index).sender.send(push.interact_ref);
+        response(StatusCode::OK)
+    }
+}
+
+fn response(status: StatusCode) -> Response<String> {
+    let mut response = Response::new(String::new());
+    *response.status_mut() = status;
+    response
+}
@@ -18,18 +18,18 @@
 //! - **Error Handling**: Comprehensive error handling for HTTP and signature operations
 use crate::client::AuthenticatedOpenPaymentsClient;
 use crate::client::BaseClient;
-use crate::http_signature::{create_signature_headers, SignOptions};
+use crate::http_signature::{
+    create_content_digest, create_signature_headers, DigestAlgorithm, SignOptions,
+};
+use crate::response_verification::ResponseVerifier;
 use crate::OpClientError;
 use crate::Result;
-use base64::engine::general_purpose;
-use base64::Engine;
 use http::{
     header::{HeaderName, HeaderValue},
     Method as HttpMethod, Request,
 };
 use reqwest::{Client, Method};
 use serde::de::DeserializeOwned;
-use sha2::{Digest, Sha512};
 
 /// Generic HTTP request builder for Open Payments operations.
 ///
@@ -49,6 +49,10 @@ pub(crate) struct HttpRequest<'a, C> {
     url: String,
     /// Optional request body content.
     body: Option<String>,
+    /// Whether repeating the request has the same effect as sending it once.
+    idempotent: bool,
+    /// Additional headers of the request.
+    headers: reqwest::header::HeaderMap,
 }
 
 impl<'a, C> HttpRequest<'a, C> {
@@ -69,6 +73,8 @@ impl<'a, C> HttpRequest<'a, C> {
             method,
             url,
             body: None,
+            idempotent: false,
+            headers: reqwest::header::HeaderMap::new(),
         }
     }
 
@@ -89,6 +95,22 @@ impl<'a, C> HttpRequest<'a, C> {
         self.body = Some(body);
         self
     }
+
+    /// Marks the request as idempotent, allowing it to be retried regardless of its method.
+    pub fn idempotent(mut self) -> Self {
+        self.idempotent = true;
+        self
+    }
+
+    /// Adds a header to the request.
+    pub fn with_header(
+        mut self,
+        name: reqwest::header::HeaderName,
+        value: reqwest::header::HeaderValue,
+    ) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
 }
 
 /// Type alias for authenticated HTTP requests.
@@ -110,9 +132,12 @@ impl AuthenticatedRequest<'_> {
     /// 1. Builds the HTTP request with proper headers
     /// 2. Adds GNAP authorization header if a token is provided
     /// 3. Generates content digest and length headers for request bodies
-    /// 4. Creates HTTP message signatures using the client's signing key
+    /// 4. Creates HTTP message signatures using the client's signer
     /// 5. Executes the request and deserializes the response
     ///
+    /// If the client has a [`RetryPolicy`](crate::client::RetryPolicy), failed attempts
+    /// are repeated from step 1, so every attempt carries a fresh signature.
+    ///
     /// ## Arguments
     ///
     /// * `access_token` - Optional GNAP access token for authorization
@@ -133,7 +158,29 @@ impl AuthenticatedRequest<'_> {
         self,
         access_token: Option<&str>,
     ) -> Result<T> {
-        let mut req = build_request(&self)?;
+        let mut attempt = 1;
+        loop {
+            let error = match self.execute_once(access_token).await {
+                Ok(result) => return Ok(result),
+                Err(error) => error,
+            };
+            let delay = self.client.retry_policy.as_ref().and_then(|policy| {
+                policy.retry_delay(&self.method, self.idempotent, attempt, &error)
+            });
+            let Some(delay) = delay else {
+                return Err(error);
+            };
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Builds, signs and executes a single attempt of the request.
+    async fn execute_once<T: DeserializeOwned + 'static>(
+        &self,
+        access_token: Option<&str>,
+    ) -> Result<T> {
+        let mut req = build_request(self)?;
 
         if let Some(token) = access_token {
             req.headers_mut().insert(
@@ -161,7 +208,7 @@ impl AuthenticatedRequest<'_> {
             );
         }
 
-        let (signature, signature_input) = Self::create_signature_headers(&self, &req)?;
+        let (signature, signature_input) = self.create_signature_headers(&req).await?;
 
         req.headers_mut().insert(
             "Signature",
@@ -176,14 +223,19 @@ impl AuthenticatedRequest<'_> {
             })?,
         );
 
-        execute_request(&self.client.http_client, req).await
+        let verification = match &self.client.response_verifier {
+            Some(verifier) => Some((verifier.as_ref(), to_http_request(&req, self.body.clone())?)),
+            None => None,
+        };
+
+        execute_request(&self.client.http_client, req, verification).await
     }
 
     /// Creates HTTP message signature headers for the request.
     ///
     /// This method converts the reqwest request to an http::Request for signature
     /// creation, then generates the signature and signature-input headers using
-    /// the client's signing key.
+    /// the client's signer.
     ///
     /// ## Arguments
     ///
@@ -193,33 +245,13 @@ impl AuthenticatedRequest<'_> {
     ///
     /// Returns a tuple of `(signature, signature_input)` strings, or an error if
     /// signature creation fails.
-    fn create_signature_headers(&self, req: &reqwest::Request) -> Result<(String, String)> {
-        // Convert to http::Request for signing
-        let mut http_req = Request::new(self.body.clone());
-        *http_req.method_mut() = HttpMethod::from_bytes(req.method().as_str().as_bytes())
-            .map_err(|e| OpClientError::header_parse(format!("Converting HTTP method: {e}")))?;
-        *http_req.uri_mut() = req
-            .url()
-            .as_str()
-            .parse()
-            .map_err(|e| OpClientError::header_parse(format!("Converting URL to URI: {e}")))?;
-
-        for (key, value) in req.headers() {
-            let header_name = HeaderName::from_bytes(key.as_str().as_bytes())
-                .map_err(|e| OpClientError::header_parse(format!("Converting header name: {e}")))?;
-            let header_value = HeaderValue::from_bytes(value.as_bytes()).map_err(|e| {
-                OpClientError::header_parse(format!("Converting header value: {e}"))
-            })?;
-            http_req.headers_mut().insert(header_name, header_value);
-        }
+    async fn create_signature_headers(&self, req: &reqwest::Request) -> Result<(String, String)> {
+        let http_req = to_http_request(req, self.body.clone())?;
 
         // Create and return signature headers
-        let options = SignOptions::new(
-            &http_req,
-            &self.client.signing_key,
-            self.client.config.key_id.clone(),
-        );
+        let options = SignOptions::new(&http_req, self.client.signer.as_ref());
         let headers = create_signature_headers(options)
+            .await
             .map_err(|e| OpClientError::signature(e.to_string()))?;
 
         Ok((headers.signature, headers.signature_input))
@@ -243,10 +275,8 @@ impl AuthenticatedRequest<'_> {
         match body {
             Some(body) => {
                 let content_length = body.len();
-                let mut hasher = Sha512::new();
-                hasher.update(body.as_bytes());
-                let digest = general_purpose::STANDARD.encode(hasher.finalize());
-                let content_digest = format!("sha-512=:{digest}:");
+                let content_digest =
+                    create_content_digest(body.as_bytes(), DigestAlgorithm::Sha512);
 
                 Some((content_length, content_digest))
             }
@@ -276,7 +306,18 @@ impl UnauthenticatedRequest<'_> {
     /// - `details`: Additional error details (if applicable)
     pub async fn build_and_execute<T: DeserializeOwned + 'static>(self) -> Result<T> {
         let req = build_request(&self)?;
-        execute_request(self.client, req).await
+        execute_request(self.client, req, None).await
+    }
+
+    /// Builds and sends an unauthenticated HTTP request, returning the response whatever
+    /// its status.
+    ///
+    /// This is meant for callers handling some statuses themselves, such as the wallet
+    /// address cache revalidating its entries. Other error statuses should be converted
+    /// with [`error_from_response`].
+    pub async fn send(self) -> Result<reqwest::Response> {
+        let req = build_request(&self)?;
+        send_request(self.client, req).await
     }
 }
 
@@ -302,7 +343,8 @@ fn build_request<C: BaseClient>(req: &HttpRequest<C>) -> Result<reqwest::Request
     let mut builder = req
         .http_client()
         .request(req.method.clone(), &req.url)
-        .header("Content-Type", "application/json");
+        .header("Content-Type", "application/json")
+        .headers(req.headers.clone());
 
     if let Some(body) = &req.body {
         builder = builder.body(body.clone());
@@ -313,16 +355,48 @@ fn build_request<C: BaseClient>(req: &HttpRequest<C>) -> Result<reqwest::Request
         .map_err(|e| Box::new(OpClientError::from(e)))
 }
 
+/// Converts a reqwest request into an `http::Request` with the given body.
+fn to_http_request(
+    req: &reqwest::Request,
+    body: Option<String>,
+) -> Result<Request<Option<String>>> {
+    let mut http_req = Request::new(body);
+    *http_req.method_mut() = HttpMethod::from_bytes(req.method().as_str().as_bytes())
+        .map_err(|e| OpClientError::header_parse(format!("Converting HTTP method: {e}")))?;
+    *http_req.uri_mut() = req
+        .url()
+        .as_str()
+        .parse()
+        .map_err(|e| OpClientError::header_parse(format!("Converting URL to URI: {e}")))?;
+    *http_req.headers_mut() = to_http_headers(req.headers())?;
+    Ok(http_req)
+}
+
+/// Converts reqwest headers into an `http::HeaderMap`.
+fn to_http_headers(headers: &reqwest::header::HeaderMap) -> Result<http::HeaderMap> {
+    let mut http_headers = http::HeaderMap::new();
+    for (key, value) in headers {
+        let header_name = HeaderName::from_bytes(key.as_str().as_bytes())
+            .map_err(|e| OpClientError::header_parse(format!("Converting header name: {e}")))?;
+        let header_value = HeaderValue::from_bytes(value.as_bytes())
+            .map_err(|e| OpClientError::header_parse(format!("Converting header value: {e}")))?;
+        http_headers.append(header_name, header_value);
+    }
+    Ok(http_headers)
+}
+
 /// Executes a reqwest request and deserializes the response.
 ///
 /// This function handles the HTTP request execution, status code checking,
 /// and response deserialization. It includes special handling for 204 No Content
-/// responses.
+/// responses. If a response verifier is given, the signature of a successful
+/// response is verified before its body is deserialized.
 ///
 /// ## Arguments
 ///
 /// * `client` - The reqwest client to use for execution
 /// * `req` - The reqwest request to execute
+/// * `verification` - Optional response verifier and the request as it was signed
 ///
 /// ## Returns
 ///
@@ -339,30 +413,61 @@ fn build_request<C: BaseClient>(req: &HttpRequest<C>) -> Result<reqwest::Request
 async fn execute_request<T: DeserializeOwned + 'static>(
     client: &Client,
     req: reqwest::Request,
+    verification: Option<(&ResponseVerifier, Request<Option<String>>)>,
 ) -> Result<T> {
-    let resp = client.execute(req).await.map_err(OpClientError::from)?;
+    let resp = send_request(client, req).await?;
 
     if !resp.status().is_success() {
-        return Err(Box::new(OpClientError::http(
-            "HTTP request failed".to_string(),
-            Some(
-                resp.status()
-                    .canonical_reason()
-                    .unwrap_or("Unknown")
-                    .to_string(),
-            ),
-            Some(resp.status().as_u16()),
-        )));
+        return Err(error_from_response(resp).await);
     }
 
-    if resp.status() == reqwest::StatusCode::NO_CONTENT
-        && std::any::TypeId::of::<T>() == std::any::TypeId::of::<()>()
-    {
-        return Ok(serde_json::from_str::<T>("null")
-            .expect("Deserializing unit type from null should never fail"));
-    }
+    let no_content_unit = resp.status() == reqwest::StatusCode::NO_CONTENT
+        && std::any::TypeId::of::<T>() == std::any::TypeId::of::<()>();
 
-    let result: T = resp.json().await.map_err(OpClientError::from)?;
+    let result: T = match verification {
+        Some((verifier, request)) => {
+            let url = resp.url().clone();
+            let status = http::StatusCode::from_u16(resp.status().as_u16())
+                .map_err(|e| OpClientError::header_parse(format!("Converting status code: {e}")))?;
+            let headers = to_http_headers(resp.headers())?;
+            let body = resp.text().await.map_err(OpClientError::from)?;
+
+            let mut response = http::Response::new(Some(body));
+            *response.status_mut() = status;
+            *response.headers_mut() = headers;
+            verifier.verify(client, &request, &response, &url).await?;
+
+            if no_content_unit {
+                return Ok(serde_json::from_str::<T>("null")
+                    .expect("Deserializing unit type from null should never fail"));
+            }
+            serde_json::from_str(response.body().as_deref().unwrap_or_default())?
+        }
+        None => {
+            if no_content_unit {
+                return Ok(serde_json::from_str::<T>("null")
+                    .expect("Deserializing unit type from null should never fail"));
+            }
+            resp.json().await.map_err(OpClientError::from)?
+        }
+    };
 
     Ok(result)
 }
+
+/// Sends a reqwest request, reporting failures to get a response as errors.
+async fn send_request(client: &Client, req: reqwest::Request) -> Result<reqwest::Response> {
+    client
+        .execute(req)
+        .await
+        .map_err(|e| Box::new(OpClientError::from(e)))
+}
+
+/// Converts a response with an error status into an error.
+pub(crate) async fn error_from_response(resp: reqwest::Response) -> Box<OpClientError> {
+    let status = resp.status();
+    let headers = resp.headers().clone();
+    // The body only adds detail to the error, so a failure to read it is not reported
+    let body = resp.text().await.unwrap_or_default();
+    Box::new(OpClientError::from_response(status, &headers, &body))
+}
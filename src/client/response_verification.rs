@@ -0,0 +1,114 @@
+//! # Response Signature Verification
+//!
+//! This module verifies HTTP message signatures on responses from Open Payments
+//! authorization and resource servers. It is enabled with
+//! [`ClientBuilder::verify_responses`](crate::client::ClientBuilder::verify_responses).
+//!
+//! The public keys are read from the server's JWKS, by default published at
+//! `{origin}/jwks.json`, and cached for five minutes. An unknown key ID triggers a
+//! refetch at most every ten seconds, so key rotation on the server is picked up without
+//! restarting the client.
+//!
+//! A response is accepted only if its signature covers `@status`, covers
+//! `content-digest` when the response has a body, and the digest matches the body.
+use crate::error::{OpClientError, Result};
+use crate::http_signature::{
+    parse_signature_input, validate_signature, ValidationOptions, ValidationPolicy,
+};
+use crate::key_resolver::KeyCache;
+use crate::types::wallet_address::JsonWebKeySet;
+use ed25519_dalek::VerifyingKey;
+use http::{Request, Response};
+use reqwest::Client;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+/// Verifies response signatures against keys from the server's JWKS.
+pub(crate) struct ResponseVerifier {
+    /// JWKS URL used for every response instead of `{origin}/jwks.json`.
+    jwks_url: Option<String>,
+    /// Cached keys by JWKS URL.
+    keys: Mutex<HashMap<String, Arc<KeyCache>>>,
+}
+
+impl ResponseVerifier {
+    pub(crate) fn new(jwks_url: Option<String>) -> Self {
+        Self {
+            jwks_url,
+            keys: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Verifies the signature of `response`, received for `request` from `response_url`.
+    pub(crate) async fn verify(
+        &self,
+        http_client: &Client,
+        request: &Request<Option<String>>,
+        response: &Response<Option<String>>,
+        response_url: &Url,
+    ) -> Result<()> {
+        let signature_input = response
+            .headers()
+            .get("Signature-Input")
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| OpClientError::response_signature("Missing Signature-Input header"))?;
+        let signatures = parse_signature_input(signature_input)
+            .map_err(|e| OpClientError::response_signature(e.to_string()))?;
+        let (label, params) = signatures
+            .first()
+            .ok_or_else(|| OpClientError::response_signature("Missing Signature-Input header"))?;
+        let keyid = params
+            .keyid()
+            .ok_or_else(|| OpClientError::response_signature("Missing keyid parameter"))?;
+
+        let jwks_url = match &self.jwks_url {
+            Some(jwks_url) => jwks_url.clone(),
+            None => response_url.join("/jwks.json")?.to_string(),
+        };
+        let public_key = self.key(http_client, &jwks_url, keyid).await?;
+
+        let policy = ValidationPolicy {
+            required_components: vec!["@status".to_string()],
+            ..ValidationPolicy::default()
+        };
+        let options = ValidationOptions::new(request, response.headers(), &public_key)
+            .with_response(response)
+            .with_label(label)
+            .with_policy(policy);
+        validate_signature(options)
+            .map_err(|e| Box::new(OpClientError::response_signature(e.to_string())))
+    }
+
+    /// Returns the verifying key with the given ID, fetching the JWKS if needed.
+    async fn key(&self, http_client: &Client, jwks_url: &str, keyid: &str) -> Result<VerifyingKey> {
+        let keys = self
+            .keys
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(jwks_url.to_string())
+            .or_insert_with(|| Arc::new(KeyCache::new()))
+            .clone();
+
+        let key = keys
+            .get(keyid, || async {
+                http_client
+                    .get(jwks_url)
+                    .send()
+                    .await
+                    .and_then(|resp| resp.error_for_status())
+                    .map_err(|e| {
+                        OpClientError::response_signature(format!("Failed to fetch JWKS: {e}"))
+                    })?
+                    .json::<JsonWebKeySet>()
+                    .await
+                    .map_err(|e| OpClientError::response_signature(format!("Invalid JWKS: {e}")))
+            })
+            .await?;
+        key.ok_or_else(|| {
+            Box::new(OpClientError::response_signature(format!(
+                "Unknown key ID {keyid:?}"
+            )))
+        })
+    }
+}
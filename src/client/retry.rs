@@ -0,0 +1,91 @@
+//! # Request Retries
+//!
+//! This module defines [`RetryPolicy`], configured with
+//! [`ClientBuilder::retry_policy`](crate::client::ClientBuilder::retry_policy), which
+//! retries authenticated requests that failed with a retryable error (see
+//! [`OpClientError::is_retryable`]).
+//!
+//! Delays grow exponentially with full jitter, and a `Retry-After` header sent by the
+//! server takes precedence over the computed delay. Every attempt is signed again, so
+//! the signature carries a fresh `created` timestamp and a recomputed `Content-Digest`.
+//!
+//! Only safe methods and requests known to be idempotent, such as completing an incoming
+//! payment, are retried by default. Retrying other requests, such as creating an
+//! outgoing payment, may perform the operation twice if the first attempt reached the
+//! server, so it must be enabled with [`RetryPolicy::retry_unsafe_methods`].
+//!
+//! ## Example
+//!
+//! ```rust
+//! use open_payments::client::RetryPolicy;
+//! use std::time::Duration;
+//!
+//! let policy = RetryPolicy {
+//!     max_attempts: 5,
+//!     initial_backoff: Duration::from_millis(200),
+//!     ..RetryPolicy::default()
+//! };
+//! ```
+use crate::OpClientError;
+use rand::Rng;
+use reqwest::Method;
+use std::time::Duration;
+
+/// Policy for retrying failed requests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first one.
+    pub max_attempts: u32,
+    /// Upper bound of the delay before the first retry.
+    pub initial_backoff: Duration,
+    /// Upper bound of any delay.
+    ///
+    /// A request whose `Retry-After` exceeds this delay is not retried.
+    pub max_backoff: Duration,
+    /// Whether requests with methods other than `GET`, `HEAD` and `OPTIONS` that are not
+    /// known to be idempotent are retried.
+    pub retry_unsafe_methods: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            retry_unsafe_methods: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns the delay before retrying a request that failed with `error`, or `None` if
+    /// it must not be retried.
+    ///
+    /// `attempt` is the number of attempts made so far, starting at 1.
+    pub(crate) fn retry_delay(
+        &self,
+        method: &Method,
+        idempotent: bool,
+        attempt: u32,
+        error: &OpClientError,
+    ) -> Option<Duration> {
+        let safe = matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS);
+        if attempt >= self.max_attempts
+            || !error.is_retryable()
+            || !(safe || idempotent || self.retry_unsafe_methods)
+        {
+            return None;
+        }
+
+        if let Some(retry_after) = error.retry_after() {
+            return (retry_after <= self.max_backoff).then_some(retry_after);
+        }
+
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(self.max_backoff);
+        Some(rand::thread_rng().gen_range(Duration::ZERO..=backoff))
+    }
+}
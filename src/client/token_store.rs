@@ -0,0 +1,411 @@
+//! # Token Storage
+//!
+//! This module defines [`TokenStore`], which persists access tokens, their `manage` URLs
+//! and the continuation information of their grants, so they survive restarts of the
+//! process holding them.
+//!
+//! A store is configured with
+//! [`ClientBuilder::token_store`](crate::client::ClientBuilder::token_store). Tokens
+//! issued by grant requests and continuations, and tokens rotated through the token
+//! resource or a [`GrantManager`](crate::client::GrantManager), are then written to the
+//! store under a [`TokenKey`] made of the client's wallet address and the granted access.
+//! Tokens issued without an `access` list cannot be keyed and are not stored. A token
+//! that cannot be written fails the call that obtained it with an
+//! [`ErrorKind::TokenStore`](crate::ErrorKind::TokenStore) error, from which the token is
+//! recovered with [`OpClientError::unsaved_token`].
+//!
+//! Two implementations are provided:
+//!
+//! - [`InMemoryTokenStore`] - keeps tokens for the lifetime of the process
+//! - [`EncryptedFileTokenStore`] - keeps tokens in a file encrypted with
+//!   XChaCha20-Poly1305 under a key supplied by the caller
+//!
+//! ## Example
+//!
+//! ```rust,no_run
+//! use open_payments::client::{AuthenticatedClient, EncryptedFileTokenStore};
+//! use std::sync::Arc;
+//!
+//! # fn example(signing_key: ed25519_dalek::SigningKey, key: [u8; 32]) -> open_payments::client::Result<()> {
+//! let client = AuthenticatedClient::builder()
+//!     .key_id("my-key-id")
+//!     .wallet_address_url("https://rafiki.money/alice")
+//!     .signing_key(signing_key)
+//!     .token_store(Arc::new(EncryptedFileTokenStore::new("tokens.bin", key)))
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::client::AuthenticatedOpenPaymentsClient;
+use crate::grant_manager::ManagedToken;
+use crate::types::{AccessItem, AccessToken};
+use crate::{OpClientError, Result};
+use async_trait::async_trait;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Key under which a token is stored.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TokenKey {
+    /// Wallet address of the client the token was issued to.
+    pub wallet_address: String,
+    /// Canonical form of the access granted by the token.
+    pub scope: String,
+}
+
+impl TokenKey {
+    /// Creates the key for a token granting `access` to the client identified by
+    /// `wallet_address`.
+    ///
+    /// The scope does not depend on the order of the access items.
+    pub fn new(wallet_address: impl Into<String>, access: &[AccessItem]) -> Self {
+        let mut items: Vec<String> = access
+            .iter()
+            .map(|item| serde_json::to_string(item).unwrap_or_default())
+            .collect();
+        items.sort();
+        Self {
+            wallet_address: wallet_address.into(),
+            scope: items.join(" "),
+        }
+    }
+}
+
+/// Persistent storage for access tokens.
+///
+/// Each `put` must replace the stored token in a single step, so a crash never leaves
+/// a token that has already been rotated away as the only stored copy.
+#[async_trait]
+pub trait TokenStore: Send + Sync {
+    /// Returns the token stored under `key`.
+    async fn get(&self, key: &TokenKey) -> Result<Option<ManagedToken>>;
+
+    /// Stores `token` under `key`, replacing any previous token.
+    async fn put(&self, key: &TokenKey, token: &ManagedToken) -> Result<()>;
+
+    /// Removes the token stored under `key`.
+    async fn delete(&self, key: &TokenKey) -> Result<()>;
+
+    /// Returns the token whose `manage` URL is `manage`, with the key it is stored under.
+    async fn find_by_manage(&self, manage: &str) -> Result<Option<(TokenKey, ManagedToken)>>;
+}
+
+/// [`TokenStore`] keeping tokens in memory.
+#[derive(Debug, Default)]
+pub struct InMemoryTokenStore {
+    tokens: Mutex<HashMap<TokenKey, ManagedToken>>,
+}
+
+impl InMemoryTokenStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<TokenKey, ManagedToken>> {
+        self.tokens.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl TokenStore for InMemoryTokenStore {
+    async fn get(&self, key: &TokenKey) -> Result<Option<ManagedToken>> {
+        Ok(self.lock().get(key).cloned())
+    }
+
+    async fn put(&self, key: &TokenKey, token: &ManagedToken) -> Result<()> {
+        self.lock().insert(key.clone(), token.clone());
+        Ok(())
+    }
+
+    async fn delete(&self, key: &TokenKey) -> Result<()> {
+        self.lock().remove(key);
+        Ok(())
+    }
+
+    async fn find_by_manage(&self, manage: &str) -> Result<Option<(TokenKey, ManagedToken)>> {
+        Ok(self
+            .lock()
+            .iter()
+            .find(|(_, token)| token.access_token.manage == manage)
+            .map(|(key, token)| (key.clone(), token.clone())))
+    }
+}
+
+/// Length of the XChaCha20-Poly1305 nonce stored at the start of the file.
+const NONCE_LEN: usize = 24;
+
+/// [`TokenStore`] keeping tokens in a file encrypted at rest.
+///
+/// All tokens are kept in one file holding a random nonce followed by the
+/// XChaCha20-Poly1305 encryption of the tokens. Every change writes and syncs a temporary
+/// file next to it, readable only by its owner on Unix, which then replaces the file, so
+/// readers never see a partial write. File access runs on tokio's blocking thread pool.
+///
+/// The store is safe to use from several tasks, but not from several processes at once.
+pub struct EncryptedFileTokenStore {
+    file: Arc<TokenFile>,
+}
+
+struct TokenFile {
+    path: PathBuf,
+    cipher: XChaCha20Poly1305,
+    lock: Mutex<()>,
+}
+
+impl EncryptedFileTokenStore {
+    /// Creates a store at `path`, encrypted with the 256-bit `key`.
+    ///
+    /// The file is created on the first `put`.
+    pub fn new(path: impl Into<PathBuf>, key: [u8; 32]) -> Self {
+        Self {
+            file: Arc::new(TokenFile {
+                path: path.into(),
+                cipher: XChaCha20Poly1305::new(&key.into()),
+                lock: Mutex::new(()),
+            }),
+        }
+    }
+
+    /// Runs `f` on the blocking thread pool while holding the file lock.
+    async fn with_file<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&TokenFile) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        let file = self.file.clone();
+        tokio::task::spawn_blocking(move || {
+            let _guard = file.lock.lock().unwrap_or_else(|e| e.into_inner());
+            f(&file)
+        })
+        .await
+        .map_err(|e| OpClientError::other(format!("Token store task failed: {e}")))?
+    }
+}
+
+impl TokenFile {
+    fn read(&self) -> Result<Vec<(TokenKey, ManagedToken)>> {
+        let data = match std::fs::read(&self.path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        if data.len() < NONCE_LEN {
+            return Err(Box::new(OpClientError::other(
+                "Token store file is truncated",
+            )));
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| {
+                OpClientError::other("Failed to decrypt token store: wrong key or corrupted file")
+            })?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    fn write(&self, tokens: &[(TokenKey, ManagedToken)]) -> Result<()> {
+        let plaintext = serde_json::to_vec(tokens)?;
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let ciphertext = self
+            .cipher
+            .encrypt(XNonce::from_slice(&nonce), plaintext.as_slice())
+            .map_err(|_| OpClientError::other("Failed to encrypt token store"))?;
+
+        let tmp_path = tmp_path(&self.path);
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut tmp = options.open(&tmp_path)?;
+        tmp.write_all(&nonce)?;
+        tmp.write_all(&ciphertext)?;
+        tmp.sync_all()?;
+        drop(tmp);
+        std::fs::rename(&tmp_path, &self.path)?;
+        // Make the rename itself durable
+        #[cfg(unix)]
+        if let Some(dir) = self.path.parent() {
+            let dir = if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            };
+            std::fs::File::open(dir)?.sync_all()?;
+        }
+        Ok(())
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Vec<(TokenKey, ManagedToken)>)) -> Result<()> {
+        let mut tokens = self.read()?;
+        f(&mut tokens);
+        self.write(&tokens)
+    }
+}
+
+fn tmp_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".tmp");
+    path.with_file_name(file_name)
+}
+
+#[async_trait]
+impl TokenStore for EncryptedFileTokenStore {
+    async fn get(&self, key: &TokenKey) -> Result<Option<ManagedToken>> {
+        let key = key.clone();
+        self.with_file(move |file| {
+            Ok(file
+                .read()?
+                .into_iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, token)| token))
+        })
+        .await
+    }
+
+    async fn put(&self, key: &TokenKey, token: &ManagedToken) -> Result<()> {
+        let (key, token) = (key.clone(), token.clone());
+        self.with_file(move |file| {
+            file.update(|tokens| {
+                tokens.retain(|(k, _)| *k != key);
+                tokens.push((key, token));
+            })
+        })
+        .await
+    }
+
+    async fn delete(&self, key: &TokenKey) -> Result<()> {
+        let key = key.clone();
+        self.with_file(move |file| file.update(|tokens| tokens.retain(|(k, _)| *k != key)))
+            .await
+    }
+
+    async fn find_by_manage(&self, manage: &str) -> Result<Option<(TokenKey, ManagedToken)>> {
+        let manage = manage.to_string();
+        self.with_file(move |file| {
+            Ok(file
+                .read()?
+                .into_iter()
+                .find(|(_, token)| token.access_token.manage == manage))
+        })
+        .await
+    }
+}
+
+/// Returns the key of `token` in the client's token store, if it has one.
+pub(crate) fn token_key(
+    client: &AuthenticatedOpenPaymentsClient,
+    token: &ManagedToken,
+) -> Option<TokenKey> {
+    client.token_store.as_ref()?;
+    let access = token.access_token.access.as_deref()?;
+    Some(TokenKey::new(&client.config.wallet_address_url, access))
+}
+
+impl OpClientError {
+    /// Returns the access token that was issued or rotated but could not be written to
+    /// the token store, for errors of kind [`ErrorKind::TokenStore`](crate::ErrorKind::TokenStore).
+    ///
+    /// The server has issued the token, and may have revoked the token it replaces, so
+    /// the caller should keep using it and store it again.
+    pub fn unsaved_token(&self) -> Option<ManagedToken> {
+        let token = self.details.as_ref()?.get("token")?;
+        serde_json::from_value(token.clone()).ok()
+    }
+}
+
+/// Returns a token store error carrying `token`, which was not stored.
+fn store_error(
+    action: &str,
+    error: Box<OpClientError>,
+    token: &ManagedToken,
+) -> Box<OpClientError> {
+    let error = OpClientError::token_store(format!("Failed to {action}: {error}"));
+    Box::new(match serde_json::to_value(token) {
+        Ok(token) => error.with_detail("token", token),
+        Err(_) => error,
+    })
+}
+
+/// Writes `token` to the client's token store, if one is configured.
+///
+/// Tokens without an `access` list cannot be keyed and are not stored. Failures are
+/// returned as [`ErrorKind::TokenStore`](crate::ErrorKind::TokenStore) errors carrying
+/// the token, since it was issued successfully and is lost otherwise.
+pub(crate) async fn save_token(
+    client: &AuthenticatedOpenPaymentsClient,
+    token: &ManagedToken,
+) -> Result<()> {
+    let (Some(store), Some(key)) = (&client.token_store, token_key(client, token)) else {
+        return Ok(());
+    };
+    store
+        .put(&key, token)
+        .await
+        .map_err(|e| store_error("store access token", e, token))
+}
+
+/// Writes a token returned by rotating the token managed at `manage` to the client's
+/// token store.
+///
+/// The rotated token replaces the stored token and keeps its continuation information,
+/// and its granted access if the server does not repeat it. Failures are returned like
+/// those of [`save_token`].
+pub(crate) async fn save_rotated_token(
+    client: &AuthenticatedOpenPaymentsClient,
+    manage: &str,
+    access_token: &AccessToken,
+) -> Result<()> {
+    let Some(store) = &client.token_store else {
+        return Ok(());
+    };
+    let mut token = ManagedToken::new(access_token.clone(), None);
+    let previous = store
+        .find_by_manage(manage)
+        .await
+        .map_err(|e| store_error("read access token from store", e, &token))?;
+
+    if let Some((_, stored)) = &previous {
+        token.continue_ = stored.continue_.clone();
+        if token.access_token.access.is_none() {
+            token.access_token.access = stored.access_token.access.clone();
+        }
+    }
+    let Some(key) = token_key(client, &token) else {
+        return Ok(());
+    };
+    store
+        .put(&key, &token)
+        .await
+        .map_err(|e| store_error("store access token", e, &token))?;
+    // The previous token was stored under another key if the granted access changed
+    if let Some((previous_key, _)) = previous.filter(|(k, _)| *k != key) {
+        store
+            .delete(&previous_key)
+            .await
+            .map_err(|e| store_error("remove replaced access token from store", e, &token))?;
+    }
+    Ok(())
+}
+
+/// Removes `token` from the client's token store, if one is configured.
+pub(crate) async fn remove_token(
+    client: &AuthenticatedOpenPaymentsClient,
+    token: &ManagedToken,
+) -> Result<()> {
+    let (Some(store), Some(key)) = (&client.token_store, token_key(client, token)) else {
+        return Ok(());
+    };
+    store.delete(&key).await.map_err(|e| {
+        Box::new(OpClientError::token_store(format!(
+            "Failed to remove access token from store: {e}"
+        )))
+    })
+}
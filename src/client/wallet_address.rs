@@ -1,19 +1,48 @@
+use crate::cache::{jwks_url, wallet_address_url};
+use crate::client::BaseClient;
 use crate::request::UnauthenticatedRequest;
-use crate::types::{JsonWebKeySet, WalletAddress};
+use crate::types::{DidDocument, JsonWebKeySet, WalletAddress, WalletAddressUrl};
 use crate::Result;
 use reqwest::{Client, Method};
 
-pub(crate) async fn get_wallet_address(
-    client: &Client,
-    wallet_address_url: &str,
+pub(crate) async fn get_wallet_address<C: BaseClient>(
+    client: &C,
+    wallet_address_url: &WalletAddressUrl,
 ) -> Result<WalletAddress> {
-    UnauthenticatedRequest::new(client, Method::GET, wallet_address_url.into())
+    if let Some(cache) = client.wallet_address_cache() {
+        return cache
+            .get(client.http_client(), wallet_address_url.as_str())
+            .await;
+    }
+
+    UnauthenticatedRequest::new(
+        client.http_client(),
+        Method::GET,
+        wallet_address_url.to_string(),
+    )
+    .build_and_execute()
+    .await
+}
+
+pub(crate) async fn get_keys<C: BaseClient>(
+    client: &C,
+    wallet: &WalletAddress,
+) -> Result<JsonWebKeySet> {
+    let url = jwks_url(&wallet_address_url(wallet)?);
+    if let Some(cache) = client.wallet_address_cache() {
+        return cache.get(client.http_client(), &url).await;
+    }
+
+    UnauthenticatedRequest::new(client.http_client(), Method::GET, url)
         .build_and_execute()
         .await
 }
 
-pub(crate) async fn get_keys(client: &Client, wallet: &WalletAddress) -> Result<JsonWebKeySet> {
-    let url = format!("{}/jwks.json", wallet.id.trim_end_matches('/'));
+pub(crate) async fn get_did_document(
+    client: &Client,
+    wallet: &WalletAddress,
+) -> Result<DidDocument> {
+    let url = format!("{}/did.json", wallet.id.trim_end_matches('/'));
 
     UnauthenticatedRequest::new(client, Method::GET, url)
         .build_and_execute()
@@ -0,0 +1,592 @@
+//! # Open Payments Client Error Types
+//!
+//! This module defines the error types used throughout the Open Payments client.
+//! All client operations return a [`Result<T, OpClientError>`] which provides
+//! detailed error information for different failure scenarios.
+//!
+//! ## Error Structure
+//!
+//! - `description` - Human-readable error message
+//! - `validationErrors` - Optional list of validation error messages
+//! - `status` - HTTP status code (only for HTTP errors)
+//! - `code` - Error code (only for HTTP errors)
+//! - `gnap_error` - GNAP error code returned by the server (only for HTTP errors)
+//! - `server_description` - Error description returned by the server (only for HTTP errors)
+//! - `details` - Additional error details as key-value pairs
+//!
+//! [`OpClientError::kind`] classifies the error as an [`ErrorKind`], and
+//! [`OpClientError::is_retryable`] and [`OpClientError::retry_after`] tell whether and
+//! when a failed request may be retried.
+//!
+//! For HTTP errors, the response body is parsed as a GNAP error
+//! (`{"error": {"code": "...", "description": "..."}}`) or an OpenAPI validation error,
+//! and the raw body and response headers are kept in `details` under `body` and `headers`.
+//!
+//! ## Example Usage
+//!
+//! ```rust
+//! use open_payments::client::{OpClientError, Result};
+//!
+//! fn handle_client_error(result: Result<()>) {
+//!     match result {
+//!         Ok(()) => println!("Operation successful"),
+//!         Err(e) => {
+//!             eprintln!("Error: {}", e.description);
+//!             if e.is_retryable() {
+//!                 eprintln!("Retry after: {:?}", e.retry_after());
+//!             }
+//!             if let Some(status) = e.status {
+//!                 eprintln!("Status: {}", status);
+//!             }
+//!             if let Some(code) = e.code {
+//!                 eprintln!("Code: {}", code);
+//!             }
+//!             if let Some(validation_errors) = e.validation_errors {
+//!                 for error in validation_errors {
+//!                     eprintln!("Validation error: {}", error);
+//!                 }
+//!             }
+//!         }
+//!     }
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Error codes defined by GNAP ([RFC 9635 Section 3.6](https://www.rfc-editor.org/rfc/rfc9635#section-3.6)).
+///
+/// Codes not defined by the specification are kept as [`GnapErrorCode::Other`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum GnapErrorCode {
+    InvalidRequest,
+    InvalidClient,
+    InvalidInteraction,
+    InvalidFlag,
+    InvalidRotation,
+    KeyRotationNotSupported,
+    InvalidContinuation,
+    UserDenied,
+    RequestDenied,
+    UnknownUser,
+    UnknownInteraction,
+    TooFast,
+    TooManyAttempts,
+    Other(String),
+}
+
+impl GnapErrorCode {
+    /// Returns the error code as sent on the wire.
+    pub fn as_str(&self) -> &str {
+        match self {
+            GnapErrorCode::InvalidRequest => "invalid_request",
+            GnapErrorCode::InvalidClient => "invalid_client",
+            GnapErrorCode::InvalidInteraction => "invalid_interaction",
+            GnapErrorCode::InvalidFlag => "invalid_flag",
+            GnapErrorCode::InvalidRotation => "invalid_rotation",
+            GnapErrorCode::KeyRotationNotSupported => "key_rotation_not_supported",
+            GnapErrorCode::InvalidContinuation => "invalid_continuation",
+            GnapErrorCode::UserDenied => "user_denied",
+            GnapErrorCode::RequestDenied => "request_denied",
+            GnapErrorCode::UnknownUser => "unknown_user",
+            GnapErrorCode::UnknownInteraction => "unknown_interaction",
+            GnapErrorCode::TooFast => "too_fast",
+            GnapErrorCode::TooManyAttempts => "too_many_attempts",
+            GnapErrorCode::Other(code) => code,
+        }
+    }
+}
+
+impl From<String> for GnapErrorCode {
+    fn from(code: String) -> Self {
+        match code.as_str() {
+            "invalid_request" => GnapErrorCode::InvalidRequest,
+            "invalid_client" => GnapErrorCode::InvalidClient,
+            "invalid_interaction" => GnapErrorCode::InvalidInteraction,
+            "invalid_flag" => GnapErrorCode::InvalidFlag,
+            "invalid_rotation" => GnapErrorCode::InvalidRotation,
+            "key_rotation_not_supported" => GnapErrorCode::KeyRotationNotSupported,
+            "invalid_continuation" => GnapErrorCode::InvalidContinuation,
+            "user_denied" => GnapErrorCode::UserDenied,
+            "request_denied" => GnapErrorCode::RequestDenied,
+            "unknown_user" => GnapErrorCode::UnknownUser,
+            "unknown_interaction" => GnapErrorCode::UnknownInteraction,
+            "too_fast" => GnapErrorCode::TooFast,
+            "too_many_attempts" => GnapErrorCode::TooManyAttempts,
+            _ => GnapErrorCode::Other(code),
+        }
+    }
+}
+
+impl From<GnapErrorCode> for String {
+    fn from(code: GnapErrorCode) -> Self {
+        code.as_str().to_string()
+    }
+}
+
+impl fmt::Display for GnapErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Classification of an [`OpClientError`].
+///
+/// New kinds may be added, so matches must have a wildcard arm.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The request could not be sent or the response could not be received.
+    Transport,
+    /// The request timed out.
+    Timeout,
+    /// The server responded with an error status.
+    Http { status: u16 },
+    /// The server responded with a GNAP error.
+    Gnap { code: GnapErrorCode },
+    /// A request or response body could not be serialized or deserialized.
+    Deserialize,
+    /// A request could not be signed, or an interaction hash could not be verified.
+    Signature,
+    /// The signature of a response could not be verified.
+    ResponseSignature,
+    /// The signing key could not be loaded, parsed or saved.
+    KeyLoad,
+    /// A URL could not be parsed.
+    Url,
+    /// An access token was issued but could not be written to the token store.
+    TokenStore,
+    /// A local I/O operation failed, such as reading a file or binding a listener.
+    Io,
+    /// The input or the server's response failed validation.
+    Validation,
+    /// Any other error.
+    Other,
+}
+
+/// Error type for Open Payments client operations.
+///
+/// ## Fields
+///
+/// - `description` - Human-readable error message
+/// - `validation_errors` - Optional list of validation error messages
+/// - `status` - HTTP status code (only relevant for HTTP errors)
+/// - `code` - Error code (only relevant for HTTP errors)
+/// - `gnap_error` - GNAP error code returned by the server (only relevant for HTTP errors)
+/// - `server_description` - Error description returned by the server (only relevant for HTTP errors)
+/// - `details` - Additional error details as key-value pairs
+///
+/// The [`ErrorKind`] is returned by [`OpClientError::kind`].
+#[derive(Debug, Error)]
+pub struct OpClientError {
+    /// Human-readable error description.
+    pub description: String,
+
+    /// Optional list of validation error messages.
+    pub validation_errors: Option<Vec<String>>,
+
+    /// HTTP status (only relevant for HTTP errors).
+    pub status: Option<String>,
+
+    /// Error code (only relevant for HTTP errors).
+    pub code: Option<u16>,
+
+    /// GNAP error code returned by the server (only relevant for HTTP errors).
+    pub gnap_error: Option<GnapErrorCode>,
+
+    /// Error description returned by the server (only relevant for HTTP errors).
+    pub server_description: Option<String>,
+
+    /// Additional error details as key-value pairs.
+    pub details: Option<HashMap<String, serde_json::Value>>,
+
+    /// Classification of the error.
+    kind: ErrorKind,
+}
+
+impl OpClientError {
+    /// Creates a new HTTP error with status code and optional error code.
+    ///
+    /// Without a status code no response was received, so the error is classified as
+    /// [`ErrorKind::Transport`].
+    pub fn http(description: impl Into<String>, status: Option<String>, code: Option<u16>) -> Self {
+        Self {
+            description: description.into(),
+            validation_errors: None,
+            status,
+            code,
+            gnap_error: None,
+            server_description: None,
+            details: None,
+            kind: code.map_or(ErrorKind::Transport, |status| ErrorKind::Http { status }),
+        }
+    }
+
+    /// Creates a new validation error with a list of validation messages.
+    pub fn validation(description: impl Into<String>, validation_errors: Vec<String>) -> Self {
+        Self {
+            description: description.into(),
+            validation_errors: Some(validation_errors),
+            status: None,
+            code: None,
+            gnap_error: None,
+            server_description: None,
+            details: None,
+            kind: ErrorKind::Validation,
+        }
+    }
+
+    /// Creates a new general error without HTTP-specific fields.
+    pub fn other(description: impl Into<String>) -> Self {
+        Self {
+            description: description.into(),
+            validation_errors: None,
+            status: None,
+            code: None,
+            gnap_error: None,
+            server_description: None,
+            details: None,
+            kind: ErrorKind::Other,
+        }
+    }
+
+    /// Creates an HTTP error from a failed response.
+    ///
+    /// GNAP error bodies provide [`OpClientError::gnap_error`] and
+    /// [`OpClientError::server_description`], and OpenAPI validation errors are collected
+    /// in [`OpClientError::validation_errors`]. The raw body and the response headers are
+    /// kept in `details` under `body` and `headers`.
+    pub fn from_response(
+        status: reqwest::StatusCode,
+        headers: &reqwest::header::HeaderMap,
+        body: &str,
+    ) -> Self {
+        let parsed: Option<Value> = serde_json::from_str(body).ok();
+        let mut gnap_error = None;
+        let mut server_description = None;
+        let mut validation_errors = Vec::new();
+
+        if let Some(parsed) = &parsed {
+            match parsed.get("error") {
+                // GNAP allows the error code on its own
+                Some(Value::String(code)) => gnap_error = Some(GnapErrorCode::from(code.clone())),
+                Some(error @ Value::Object(_)) => {
+                    gnap_error = error
+                        .get("code")
+                        .and_then(Value::as_str)
+                        .map(|code| GnapErrorCode::from(code.to_string()));
+                    server_description = error
+                        .get("description")
+                        .and_then(Value::as_str)
+                        .map(str::to_string);
+                    if let Some(details) = error.get("details") {
+                        collect_validation_errors(details, &mut validation_errors);
+                    }
+                }
+                _ => {}
+            }
+            if server_description.is_none() {
+                server_description = ["error_description", "message", "description"]
+                    .iter()
+                    .find_map(|key| parsed.get(key).and_then(Value::as_str))
+                    .map(str::to_string);
+            }
+            collect_validation_errors(parsed, &mut validation_errors);
+        }
+
+        let header_values: serde_json::Map<String, Value> = headers
+            .iter()
+            .filter_map(|(name, value)| {
+                let value = value.to_str().ok()?;
+                Some((name.as_str().to_string(), Value::String(value.to_string())))
+            })
+            .collect();
+
+        let description = match &server_description {
+            Some(server_description) => format!("HTTP request failed: {server_description}"),
+            None => "HTTP request failed".to_string(),
+        };
+
+        let mut error = Self::http(
+            description,
+            Some(status.canonical_reason().unwrap_or("Unknown").to_string()),
+            Some(status.as_u16()),
+        )
+        .with_detail("body", Value::String(body.to_string()))
+        .with_detail("headers", Value::Object(header_values));
+        if let Some(code) = &gnap_error {
+            error.kind = ErrorKind::Gnap { code: code.clone() };
+        }
+        error.gnap_error = gnap_error;
+        error.server_description = server_description;
+        if !validation_errors.is_empty() {
+            error.validation_errors = Some(validation_errors);
+        }
+        error
+    }
+
+    /// Adds additional details to the error.
+    pub fn with_details(mut self, details: HashMap<String, serde_json::Value>) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    /// Adds a single detail key-value pair to the error.
+    pub fn with_detail(mut self, key: impl Into<String>, value: serde_json::Value) -> Self {
+        if self.details.is_none() {
+            self.details = Some(HashMap::new());
+        }
+        if let Some(ref mut details) = self.details {
+            details.insert(key.into(), value);
+        }
+        self
+    }
+
+    /// Sets the kind of the error.
+    pub fn with_kind(mut self, kind: ErrorKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Returns the kind of the error.
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// Returns `true` if the failed request may succeed when retried.
+    ///
+    /// Transport errors, timeouts, the `408`, `425`, `429`, `500`, `502`, `503` and `504`
+    /// statuses, and the GNAP `too_fast` error are retryable.
+    pub fn is_retryable(&self) -> bool {
+        match &self.kind {
+            ErrorKind::Transport | ErrorKind::Timeout => true,
+            ErrorKind::Http { status } => is_retryable_status(*status),
+            ErrorKind::Gnap { code } => {
+                *code == GnapErrorCode::TooFast || self.code.is_some_and(is_retryable_status)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the delay requested by the server's `Retry-After` header.
+    ///
+    /// Both delay seconds and HTTP dates are supported; a date in the past yields a zero
+    /// delay. Returns `None` if the response had no valid `Retry-After` header.
+    pub fn retry_after(&self) -> Option<Duration> {
+        let value = self
+            .details
+            .as_ref()?
+            .get("headers")?
+            .get("retry-after")?
+            .as_str()?
+            .trim();
+        if let Ok(seconds) = value.parse::<u64>() {
+            return Some(Duration::from_secs(seconds));
+        }
+        let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+        let delay = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+        Some(delay.to_std().unwrap_or(Duration::ZERO))
+    }
+}
+
+fn is_retryable_status(status: u16) -> bool {
+    matches!(status, 408 | 425 | 429 | 500 | 502 | 503 | 504)
+}
+
+/// Collects OpenAPI validation errors from an `errors` array.
+///
+/// Entries are either plain messages or objects with a `message` and the location of
+/// the invalid field in `path`, `instancePath` or `field`.
+fn collect_validation_errors(value: &Value, validation_errors: &mut Vec<String>) {
+    let Some(errors) = value.get("errors").and_then(Value::as_array) else {
+        return;
+    };
+    for error in errors {
+        let message = match error {
+            Value::String(message) => message.clone(),
+            Value::Object(_) => {
+                let message = error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or("invalid value");
+                match ["path", "instancePath", "field"]
+                    .iter()
+                    .find_map(|key| error.get(key).and_then(Value::as_str))
+                {
+                    Some(path) if !path.is_empty() => format!("{path}: {message}"),
+                    _ => message.to_string(),
+                }
+            }
+            other => other.to_string(),
+        };
+        validation_errors.push(message);
+    }
+}
+
+impl std::fmt::Display for OpClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.description)?;
+
+        if let Some(status) = &self.status {
+            write!(f, " (Status: {status})")?;
+        }
+
+        if let Some(code) = &self.code {
+            write!(f, " (Code: {code})")?;
+        }
+
+        if let Some(gnap_error) = &self.gnap_error {
+            write!(f, " (GNAP error: {gnap_error})")?;
+        }
+
+        if let Some(validation_errors) = &self.validation_errors {
+            write!(f, " [Validation errors: {}]", validation_errors.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<reqwest::Error> for OpClientError {
+    fn from(err: reqwest::Error) -> Self {
+        let status = err
+            .status()
+            .map(|s| s.canonical_reason().unwrap_or("Unknown").to_string());
+        let code = err.status().map(|s| s.as_u16());
+        let description = format!("HTTP error: {err}");
+        let kind = if err.is_timeout() {
+            ErrorKind::Timeout
+        } else if let Some(status) = code {
+            ErrorKind::Http { status }
+        } else if err.is_decode() {
+            ErrorKind::Deserialize
+        } else if err.is_builder() {
+            // Invalid URLs are reported as builder errors
+            ErrorKind::Url
+        } else {
+            ErrorKind::Transport
+        };
+
+        Self {
+            description,
+            validation_errors: None,
+            status,
+            code,
+            gnap_error: None,
+            server_description: None,
+            details: None,
+            kind,
+        }
+    }
+}
+
+impl From<serde_json::Error> for OpClientError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::other(format!("JSON serialization/deserialization error: {err}"))
+            .with_kind(ErrorKind::Deserialize)
+    }
+}
+
+impl From<std::io::Error> for OpClientError {
+    fn from(err: std::io::Error) -> Self {
+        Self::other(format!("I/O error: {err}")).with_kind(ErrorKind::Io)
+    }
+}
+
+impl From<base64::DecodeError> for OpClientError {
+    fn from(err: base64::DecodeError) -> Self {
+        Self::other(format!("Base64 decoding error: {err}")).with_kind(ErrorKind::Deserialize)
+    }
+}
+
+impl From<url::ParseError> for OpClientError {
+    fn from(err: url::ParseError) -> Self {
+        Self::other(format!("URL parsing error: {err}")).with_kind(ErrorKind::Url)
+    }
+}
+
+impl OpClientError {
+    pub fn header_parse(description: impl Into<String>) -> Self {
+        Self::other(format!("Header parse error: {}", description.into()))
+            .with_kind(ErrorKind::Validation)
+    }
+
+    pub fn pem(description: impl Into<String>) -> Self {
+        Self::other(format!("Invalid PEM: {}", description.into())).with_kind(ErrorKind::KeyLoad)
+    }
+
+    pub fn pkcs8(description: impl Into<String>) -> Self {
+        Self::other(format!("PKCS8 error: {}", description.into())).with_kind(ErrorKind::KeyLoad)
+    }
+
+    pub fn signature(description: impl Into<String>) -> Self {
+        Self::other(format!("Signature error: {}", description.into()))
+            .with_kind(ErrorKind::Signature)
+    }
+
+    pub fn key_load(description: impl Into<String>) -> Self {
+        Self::other(format!("Key load error: {}", description.into())).with_kind(ErrorKind::KeyLoad)
+    }
+
+    pub fn token_store(description: impl Into<String>) -> Self {
+        Self::other(format!("Token store error: {}", description.into()))
+            .with_kind(ErrorKind::TokenStore)
+    }
+
+    pub fn response_signature(description: impl Into<String>) -> Self {
+        Self::other(format!(
+            "Response signature verification failed: {}",
+            description.into()
+        ))
+        .with_kind(ErrorKind::ResponseSignature)
+    }
+
+    pub fn interaction_hash(description: impl Into<String>) -> Self {
+        Self::other(format!(
+            "Interaction hash verification failed: {}",
+            description.into()
+        ))
+        .with_kind(ErrorKind::Signature)
+    }
+}
+
+impl From<url::ParseError> for Box<OpClientError> {
+    fn from(err: url::ParseError) -> Self {
+        Box::new(OpClientError::from(err))
+    }
+}
+
+impl From<reqwest::Error> for Box<OpClientError> {
+    fn from(err: reqwest::Error) -> Self {
+        Box::new(OpClientError::from(err))
+    }
+}
+
+impl From<serde_json::Error> for Box<OpClientError> {
+    fn from(err: serde_json::Error) -> Self {
+        Box::new(OpClientError::from(err))
+    }
+}
+
+impl From<std::io::Error> for Box<OpClientError> {
+    fn from(err: std::io::Error) -> Self {
+        Box::new(OpClientError::from(err))
+    }
+}
+
+impl From<base64::DecodeError> for Box<OpClientError> {
+    fn from(err: base64::DecodeError) -> Self {
+        Box::new(OpClientError::from(err))
+    }
+}
+
+/// Result type for Open Payments client operations.
+///
+/// This is a type alias for `Result<T, Box<OpClientError>>` that provides a convenient
+/// way to handle client operation results with detailed error information.
+pub type Result<T> = std::result::Result<T, Box<OpClientError>>;
@@ -0,0 +1,509 @@
+//! # Signature Components
+//!
+//! This module resolves the components covered by an HTTP message signature and builds
+//! the signature base, following
+//! [RFC 9421 Section 2](https://www.rfc-editor.org/rfc/rfc9421#section-2).
+//!
+//! Supported components:
+//!
+//! - Derived components: `@method`, `@target-uri`, `@authority`, `@scheme`, `@path`,
+//!   `@query`, `@request-target`, `@status` and `@query-param;name=`
+//! - Header fields, including the `sf`, `key` and `bs` parameters
+//! - The `req` parameter, resolving a component from the request a response belongs to
+//!
+//! A covered component that is not present in the message is an error, so that a
+//! signature never covers an empty value by accident.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use open_payments::http_signature::components::{create_signature_base, HttpMessage};
+//! use open_payments::http_signature::SignatureParams;
+//! use http::{Method, Request};
+//!
+//! let mut request = Request::new(None);
+//! *request.method_mut() = Method::GET;
+//! *request.uri_mut() = "https://example.com/incoming-payments?id=1".parse().unwrap();
+//!
+//! let params = SignatureParams::new(["@method", "@authority", "@path"])
+//!     .with_created(1618884473)
+//!     .with_keyid("test-key");
+//! let base = create_signature_base(&HttpMessage::Request(&request), &params).unwrap();
+//! assert_eq!(
+//!     base,
+//!     "\"@method\": GET\n\
+//!      \"@authority\": example.com\n\
+//!      \"@path\": /incoming-payments\n\
+//!      \"@signature-params\": (\"@method\" \"@authority\" \"@path\");created=1618884473;keyid=\"test-key\""
+//! );
+//! ```
+use crate::http_signature::error::{HttpSignatureError, Result};
+use crate::http_signature::signatures::SignatureParams;
+use crate::http_signature::structured_field::{
+    parse_dictionary, parse_item, parse_list, serialize_dictionary, serialize_item, serialize_list,
+    serialize_list_entry, BareItem, Item,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use http::{HeaderMap, Request, Response, Uri};
+use url::form_urlencoded;
+
+/// Header fields whose values are structured field dictionaries.
+const DICTIONARY_FIELDS: &[&str] = &[
+    "accept-signature",
+    "cdn-cache-control",
+    "content-digest",
+    "priority",
+    "repr-digest",
+    "signature",
+    "signature-input",
+    "want-content-digest",
+    "want-repr-digest",
+];
+
+/// Header fields whose values are structured field lists.
+const LIST_FIELDS: &[&str] = &["accept-ch", "cache-status", "proxy-status"];
+
+/// Header fields whose values are structured field items.
+const ITEM_FIELDS: &[&str] = &["access-control-allow-credentials", "access-control-max-age"];
+
+/// The HTTP message a signature covers.
+#[derive(Debug, Clone, Copy)]
+pub enum HttpMessage<'a> {
+    /// A request.
+    Request(&'a Request<Option<String>>),
+    /// A response, optionally with the request it answers for components with the `req` parameter.
+    Response {
+        response: &'a Response<Option<String>>,
+        request: Option<&'a Request<Option<String>>>,
+    },
+}
+
+impl<'a> HttpMessage<'a> {
+    /// Returns the headers of the message.
+    pub fn headers(&self) -> &'a HeaderMap {
+        match self {
+            HttpMessage::Request(request) => request.headers(),
+            HttpMessage::Response { response, .. } => response.headers(),
+        }
+    }
+
+    /// Returns the body of the message.
+    pub fn body(&self) -> Option<&'a str> {
+        match self {
+            HttpMessage::Request(request) => request.body().as_deref(),
+            HttpMessage::Response { response, .. } => response.body().as_deref(),
+        }
+    }
+
+    fn request(&self) -> Option<&'a Request<Option<String>>> {
+        match self {
+            HttpMessage::Request(request) => Some(request),
+            HttpMessage::Response { .. } => None,
+        }
+    }
+}
+
+/// Builds the signature base for the given message and signature parameters.
+///
+/// Every covered component is resolved with [`component_value`], followed by the
+/// `@signature-params` line.
+pub fn create_signature_base(
+    message: &HttpMessage<'_>,
+    params: &SignatureParams,
+) -> Result<String> {
+    let mut lines = Vec::with_capacity(params.components().len() + 1);
+    for component in params.components() {
+        let value = component_value(message, component)?;
+        lines.push(format!("{}: {value}", serialize_item(component)?));
+    }
+    lines.push(format!("\"@signature-params\": {}", params.serialize()?));
+    Ok(lines.join("\n"))
+}
+
+/// Resolves the value of a single covered component.
+///
+/// # Errors
+///
+/// Returns [`HttpSignatureError::MissingComponent`] if the component is not present
+/// in the message, and [`HttpSignatureError::InvalidComponent`] if the component
+/// identifier is not valid or not supported.
+pub fn component_value(message: &HttpMessage<'_>, component: &Item) -> Result<String> {
+    let identifier = serialize_item(component)?;
+    let invalid = |reason: &str| HttpSignatureError::InvalidComponent {
+        component: identifier.clone(),
+        reason: reason.to_string(),
+    };
+    let missing = || HttpSignatureError::MissingComponent(identifier.clone());
+
+    let name = component
+        .bare_item
+        .as_str()
+        .ok_or_else(|| invalid("component identifiers must be strings"))?;
+    if name.bytes().any(|b| b.is_ascii_uppercase()) {
+        return Err(invalid("component names must be lowercase"));
+    }
+
+    let mut from_request = false;
+    let mut structured = false;
+    let mut key = None;
+    let mut byte_sequence = false;
+    let mut query_param = None;
+    for (param, value) in component.params.iter() {
+        match (param.as_str(), value) {
+            ("req", BareItem::Boolean(true)) => from_request = true,
+            ("sf", BareItem::Boolean(true)) => structured = true,
+            ("bs", BareItem::Boolean(true)) => byte_sequence = true,
+            ("key", BareItem::String(k)) => key = Some(k.as_str()),
+            ("name", BareItem::String(n)) => query_param = Some(n.as_str()),
+            ("req" | "sf" | "bs" | "key" | "name", _) => {
+                return Err(invalid(&format!("invalid value for parameter {param:?}")))
+            }
+            ("tr", _) => return Err(invalid("trailer fields are not supported")),
+            _ => return Err(invalid(&format!("unknown parameter {param:?}"))),
+        }
+    }
+
+    let message = if from_request {
+        match message {
+            HttpMessage::Response {
+                request: Some(request),
+                ..
+            } => HttpMessage::Request(request),
+            HttpMessage::Response { request: None, .. } => return Err(missing()),
+            HttpMessage::Request(_) => {
+                return Err(invalid("the req parameter is only valid on responses"))
+            }
+        }
+    } else {
+        *message
+    };
+
+    if let Some(derived) = name.strip_prefix('@') {
+        if structured || byte_sequence || key.is_some() {
+            return Err(invalid("derived components do not accept field parameters"));
+        }
+        if derived != "query-param" && query_param.is_some() {
+            return Err(invalid("the name parameter is only valid on @query-param"));
+        }
+
+        if derived == "status" {
+            return match message {
+                HttpMessage::Response { response, .. } => {
+                    Ok(response.status().as_str().to_string())
+                }
+                HttpMessage::Request(_) => Err(invalid("@status is only valid on responses")),
+            };
+        }
+        if derived == "signature-params" {
+            return Err(invalid("@signature-params cannot be covered"));
+        }
+
+        let request = message
+            .request()
+            .ok_or_else(|| invalid("request components require the req parameter on responses"))?;
+        let uri = request.uri();
+        return match derived {
+            "method" => Ok(request.method().as_str().to_string()),
+            "target-uri" => Ok(uri.to_string()),
+            "authority" => authority(request).ok_or_else(missing),
+            "scheme" => uri
+                .scheme_str()
+                .map(str::to_ascii_lowercase)
+                .ok_or_else(missing),
+            "path" => Ok(path(uri).to_string()),
+            "query" => Ok(format!("?{}", uri.query().unwrap_or_default())),
+            "request-target" => Ok(match uri.query() {
+                Some(query) => format!("{}?{query}", path(uri)),
+                None => path(uri).to_string(),
+            }),
+            "query-param" => {
+                let name =
+                    query_param.ok_or_else(|| invalid("@query-param requires a name parameter"))?;
+                let mut values = form_urlencoded::parse(uri.query().unwrap_or_default().as_bytes())
+                    .filter(|(n, _)| n == name)
+                    .map(|(_, value)| encode_query_value(&value));
+                match (values.next(), values.next()) {
+                    (Some(value), None) => Ok(value),
+                    (None, _) => Err(missing()),
+                    (Some(_), Some(_)) => Err(invalid("query parameter occurs more than once")),
+                }
+            }
+            _ => Err(invalid("unknown derived component")),
+        };
+    }
+
+    if query_param.is_some() {
+        return Err(invalid("the name parameter is only valid on @query-param"));
+    }
+    if byte_sequence && (structured || key.is_some()) {
+        return Err(invalid("bs cannot be combined with sf or key"));
+    }
+
+    let mut lines = Vec::new();
+    for value in message.headers().get_all(name) {
+        lines.push(value.as_bytes().trim_ascii());
+    }
+    if lines.is_empty() {
+        return Err(missing());
+    }
+
+    if byte_sequence {
+        let encoded: Vec<String> = lines
+            .iter()
+            .map(|line| format!(":{}:", STANDARD.encode(line)))
+            .collect();
+        return Ok(encoded.join(", "));
+    }
+
+    let lines = lines
+        .iter()
+        .map(|line| {
+            std::str::from_utf8(line)
+                .map_err(|_| invalid("field value is not valid UTF-8, use the bs parameter"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let value = lines.join(", ");
+
+    if let Some(key) = key {
+        let dictionary = parse_dictionary(&value)
+            .map_err(|e| invalid(&format!("field is not a dictionary: {e}")))?;
+        let (_, member) = dictionary
+            .iter()
+            .find(|(k, _)| k == key)
+            .ok_or_else(missing)?;
+        return serialize_list_entry(member);
+    }
+
+    if structured {
+        return if DICTIONARY_FIELDS.contains(&name) {
+            serialize_dictionary(&parse_dictionary(&value).map_err(|e| invalid(&e.to_string()))?)
+        } else if LIST_FIELDS.contains(&name) {
+            serialize_list(&parse_list(&value).map_err(|e| invalid(&e.to_string()))?)
+        } else if ITEM_FIELDS.contains(&name) {
+            serialize_item(&parse_item(&value).map_err(|e| invalid(&e.to_string()))?)
+        } else {
+            Err(invalid("field is not a known structured field"))
+        };
+    }
+
+    Ok(value)
+}
+
+/// Returns the authority of the request, falling back to the `Host` header.
+fn authority(request: &Request<Option<String>>) -> Option<String> {
+    let authority = match request.uri().authority() {
+        Some(authority) => authority.as_str().to_string(),
+        None => request
+            .headers()
+            .get(http::header::HOST)?
+            .to_str()
+            .ok()?
+            .to_string(),
+    };
+    let authority = authority.to_ascii_lowercase();
+    // Default ports are omitted, see RFC 9421 Section 2.2.3
+    let default_port = match request.uri().scheme_str() {
+        Some("https") => Some(":443"),
+        Some("http") => Some(":80"),
+        _ => None,
+    };
+    Some(match default_port {
+        Some(port) => authority
+            .strip_suffix(port)
+            .map(str::to_string)
+            .unwrap_or(authority),
+        None => authority,
+    })
+}
+
+fn path(uri: &Uri) -> &str {
+    match uri.path() {
+        "" => "/",
+        path => path,
+    }
+}
+
+/// Re-encodes a decoded query parameter value as required for `@query-param`.
+fn encode_query_value(value: &str) -> String {
+    // The form serializer encodes a literal `+` as `%2B`, so `+` only stands for a space
+    form_urlencoded::byte_serialize(value.as_bytes())
+        .collect::<String>()
+        .replace('+', "%20")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{Method, StatusCode};
+
+    fn component(name: &str) -> Item {
+        Item::new(BareItem::String(name.to_string()))
+    }
+
+    fn with_param(mut item: Item, key: &str, value: BareItem) -> Item {
+        item.params.insert(key, value);
+        item
+    }
+
+    fn test_request() -> Request<Option<String>> {
+        let mut request = Request::new(Some("{}".to_string()));
+        *request.method_mut() = Method::POST;
+        *request.uri_mut() = Uri::from_static(
+            "https://Example.COM:443/path?param=value&foo=bar&baz=batman&qux=&var=this%20is%20a%20big%0Avalue&bar=with+plus+whitespace",
+        );
+        let headers = request.headers_mut();
+        headers.insert("content-type", "application/json".parse().unwrap());
+        headers.append("x-list", " a ".parse().unwrap());
+        headers.append("x-list", "b, c".parse().unwrap());
+        headers.insert(
+            "example-dict",
+            "a=1,    b=2;x=1;y=2,   c=(a   b   c)".parse().unwrap(),
+        );
+        headers.insert(
+            "content-digest",
+            "sha-512=:WZDPaVn/7XgHaAy8pmojAkGWoRx2UFChF41A2svX+TaPm+AbwAgBWnrIiYllu7BNNyealdVLvRwEmTHWXvJwew==:"
+                .parse()
+                .unwrap(),
+        );
+        request
+    }
+
+    #[test]
+    fn test_derived_components() {
+        let request = test_request();
+        let message = HttpMessage::Request(&request);
+        let value = |name: &str| component_value(&message, &component(name)).unwrap();
+
+        assert_eq!(value("@method"), "POST");
+        assert_eq!(value("@authority"), "example.com");
+        assert_eq!(value("@scheme"), "https");
+        assert_eq!(value("@path"), "/path");
+        assert!(value("@query").starts_with("?param=value&foo=bar"));
+        assert!(value("@request-target").starts_with("/path?param=value"));
+    }
+
+    #[test]
+    fn test_query_param() {
+        let request = test_request();
+        let message = HttpMessage::Request(&request);
+        let query_param = |name: &str| {
+            component_value(
+                &message,
+                &with_param(
+                    component("@query-param"),
+                    "name",
+                    BareItem::String(name.to_string()),
+                ),
+            )
+        };
+
+        assert_eq!(query_param("baz").unwrap(), "batman");
+        assert_eq!(query_param("qux").unwrap(), "");
+        assert_eq!(query_param("var").unwrap(), "this%20is%20a%20big%0Avalue");
+        assert_eq!(query_param("bar").unwrap(), "with%20plus%20whitespace");
+        assert!(matches!(
+            query_param("missing"),
+            Err(HttpSignatureError::MissingComponent(_))
+        ));
+    }
+
+    #[test]
+    fn test_header_fields() {
+        let request = test_request();
+        let message = HttpMessage::Request(&request);
+
+        assert_eq!(
+            component_value(&message, &component("x-list")).unwrap(),
+            "a, b, c"
+        );
+        assert_eq!(
+            component_value(
+                &message,
+                &with_param(
+                    component("example-dict"),
+                    "key",
+                    BareItem::String("b".into())
+                )
+            )
+            .unwrap(),
+            "2;x=1;y=2"
+        );
+        assert_eq!(
+            component_value(
+                &message,
+                &with_param(component("x-list"), "bs", BareItem::Boolean(true))
+            )
+            .unwrap(),
+            ":YQ==:, :YiwgYw==:"
+        );
+        assert!(component_value(
+            &message,
+            &with_param(component("content-digest"), "sf", BareItem::Boolean(true))
+        )
+        .unwrap()
+        .starts_with("sha-512=:WZDPaVn/"));
+        assert!(matches!(
+            component_value(
+                &message,
+                &with_param(component("x-list"), "sf", BareItem::Boolean(true))
+            ),
+            Err(HttpSignatureError::InvalidComponent { .. })
+        ));
+    }
+
+    #[test]
+    fn test_missing_and_invalid_components() {
+        let request = test_request();
+        let message = HttpMessage::Request(&request);
+
+        for (item, missing) in [
+            (component("authorization"), true),
+            (
+                with_param(
+                    component("example-dict"),
+                    "key",
+                    BareItem::String("z".into()),
+                ),
+                true,
+            ),
+            (component("@status"), false),
+            (component("@unknown"), false),
+            (component("Content-Type"), false),
+            (
+                with_param(component("@method"), "req", BareItem::Boolean(true)),
+                false,
+            ),
+        ] {
+            match component_value(&message, &item) {
+                Err(HttpSignatureError::MissingComponent(_)) => assert!(missing),
+                Err(HttpSignatureError::InvalidComponent { .. }) => assert!(!missing),
+                other => panic!("unexpected result {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_response_components() {
+        let request = test_request();
+        let mut response = Response::new(None);
+        *response.status_mut() = StatusCode::CREATED;
+
+        let message = HttpMessage::Response {
+            response: &response,
+            request: Some(&request),
+        };
+        assert_eq!(
+            component_value(&message, &component("@status")).unwrap(),
+            "201"
+        );
+        assert_eq!(
+            component_value(
+                &message,
+                &with_param(component("@method"), "req", BareItem::Boolean(true))
+            )
+            .unwrap(),
+            "POST"
+        );
+        assert!(component_value(&message, &component("@method")).is_err());
+    }
+}
@@ -0,0 +1,270 @@
+//! # Content Digests
+//!
+//! This module creates and verifies `Content-Digest` headers as defined by
+//! [RFC 9530](https://www.rfc-editor.org/rfc/rfc9530), and handles
+//! `Want-Content-Digest` preferences. The `sha-256` and `sha-512` algorithms
+//! are supported; Open Payments uses `sha-512`.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use open_payments::http_signature::content_digest::{
+//!     create_content_digest, verify_content_digest, DigestAlgorithm,
+//! };
+//!
+//! let body = br#"{"hello": "world"}"#;
+//! let header = create_content_digest(body, DigestAlgorithm::Sha512);
+//! assert!(header.starts_with("sha-512=:"));
+//! verify_content_digest(body, &header).unwrap();
+//! assert!(verify_content_digest(b"tampered", &header).is_err());
+//! ```
+use crate::http_signature::error::{HttpSignatureError, Result};
+use crate::http_signature::structured_field::{
+    parse_dictionary, serialize_dictionary, BareItem, Item, ListEntry,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use sha2::{Digest, Sha256, Sha512};
+use std::fmt;
+use std::str::FromStr;
+
+/// A digest algorithm supported for `Content-Digest`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DigestAlgorithm {
+    Sha256,
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    /// Returns the algorithm key used in digest fields.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DigestAlgorithm::Sha256 => "sha-256",
+            DigestAlgorithm::Sha512 => "sha-512",
+        }
+    }
+
+    /// Computes the digest of the given content.
+    pub fn digest(&self, content: &[u8]) -> Vec<u8> {
+        match self {
+            DigestAlgorithm::Sha256 => Sha256::digest(content).to_vec(),
+            DigestAlgorithm::Sha512 => Sha512::digest(content).to_vec(),
+        }
+    }
+}
+
+impl fmt::Display for DigestAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for DigestAlgorithm {
+    type Err = HttpSignatureError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "sha-256" => Ok(DigestAlgorithm::Sha256),
+            "sha-512" => Ok(DigestAlgorithm::Sha512),
+            other => Err(HttpSignatureError::UnsupportedDigestAlgorithm(
+                other.to_string(),
+            )),
+        }
+    }
+}
+
+/// Creates a `Content-Digest` header value for the given content.
+pub fn create_content_digest(content: &[u8], algorithm: DigestAlgorithm) -> String {
+    format!(
+        "{}=:{}:",
+        algorithm.as_str(),
+        STANDARD.encode(algorithm.digest(content))
+    )
+}
+
+/// Parses a `Content-Digest` header value into algorithm keys and digests.
+///
+/// Members with unknown algorithms are returned as well, so callers can decide how to
+/// treat them.
+pub fn parse_content_digest(header: &str) -> Result<Vec<(String, Vec<u8>)>> {
+    let malformed = |reason: String| HttpSignatureError::MalformedHeader {
+        header: "Content-Digest".to_string(),
+        reason,
+    };
+
+    parse_dictionary(header)
+        .map_err(|e| malformed(e.to_string()))?
+        .into_iter()
+        .map(|(algorithm, member)| match member {
+            ListEntry::Item(Item {
+                bare_item: BareItem::ByteSequence(digest),
+                ..
+            }) => Ok((algorithm, digest)),
+            _ => Err(malformed(format!(
+                "member {algorithm:?} is not a byte sequence"
+            ))),
+        })
+        .collect()
+}
+
+/// Verifies content against a `Content-Digest` header value.
+///
+/// Every digest with a supported algorithm must match the content, and at least one
+/// supported algorithm must be present. Digests with unknown algorithms are ignored.
+pub fn verify_content_digest(content: &[u8], header: &str) -> Result<()> {
+    let mut verified = false;
+    for (algorithm, expected) in parse_content_digest(header)? {
+        let Ok(algorithm) = algorithm.parse::<DigestAlgorithm>() else {
+            continue;
+        };
+        if !constant_time_eq(&algorithm.digest(content), &expected) {
+            return Err(HttpSignatureError::ContentDigestMismatch(
+                algorithm.to_string(),
+            ));
+        }
+        verified = true;
+    }
+
+    if verified {
+        Ok(())
+    } else {
+        Err(HttpSignatureError::UnsupportedDigestAlgorithm(
+            header.to_string(),
+        ))
+    }
+}
+
+/// Parses a `Want-Content-Digest` header value into algorithm keys and preferences.
+///
+/// Preferences range from 0 (not acceptable) to 10 (most preferred).
+pub fn parse_want_content_digest(header: &str) -> Result<Vec<(String, i64)>> {
+    let malformed = |reason: String| HttpSignatureError::MalformedHeader {
+        header: "Want-Content-Digest".to_string(),
+        reason,
+    };
+
+    parse_dictionary(header)
+        .map_err(|e| malformed(e.to_string()))?
+        .into_iter()
+        .map(|(algorithm, member)| match member {
+            ListEntry::Item(Item {
+                bare_item: BareItem::Integer(preference @ 0..=10),
+                ..
+            }) => Ok((algorithm, preference)),
+            _ => Err(malformed(format!(
+                "preference for {algorithm:?} is not an integer from 0 to 10"
+            ))),
+        })
+        .collect()
+}
+
+/// Selects the most preferred supported algorithm from a `Want-Content-Digest` header value.
+///
+/// Returns `None` if no supported algorithm has a non-zero preference. Ties are broken
+/// in favour of `sha-512`.
+pub fn select_digest_algorithm(want_content_digest: &str) -> Result<Option<DigestAlgorithm>> {
+    let mut selected: Option<(DigestAlgorithm, i64)> = None;
+    for (algorithm, preference) in parse_want_content_digest(want_content_digest)? {
+        let Ok(algorithm) = algorithm.parse::<DigestAlgorithm>() else {
+            continue;
+        };
+        if preference == 0 {
+            continue;
+        }
+        let better = match selected {
+            None => true,
+            Some((current, current_preference)) => {
+                preference > current_preference
+                    || (preference == current_preference
+                        && algorithm == DigestAlgorithm::Sha512
+                        && current != DigestAlgorithm::Sha512)
+            }
+        };
+        if better {
+            selected = Some((algorithm, preference));
+        }
+    }
+    Ok(selected.map(|(algorithm, _)| algorithm))
+}
+
+/// Creates a `Want-Content-Digest` header value from algorithms and preferences.
+pub fn create_want_content_digest(preferences: &[(DigestAlgorithm, i64)]) -> Result<String> {
+    let dictionary: Vec<_> = preferences
+        .iter()
+        .map(|(algorithm, preference)| {
+            (
+                algorithm.as_str().to_string(),
+                ListEntry::Item(Item::new(BareItem::Integer((*preference).clamp(0, 10)))),
+            )
+        })
+        .collect();
+    serialize_dictionary(&dictionary)
+}
+
+/// Compares two byte strings in time independent of where they differ.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Examples from RFC 9530 Appendix B
+    const BODY: &[u8] = b"{\"hello\": \"world\"}\n";
+    const SHA_256: &str = "sha-256=:RK/0qy18MlBSVnWgjwz6lZEWjP/lF5HF9bvEF8FabDg=:";
+    const SHA_512: &str = "sha-512=:YMAam51Jz/jOATT6/zvHrLVgOYTGFy1d6GJiOHTohq4yP+pgk4vf2aCsyRZOtw8MjkM7iw7yZ/WkppmM44T3qg==:";
+
+    #[test]
+    fn test_create_content_digest() {
+        assert_eq!(
+            create_content_digest(BODY, DigestAlgorithm::Sha256),
+            SHA_256
+        );
+        assert_eq!(
+            create_content_digest(BODY, DigestAlgorithm::Sha512),
+            SHA_512
+        );
+    }
+
+    #[test]
+    fn test_verify_content_digest() {
+        verify_content_digest(BODY, SHA_256).unwrap();
+        verify_content_digest(BODY, &format!("{SHA_256}, {SHA_512}")).unwrap();
+        verify_content_digest(BODY, &format!("md5=:AAAA:, {SHA_512}")).unwrap();
+
+        assert!(matches!(
+            verify_content_digest(b"other", SHA_512),
+            Err(HttpSignatureError::ContentDigestMismatch(alg)) if alg == "sha-512"
+        ));
+        assert!(matches!(
+            verify_content_digest(BODY, "md5=:AAAA:"),
+            Err(HttpSignatureError::UnsupportedDigestAlgorithm(_))
+        ));
+        assert!(matches!(
+            verify_content_digest(BODY, "sha-512=abc"),
+            Err(HttpSignatureError::MalformedHeader { .. })
+        ));
+    }
+
+    #[test]
+    fn test_want_content_digest_negotiation() {
+        assert_eq!(
+            select_digest_algorithm("sha-256=1, sha-512=3").unwrap(),
+            Some(DigestAlgorithm::Sha512)
+        );
+        assert_eq!(
+            select_digest_algorithm("sha-512=0, sha-256=1, unixsum=10").unwrap(),
+            Some(DigestAlgorithm::Sha256)
+        );
+        assert_eq!(select_digest_algorithm("sha-512=0").unwrap(), None);
+        assert!(select_digest_algorithm("sha-512=11").is_err());
+        assert_eq!(
+            create_want_content_digest(&[
+                (DigestAlgorithm::Sha512, 10),
+                (DigestAlgorithm::Sha256, 3)
+            ])
+            .unwrap(),
+            "sha-512=10, sha-256=3"
+        );
+    }
+}
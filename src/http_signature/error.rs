@@ -49,6 +49,18 @@ use thiserror::Error;
 /// - `Jwk` - JSON Web Key format errors
 /// - `Signature` - Signature creation and verification errors
 /// - `Validation` - Signature validation and verification errors
+/// - `StructuredField` - Structured field parsing and serialization errors
+/// - `MalformedHeader` - Signature headers that are not valid structured fields
+/// - `SignatureNotFound` - No signature with the requested label
+/// - `MissingParameter` - Required signature parameters that are absent
+/// - `InvalidParameter` - Signature parameters with an invalid type or value
+/// - `MissingComponent` - Covered components that are absent from the message
+/// - `InvalidComponent` - Component identifiers that are invalid or unsupported
+/// - `UncoveredComponent` - Components the validation policy requires but the signature does not cover
+/// - `ReplayedNonce` - Nonces that were already used, or that a full nonce store could not record
+/// - `UnknownKey` - Key IDs that no key can be resolved for
+/// - `ContentDigestMismatch` - Content that does not match its `Content-Digest`
+/// - `UnsupportedDigestAlgorithm` - Digests using only unsupported algorithms
 /// - `Other` - Miscellaneous errors
 #[derive(Debug, Error)]
 pub enum HttpSignatureError {
@@ -120,6 +132,90 @@ pub enum HttpSignatureError {
     #[error("Validation error: {0}")]
     Validation(String),
 
+    /// Structured field parsing and serialization errors.
+    ///
+    /// Occurs when a value does not follow the RFC 8941 structured field syntax,
+    /// or when a value cannot be represented as a structured field.
+    #[error("Structured field error: {0}")]
+    StructuredField(String),
+
+    /// Signature headers that cannot be parsed.
+    ///
+    /// Occurs when the `Signature-Input` or `Signature` header is not a valid
+    /// structured field dictionary, or a member has the wrong type.
+    #[error("Malformed {header} header: {reason}")]
+    MalformedHeader { header: String, reason: String },
+
+    /// No signature with the requested label.
+    ///
+    /// Occurs when the `Signature-Input` or `Signature` header has no member with
+    /// the label selected for validation.
+    #[error("No signature with label {0:?}")]
+    SignatureNotFound(String),
+
+    /// Required signature parameters that are absent.
+    ///
+    /// Occurs when a signature does not declare a parameter needed for validation,
+    /// such as `created` or `keyid`.
+    #[error("Missing signature parameter: {0}")]
+    MissingParameter(String),
+
+    /// Signature parameters with an invalid type or value.
+    ///
+    /// Occurs when a parameter such as `created`, `expires` or `alg` has the wrong
+    /// structured field type, or a value that makes the signature invalid.
+    #[error("Invalid signature parameter {name}: {reason}")]
+    InvalidParameter { name: String, reason: String },
+
+    /// Covered components that are absent from the message.
+    ///
+    /// Occurs when a signature covers a header field, query parameter or dictionary
+    /// member that the message does not contain.
+    #[error("Missing covered component: {0}")]
+    MissingComponent(String),
+
+    /// Component identifiers that are invalid or unsupported.
+    ///
+    /// Occurs when a component identifier is unknown, has invalid parameters, or
+    /// does not apply to the message, such as `@status` on a request.
+    #[error("Invalid component {component}: {reason}")]
+    InvalidComponent { component: String, reason: String },
+
+    /// Components the validation policy requires but the signature does not cover.
+    ///
+    /// Occurs when a signature is valid but leaves a required part of the message,
+    /// such as the `Authorization` header or the content digest, unprotected.
+    #[error("Required component is not covered: {0}")]
+    UncoveredComponent(String),
+
+    /// Nonces that were already used.
+    ///
+    /// Occurs when a signature reuses a nonce recorded in the policy's nonce store,
+    /// which indicates a replayed request.
+    #[error("Nonce {0:?} was already used or could not be recorded")]
+    ReplayedNonce(String),
+
+    /// Key IDs that no key can be resolved for.
+    ///
+    /// Occurs when a [`KeyResolver`](crate::http_signature::KeyResolver) has no key
+    /// for the `keyid` of a signature.
+    #[error("Unknown key ID {0:?}")]
+    UnknownKey(String),
+
+    /// Content that does not match its `Content-Digest`.
+    ///
+    /// Occurs when the digest computed over a message body differs from the digest
+    /// declared for the given algorithm.
+    #[error("Content digest mismatch for {0}")]
+    ContentDigestMismatch(String),
+
+    /// Digests using only unsupported algorithms.
+    ///
+    /// Occurs when a digest algorithm other than `sha-256` or `sha-512` is requested,
+    /// or a `Content-Digest` header contains no digest that can be checked.
+    #[error("Unsupported digest algorithm: {0}")]
+    UnsupportedDigestAlgorithm(String),
+
     /// Miscellaneous errors that don't fit into other categories.
     ///
     /// This variant is used for errors that are specific to the HTTP signature
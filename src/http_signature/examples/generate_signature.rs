@@ -2,7 +2,7 @@ use base64::Engine;
 use ed25519_dalek::SigningKey;
 use http::{Method, Request, Uri};
 use open_payments::http_signature::signatures::create_signature_headers;
-use open_payments::http_signature::{HttpSignatureError, SignOptions};
+use open_payments::http_signature::{Ed25519Signer, HttpSignatureError, SignOptions};
 use rand::rngs::OsRng;
 use serde_json::json;
 
@@ -22,8 +22,9 @@ fn main() -> Result<(), HttpSignatureError> {
     let signing_key = SigningKey::generate(&mut OsRng);
 
     // Create signature headers
-    let options = SignOptions::new(&request, &signing_key, "test-key".to_string());
-    let headers = create_signature_headers(options)?;
+    let signer = Ed25519Signer::new("test-key", signing_key.clone());
+    let options = SignOptions::new(&request, &signer);
+    let headers = futures::executor::block_on(create_signature_headers(options))?;
 
     // Output the signature data and public key in JSON format
     println!(
@@ -1,4 +1,6 @@
 use crate::http_signature::{HttpSignatureError, Result};
+use crate::types::wallet_address::jwk_thumbprint;
+use crate::types::{JsonWebKey, JwkAlgorithm, JwkCurve, JwkKeyType, JwkUse};
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use ed25519_dalek::{SigningKey, VerifyingKey};
 use rand::rngs::OsRng;
@@ -14,9 +16,11 @@ pub enum JwkError {
     EmptyKeyId,
     #[error("Key is not EdDSA-Ed25519")]
     InvalidKeyType,
+    #[error("Key use {0:?} is not supported")]
+    UnsupportedUse(String),
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Jwk {
     pub kid: String,
     pub alg: String,
@@ -81,6 +85,75 @@ impl Jwk {
         fs::write(jwks_path, jwks_json).map_err(HttpSignatureError::from)?;
         Ok(())
     }
+
+    /// Returns the [RFC 7638](https://www.rfc-editor.org/rfc/rfc7638) thumbprint of the
+    /// key, computed with SHA-256 and encoded with base64url.
+    pub fn thumbprint(&self) -> String {
+        jwk_thumbprint(&self.crv, &self.kty, &self.x)
+    }
+}
+
+/// Creates the JWK of the public key, identified by its thumbprint.
+impl From<&SigningKey> for Jwk {
+    fn from(signing_key: &SigningKey) -> Self {
+        Jwk::from(&JsonWebKey::from(signing_key))
+    }
+}
+
+impl From<&JsonWebKey> for Jwk {
+    fn from(jwk: &JsonWebKey) -> Self {
+        Self {
+            kid: jwk.kid.clone(),
+            alg: match jwk.alg {
+                JwkAlgorithm::EdDSA => "EdDSA",
+            }
+            .to_string(),
+            use_: jwk.use_.map(|use_| {
+                match use_ {
+                    JwkUse::Signature => "sig",
+                }
+                .to_string()
+            }),
+            kty: match jwk.kty {
+                JwkKeyType::OKP => "OKP",
+            }
+            .to_string(),
+            crv: match jwk.crv {
+                JwkCurve::Ed25519 => "Ed25519",
+            }
+            .to_string(),
+            x: jwk.x.clone(),
+        }
+    }
+}
+
+impl TryFrom<&Jwk> for JsonWebKey {
+    type Error = HttpSignatureError;
+
+    fn try_from(jwk: &Jwk) -> Result<Self> {
+        if jwk.alg != "EdDSA" || jwk.kty != "OKP" || jwk.crv != "Ed25519" {
+            return Err(HttpSignatureError::Jwk(
+                JwkError::InvalidKeyType.to_string(),
+            ));
+        }
+        let use_ = match jwk.use_.as_deref() {
+            None => None,
+            Some("sig") => Some(JwkUse::Signature),
+            Some(use_) => {
+                return Err(HttpSignatureError::Jwk(
+                    JwkError::UnsupportedUse(use_.to_string()).to_string(),
+                ))
+            }
+        };
+        Ok(Self {
+            kid: jwk.kid.clone(),
+            alg: JwkAlgorithm::EdDSA,
+            use_,
+            kty: JwkKeyType::OKP,
+            crv: JwkCurve::Ed25519,
+            x: jwk.x.clone(),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -102,4 +175,58 @@ mod tests {
         let result = Jwk::new("".to_string(), None);
         assert!(matches!(result, Err(HttpSignatureError::Jwk(_))));
     }
+
+    #[test]
+    fn test_thumbprint() {
+        // RFC 8037, appendix A.3
+        let jwk = Jwk {
+            kid: "test-key".to_string(),
+            alg: "EdDSA".to_string(),
+            use_: None,
+            kty: "OKP".to_string(),
+            crv: "Ed25519".to_string(),
+            x: "11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo".to_string(),
+        };
+        assert_eq!(
+            jwk.thumbprint(),
+            "kPrK_qmxVWaYVA9wwBF6Iuo3vVzz7TxHCTwXBygrS4k"
+        );
+        let json_web_key = JsonWebKey::try_from(&jwk).unwrap();
+        assert_eq!(json_web_key.thumbprint(), jwk.thumbprint());
+    }
+
+    #[test]
+    fn test_signing_key_conversions() {
+        let signing_key = SigningKey::generate(&mut OsRng);
+        let json_web_key = JsonWebKey::from(&signing_key);
+        let jwk = Jwk::from(&signing_key);
+        assert_eq!(json_web_key.kid, json_web_key.thumbprint());
+        assert_eq!(jwk, Jwk::from(&json_web_key));
+        assert_eq!(JsonWebKey::try_from(&jwk).unwrap(), json_web_key);
+        assert_eq!(
+            VerifyingKey::try_from(&json_web_key).unwrap(),
+            signing_key.verifying_key()
+        );
+
+        let generated = Jwk::new("test-key".to_string(), Some(&signing_key)).unwrap();
+        assert_eq!(generated.x, jwk.x);
+    }
+
+    #[test]
+    fn test_invalid_keys() {
+        let mut json_web_key = JsonWebKey::from(&SigningKey::generate(&mut OsRng));
+        json_web_key.x = URL_SAFE_NO_PAD.encode([1u8; 31]);
+        assert!(VerifyingKey::try_from(&json_web_key).is_err());
+        json_web_key.x = "not base64url!".to_string();
+        assert!(VerifyingKey::try_from(&json_web_key).is_err());
+
+        let mut jwk = Jwk::new("test-key".to_string(), None).unwrap();
+        jwk.use_ = Some("enc".to_string());
+        assert!(matches!(
+            JsonWebKey::try_from(&jwk),
+            Err(HttpSignatureError::Jwk(_))
+        ));
+        jwk.crv = "X25519".to_string();
+        assert!(JsonWebKey::try_from(&jwk).is_err());
+    }
 }
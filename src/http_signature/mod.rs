@@ -6,7 +6,7 @@
 //!
 //! ## Features
 //!
-//! - **Signature Creation**: Create HTTP message signatures with Ed25519 keys
+//! - **Signature Creation**: Create HTTP message signatures with Ed25519 keys or any [`HttpMessageSigner`]
 //! - **Signature Validation**: Validate incoming HTTP message signatures
 //! - **JWK Support**: Generate and handle JSON Web Keys
 //! - **Key Management**: Load keys from files or generate new ones
@@ -14,19 +14,24 @@
 //! ## Signature Creation and Validation
 //!
 //! ```rust
-//! use open_payments::http_signature::{create_signature_headers, validate_signature, SignOptions, ValidationOptions, HttpSignatureError};
+//! use open_payments::http_signature::{create_content_digest, create_signature_headers, validate_signature, DigestAlgorithm, Ed25519Signer, SignOptions, ValidationOptions, HttpSignatureError};
 //! use http::{Request, Method, Uri, HeaderMap};
 //! use ed25519_dalek::SigningKey;
 //! use rand::rngs::OsRng;
 //!
-//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!     let mut request = Request::new(Some("test body".to_string()));
 //!     *request.method_mut() = Method::POST;
 //!     *request.uri_mut() = Uri::from_static("https://ilp.rafiki.money/incoming-payments");
+//!     // Signatures of requests with a body must cover their digest
+//!     let digest = create_content_digest(b"test body", DigestAlgorithm::Sha512);
+//!     request.headers_mut().insert("Content-Digest", digest.parse()?);
 //!
 //!     let signing_key = SigningKey::generate(&mut rand::rngs::OsRng);
-//!     let options = SignOptions::new(&request, &signing_key, "test-key".to_string());
-//!     let signature_headers = create_signature_headers(options)?;
+//!     let signer = Ed25519Signer::new("test-key", signing_key.clone());
+//!     let options = SignOptions::new(&request, &signer);
+//!     let signature_headers = create_signature_headers(options).await?;
 //!
 //!     let mut headers = HeaderMap::new();
 //!     headers.insert("Signature", signature_headers.signature.parse().map_err(|_| HttpSignatureError::Validation("Invalid signature".to_string()))?);
@@ -68,19 +73,38 @@
 //! ## Module Structure
 //!
 //! - [`signatures`] - Core signature creation functionality
+//! - [`components`] - Covered component resolution and signature base creation
+//! - [`content_digest`] - `Content-Digest` creation, verification and negotiation
+//! - [`signer`] - Signer trait and in-memory Ed25519 signer
+//! - [`structured_field`] - RFC 8941 structured field parsing and serialization
 //! - [`validation`] - Signature validation utilities
+//! - [`policy`] - Validation policy and replay protection
+//! - [`resolver`] - Key resolution from the `keyid` of a signature
 //! - [`jwk`] - JSON Web Key generation and handling
 //! - [`utils`] - Key management utilities
 //! - [`error`] - Error types and handling
 
+pub mod components;
+pub mod content_digest;
 pub mod error;
 pub mod jwk;
+pub mod policy;
+pub mod resolver;
 pub mod signatures;
+pub mod signer;
+pub mod structured_field;
 pub mod utils;
 pub mod validation;
 
+pub use self::components::HttpMessage;
+pub use self::content_digest::{create_content_digest, verify_content_digest, DigestAlgorithm};
 pub use self::error::{HttpSignatureError, Result};
 pub use self::jwk::{Jwk, JwkError};
-pub use self::signatures::{create_signature_headers, SignOptions, SignatureHeaders};
-pub use self::utils::load_or_generate_key;
-pub use self::validation::{validate_signature, ValidationOptions};
+pub use self::policy::{InMemoryNonceStore, NonceStore, ValidationPolicy};
+pub use self::resolver::{validate_signature_with_resolver, KeyResolver};
+pub use self::signatures::{
+    create_signature_headers, SignOptions, SignatureHeaders, SignatureParams, SIGNATURE_LABEL,
+};
+pub use self::signer::{Ed25519Signer, HttpMessageSigner};
+pub use self::utils::{load_key, load_or_generate_key, parse_private_key};
+pub use self::validation::{parse_signature_input, validate_signature, ValidationOptions};
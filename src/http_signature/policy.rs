@@ -0,0 +1,285 @@
+//! # Validation Policy
+//!
+//! This module defines [`ValidationPolicy`], the checks applied by
+//! [`validate_signature`](crate::http_signature::validate_signature) beyond the
+//! cryptographic verification: signature age, clock skew, required parameters,
+//! required covered components and replay protection.
+//!
+//! Replay protection is provided by a [`NonceStore`] that remembers the nonces of
+//! accepted signatures. [`InMemoryNonceStore`] is a bounded in-process implementation;
+//! servers running several instances should share a store, e.g. one backed by Redis.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use open_payments::http_signature::{InMemoryNonceStore, ValidationPolicy};
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! let policy = ValidationPolicy {
+//!     max_age: Some(Duration::from_secs(60)),
+//!     required_components: vec!["@method".into(), "@target-uri".into()],
+//!     required_components_with_body: vec!["content-digest".into(), "content-length".into()],
+//!     nonce_store: Some(Arc::new(InMemoryNonceStore::new(10_000))),
+//!     ..ValidationPolicy::default()
+//! };
+//! ```
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Default maximum age of a signature.
+pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(5 * 60);
+
+/// Default tolerance for `created` timestamps ahead of the local clock.
+pub const DEFAULT_FUTURE_SKEW: Duration = Duration::from_secs(60);
+
+/// Store of nonces already used by accepted signatures.
+pub trait NonceStore: Send + Sync {
+    /// Records a nonce used with the given key ID.
+    ///
+    /// The nonce only needs to be remembered until `expires_at`, a Unix timestamp after
+    /// which a signature using it is rejected anyway. Returns `false` if the nonce was
+    /// already recorded for this key ID and has not expired, or if the store cannot
+    /// remember it until then.
+    fn insert(&self, keyid: &str, nonce: &str, expires_at: i64) -> bool;
+}
+
+/// In-memory [`NonceStore`] holding a bounded number of nonces.
+///
+/// Nonces are kept in the order they were recorded. Recording a nonce drops expired
+/// nonces from the front of that queue in constant amortized time, and from anywhere in
+/// the store once it is full. Nonces are never
+/// forgotten before they expire, as that would allow replays: once the store is full of
+/// live nonces, new nonces are rejected until old ones expire. The capacity should
+/// therefore exceed the number of signatures accepted within the policy's maximum age.
+pub struct InMemoryNonceStore {
+    capacity: usize,
+    state: Mutex<NonceState>,
+}
+
+#[derive(Default)]
+struct NonceState {
+    seen: HashMap<(String, String), (i64, u64)>,
+    order: VecDeque<((String, String), u64)>,
+    generation: u64,
+}
+
+impl InMemoryNonceStore {
+    /// Creates a store remembering at most `capacity` nonces.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            state: Mutex::new(NonceState::default()),
+        }
+    }
+
+    /// Returns the number of nonces currently remembered.
+    pub fn len(&self) -> usize {
+        self.lock().seen.len()
+    }
+
+    /// Returns `true` if no nonces are remembered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, NonceState> {
+        // The state is consistent after every operation, so a poisoned lock is still usable
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl NonceState {
+    /// Drops queue entries of nonces that were replaced.
+    fn compact(&mut self) {
+        let seen = &self.seen;
+        self.order
+            .retain(|(key, generation)| seen.get(key).is_some_and(|(_, g)| g == generation));
+    }
+
+    /// Drops queue entries from the front while they are expired or were replaced.
+    fn drop_expired(&mut self, now: i64) {
+        while let Some((key, generation)) = self.order.front() {
+            match self.seen.get(key) {
+                Some((expires_at, g)) if *g == *generation && *expires_at >= now => break,
+                Some((_, g)) if *g == *generation => {
+                    self.seen.remove(key);
+                }
+                _ => {}
+            }
+            self.order.pop_front();
+        }
+    }
+}
+
+impl NonceStore for InMemoryNonceStore {
+    fn insert(&self, keyid: &str, nonce: &str, expires_at: i64) -> bool {
+        let now = chrono::Utc::now().timestamp();
+        let mut state = self.lock();
+
+        state.drop_expired(now);
+        let key = (keyid.to_string(), nonce.to_string());
+        // An expired nonce further back in the queue may be reused; its old queue entry
+        // becomes stale and is skipped once it reaches the front
+        if state
+            .seen
+            .get(&key)
+            .is_some_and(|(expires_at, _)| *expires_at >= now)
+        {
+            return false;
+        }
+        // Reusing an expired nonce replaces its entry rather than adding one
+        if state.seen.len() >= self.capacity && !state.seen.contains_key(&key) {
+            // Expired nonces behind live ones are only dropped when room is needed
+            state.seen.retain(|_, (expires_at, _)| *expires_at >= now);
+            state.compact();
+        }
+        if state.seen.len() >= self.capacity && !state.seen.contains_key(&key) {
+            log::warn!("Nonce store is full, rejecting nonce for key {keyid}");
+            return false;
+        }
+
+        state.generation += 1;
+        let generation = state.generation;
+        state.seen.insert(key.clone(), (expires_at, generation));
+        state.order.push_back((key, generation));
+        // Replaced entries are skipped at the front, but may pile up behind live ones
+        if state.order.len() > 2 * self.capacity {
+            state.compact();
+        }
+        true
+    }
+}
+
+/// Checks applied to a signature in addition to cryptographic verification.
+///
+/// The default policy accepts signatures created at most [`DEFAULT_MAX_AGE`] ago and
+/// at most [`DEFAULT_FUTURE_SKEW`] ahead of the local clock, requires `content-digest`
+/// to be covered when the message has a body and the `Authorization` header whenever it
+/// is sent, and performs no replay protection.
+#[derive(Clone)]
+pub struct ValidationPolicy {
+    /// Maximum age of a signature, based on its `created` parameter. `None` disables the check.
+    pub max_age: Option<Duration>,
+    /// Tolerance for `created` timestamps ahead of the local clock.
+    pub future_skew: Duration,
+    /// Whether signatures must declare an `expires` parameter.
+    pub require_expires: bool,
+    /// Components every signature must cover.
+    pub required_components: Vec<String>,
+    /// Components a signature must cover when the message has a body.
+    pub required_components_with_body: Vec<String>,
+    /// Header fields a signature must cover whenever the message contains them.
+    pub required_fields_if_present: Vec<String>,
+    /// Store used to reject reused nonces.
+    ///
+    /// When set, signatures must declare a `nonce` parameter.
+    pub nonce_store: Option<Arc<dyn NonceStore>>,
+}
+
+impl Default for ValidationPolicy {
+    fn default() -> Self {
+        Self {
+            max_age: Some(DEFAULT_MAX_AGE),
+            future_skew: DEFAULT_FUTURE_SKEW,
+            require_expires: false,
+            required_components: Vec::new(),
+            required_components_with_body: vec!["content-digest".to_string()],
+            required_fields_if_present: vec!["authorization".to_string()],
+            nonce_store: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for ValidationPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ValidationPolicy")
+            .field("max_age", &self.max_age)
+            .field("future_skew", &self.future_skew)
+            .field("require_expires", &self.require_expires)
+            .field("required_components", &self.required_components)
+            .field(
+                "required_components_with_body",
+                &self.required_components_with_body,
+            )
+            .field(
+                "required_fields_if_present",
+                &self.required_fields_if_present,
+            )
+            .field("nonce_store", &self.nonce_store.is_some())
+            .finish()
+    }
+}
+
+impl ValidationPolicy {
+    /// Policy with no time, component or replay checks.
+    ///
+    /// Only the signature itself and the parameters required to verify it are checked.
+    pub fn permissive() -> Self {
+        Self {
+            max_age: None,
+            future_skew: Duration::MAX,
+            require_expires: false,
+            required_components: Vec::new(),
+            required_components_with_body: Vec::new(),
+            required_fields_if_present: Vec::new(),
+            nonce_store: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nonce_store_rejects_reuse() {
+        let store = InMemoryNonceStore::new(10);
+        let expires_at = chrono::Utc::now().timestamp() + 60;
+
+        assert!(store.insert("key-1", "nonce", expires_at));
+        assert!(!store.insert("key-1", "nonce", expires_at));
+        assert!(store.insert("key-2", "nonce", expires_at));
+        assert_eq!(store.len(), 2);
+    }
+
+    #[test]
+    fn test_nonce_store_drops_expired_nonces() {
+        let store = InMemoryNonceStore::new(2);
+        let now = chrono::Utc::now().timestamp();
+
+        assert!(store.insert("k", "expired", now - 1));
+        assert!(store.insert("k", "expired", now + 60));
+        assert!(store.insert("k", "a", now - 1));
+        // "a" expired, so it makes room for "b"
+        assert!(store.insert("k", "b", now + 60));
+        assert_eq!(store.len(), 2);
+    }
+
+    #[test]
+    fn test_nonce_store_keeps_live_nonces_when_full() {
+        let store = InMemoryNonceStore::new(3);
+        let now = chrono::Utc::now().timestamp();
+
+        for nonce in ["first", "second", "third"] {
+            assert!(store.insert("k", nonce, now + 60));
+        }
+        // A full store rejects new nonces rather than forgetting live ones
+        assert!(!store.insert("k", "fourth", now + 60));
+        assert!(!store.insert("k", "first", now + 60));
+        assert_eq!(store.len(), 3);
+    }
+
+    #[test]
+    fn test_nonce_store_accepts_expired_nonce_behind_live_ones() {
+        let store = InMemoryNonceStore::new(10);
+        let now = chrono::Utc::now().timestamp();
+
+        assert!(store.insert("k", "live", now + 60));
+        assert!(store.insert("k", "expired", now - 1));
+        assert!(store.insert("k", "expired", now + 60));
+        assert!(!store.insert("k", "expired", now + 60));
+        assert_eq!(store.len(), 2);
+    }
+}
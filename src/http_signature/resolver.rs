@@ -0,0 +1,99 @@
+//! # Key Resolution
+//!
+//! [`validate_signature`] expects the caller
+//! to hold the public key of the signer. A server validating signatures from many
+//! clients usually only learns which key to use from the `keyid` parameter of the
+//! signature itself. This module defines [`KeyResolver`], which looks up the key for a
+//! `keyid`, and [`validate_signature_with_resolver`], which validates a signature with
+//! the key it resolves.
+//!
+//! [`JwksKeyResolver`](crate::client::JwksKeyResolver) resolves keys from the JWKS of
+//! a client's wallet address.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use open_payments::http_signature::{
+//!     create_signature_headers, validate_signature_with_resolver, Ed25519Signer, SignOptions,
+//!     ValidationPolicy,
+//! };
+//! use ed25519_dalek::{SigningKey, VerifyingKey};
+//! use http::{HeaderMap, Request};
+//! use std::collections::HashMap;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let signing_key = SigningKey::generate(&mut rand::rngs::OsRng);
+//! let mut request = Request::new(None);
+//! *request.uri_mut() = "https://ilp.rafiki.money/incoming-payments".parse()?;
+//! let signer = Ed25519Signer::new("key-1", signing_key.clone());
+//! let signature = create_signature_headers(SignOptions::new(&request, &signer)).await?;
+//!
+//! let mut headers = HeaderMap::new();
+//! headers.insert("Signature", signature.signature.parse()?);
+//! headers.insert("Signature-Input", signature.signature_input.parse()?);
+//!
+//! let keys: HashMap<String, VerifyingKey> =
+//!     HashMap::from([("key-1".to_string(), signing_key.verifying_key())]);
+//! validate_signature_with_resolver(&request, &headers, &keys, ValidationPolicy::default())
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::http_signature::error::{HttpSignatureError, Result};
+use crate::http_signature::policy::ValidationPolicy;
+use crate::http_signature::validation::{
+    parse_signature_header, signature_params, validate_signature, ValidationOptions,
+};
+use async_trait::async_trait;
+use ed25519_dalek::VerifyingKey;
+use http::{HeaderMap, Request};
+use std::collections::HashMap;
+
+/// Looks up the public key of a signature from its `keyid`.
+#[async_trait]
+pub trait KeyResolver: Send + Sync {
+    /// Returns the key identified by `keyid`.
+    ///
+    /// Fails with [`HttpSignatureError::UnknownKey`] if there is no such key.
+    async fn resolve(&self, keyid: &str) -> Result<VerifyingKey>;
+}
+
+/// Resolves keys from a fixed set of keys by key ID.
+#[async_trait]
+impl KeyResolver for HashMap<String, VerifyingKey> {
+    async fn resolve(&self, keyid: &str) -> Result<VerifyingKey> {
+        self.get(keyid)
+            .copied()
+            .ok_or_else(|| HttpSignatureError::UnknownKey(keyid.to_string()))
+    }
+}
+
+/// Validates the first signature of a request with the key resolved from its `keyid`.
+///
+/// See [`validate_signature`] for the checks applied to the signature.
+pub async fn validate_signature_with_resolver(
+    request: &Request<Option<String>>,
+    headers: &HeaderMap,
+    resolver: &(impl KeyResolver + ?Sized),
+    policy: ValidationPolicy,
+) -> Result<()> {
+    let signature_input = parse_signature_header(headers, "Signature-Input")?.ok_or_else(|| {
+        HttpSignatureError::Validation("Missing Signature-Input header".to_string())
+    })?;
+    let (label, params) = signature_params(&signature_input)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| {
+            HttpSignatureError::Validation("Missing Signature-Input header".to_string())
+        })?;
+    let keyid = params
+        .keyid()
+        .ok_or_else(|| HttpSignatureError::MissingParameter("keyid".to_string()))?;
+
+    let public_key = resolver.resolve(keyid).await?;
+    let options = ValidationOptions::new(request, headers, &public_key)
+        .with_label(&label)
+        .with_policy(policy);
+    validate_signature(options)
+}
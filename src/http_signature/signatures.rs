@@ -1,9 +1,16 @@
-use crate::http_signature::error::Result;
-use base64::{engine::general_purpose::STANDARD, Engine};
-use ed25519_dalek::{Signer, SigningKey};
+use crate::http_signature::components::{create_signature_base, HttpMessage};
+use crate::http_signature::error::{HttpSignatureError, Result};
+use crate::http_signature::signer::{HttpMessageSigner, ED25519_ALGORITHM};
+use crate::http_signature::structured_field::{
+    serialize_dictionary, serialize_inner_list, serialize_item, BareItem, InnerList, Item,
+    ListEntry, Parameters,
+};
 use http::Request;
 use serde::{Deserialize, Serialize};
 
+/// Label of the signature created by [`create_signature_headers`].
+pub const SIGNATURE_LABEL: &str = "sig1";
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SignatureHeaders {
     pub signature: String,
@@ -12,96 +19,213 @@ pub struct SignatureHeaders {
 
 pub struct SignOptions<'a> {
     pub request: &'a Request<Option<String>>,
-    pub private_key: &'a SigningKey,
-    pub key_id: String,
+    pub signer: &'a dyn HttpMessageSigner,
 }
 
 impl SignOptions<'_> {
     pub fn new<'a>(
         request: &'a Request<Option<String>>,
-        private_key: &'a SigningKey,
-        key_id: String,
+        signer: &'a dyn HttpMessageSigner,
     ) -> SignOptions<'a> {
-        SignOptions {
-            request,
-            private_key,
-            key_id,
-        }
+        SignOptions { request, signer }
     }
 }
 
-fn create_signature_base_string(
-    request: &Request<Option<String>>,
-    components: &[&str],
-    created: i64,
-    keyid: &str,
-) -> String {
-    let mut parts = Vec::new();
-
-    for component in components {
-        let value = match *component {
-            "@method" => request.method().as_str(),
-            "@target-uri" => &request.uri().to_string(),
-            "authorization" => request
-                .headers()
-                .get("Authorization")
-                .and_then(|v| v.to_str().ok())
-                .unwrap_or(""),
-            "content-digest" => request
-                .headers()
-                .get("Content-Digest")
-                .and_then(|v| v.to_str().ok())
-                .unwrap_or(""),
-            "content-length" => request
-                .headers()
-                .get("Content-Length")
-                .and_then(|v| v.to_str().ok())
-                .unwrap_or(""),
-            "content-type" => request
-                .headers()
-                .get("Content-Type")
-                .and_then(|v| v.to_str().ok())
-                .unwrap_or(""),
-            _ => "",
-        };
-        parts.push(format!("\"{component}\": {value}"));
-    }
-
-    let sig_params = format!(
-        "({});created={};keyid=\"{}\"",
-        components.join(" "),
-        created,
-        keyid
-    );
-    parts.push(format!("\"@signature-params\": {sig_params}"));
-
-    parts.join("\n")
+/// Signature parameters of a single signature, as carried in a `Signature-Input` member.
+///
+/// The covered components and parameters keep their order, so that the
+/// `@signature-params` line of a received signature can be reproduced exactly.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SignatureParams {
+    components: Vec<Item>,
+    params: Parameters,
 }
 
-pub fn create_signature_headers(options: SignOptions<'_>) -> Result<SignatureHeaders> {
-    let mut components = vec!["@method", "@target-uri", "content-type"];
+impl SignatureParams {
+    /// Creates signature parameters covering the given component names.
+    pub fn new<I, S>(components: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            components: components
+                .into_iter()
+                .map(|name| Item::new(BareItem::String(name.into())))
+                .collect(),
+            params: Parameters::default(),
+        }
+    }
+
+    /// Parses signature parameters from a `Signature-Input` inner list.
+    ///
+    /// Covered components must be strings and appear only once. The `created`
+    /// and `expires` parameters must be integers; `alg`, `keyid`, `nonce` and `tag`
+    /// must be strings. Unknown parameters are kept as-is.
+    pub fn from_inner_list(inner_list: &InnerList) -> Result<Self> {
+        for (i, component) in inner_list.items.iter().enumerate() {
+            if component.bare_item.as_str().is_none() {
+                return Err(HttpSignatureError::MalformedHeader {
+                    header: "Signature-Input".to_string(),
+                    reason: "component identifiers must be strings".to_string(),
+                });
+            }
+            if inner_list.items[..i].contains(component) {
+                return Err(HttpSignatureError::MalformedHeader {
+                    header: "Signature-Input".to_string(),
+                    reason: format!(
+                        "component {} is covered more than once",
+                        serialize_item(component)?
+                    ),
+                });
+            }
+        }
+
+        for (name, value) in inner_list.params.iter() {
+            let valid = match name.as_str() {
+                "created" | "expires" => value.as_integer().is_some(),
+                "alg" | "keyid" | "nonce" | "tag" => value.as_str().is_some(),
+                _ => true,
+            };
+            if !valid {
+                let expected = match name.as_str() {
+                    "created" | "expires" => "an integer",
+                    _ => "a string",
+                };
+                return Err(HttpSignatureError::InvalidParameter {
+                    name: name.clone(),
+                    reason: format!("expected {expected}"),
+                });
+            }
+        }
+
+        Ok(Self {
+            components: inner_list.items.clone(),
+            params: inner_list.params.clone(),
+        })
+    }
+
+    /// Returns the covered component identifiers, in order.
+    pub fn components(&self) -> &[Item] {
+        &self.components
+    }
+
+    /// Returns the `created` parameter.
+    pub fn created(&self) -> Option<i64> {
+        self.params.get("created").and_then(BareItem::as_integer)
+    }
+
+    /// Returns the `expires` parameter.
+    pub fn expires(&self) -> Option<i64> {
+        self.params.get("expires").and_then(BareItem::as_integer)
+    }
+
+    /// Returns the `alg` parameter.
+    pub fn alg(&self) -> Option<&str> {
+        self.params.get("alg").and_then(BareItem::as_str)
+    }
+
+    /// Returns the `keyid` parameter.
+    pub fn keyid(&self) -> Option<&str> {
+        self.params.get("keyid").and_then(BareItem::as_str)
+    }
+
+    /// Returns the `nonce` parameter.
+    pub fn nonce(&self) -> Option<&str> {
+        self.params.get("nonce").and_then(BareItem::as_str)
+    }
+
+    /// Returns the `tag` parameter.
+    pub fn tag(&self) -> Option<&str> {
+        self.params.get("tag").and_then(BareItem::as_str)
+    }
+
+    /// Sets the `created` parameter.
+    pub fn with_created(mut self, created: i64) -> Self {
+        self.params.insert("created", BareItem::Integer(created));
+        self
+    }
+
+    /// Sets the `expires` parameter.
+    pub fn with_expires(mut self, expires: i64) -> Self {
+        self.params.insert("expires", BareItem::Integer(expires));
+        self
+    }
+
+    /// Sets the `alg` parameter.
+    pub fn with_alg(mut self, alg: impl Into<String>) -> Self {
+        self.params.insert("alg", BareItem::String(alg.into()));
+        self
+    }
 
-    if options.request.headers().get("Authorization").is_some() {
-        components.push("authorization");
+    /// Sets the `keyid` parameter.
+    pub fn with_keyid(mut self, keyid: impl Into<String>) -> Self {
+        self.params.insert("keyid", BareItem::String(keyid.into()));
+        self
     }
 
+    /// Sets the `nonce` parameter.
+    pub fn with_nonce(mut self, nonce: impl Into<String>) -> Self {
+        self.params.insert("nonce", BareItem::String(nonce.into()));
+        self
+    }
+
+    /// Sets the `tag` parameter.
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.params.insert("tag", BareItem::String(tag.into()));
+        self
+    }
+
+    /// Returns the parameters as a structured field inner list.
+    pub fn to_inner_list(&self) -> InnerList {
+        InnerList {
+            items: self.components.clone(),
+            params: self.params.clone(),
+        }
+    }
+
+    /// Serializes the parameters as used in the `@signature-params` component.
+    pub fn serialize(&self) -> Result<String> {
+        serialize_inner_list(&self.to_inner_list())
+    }
+}
+
+pub async fn create_signature_headers(options: SignOptions<'_>) -> Result<SignatureHeaders> {
+    let headers = options.request.headers();
+    let mut components = vec!["@method", "@target-uri"];
+    for header in ["content-type", "authorization"] {
+        if headers.contains_key(header) {
+            components.push(header);
+        }
+    }
     if options.request.body().is_some() {
-        components.extend_from_slice(&["content-digest", "content-length"]);
+        for header in ["content-digest", "content-length"] {
+            if headers.contains_key(header) {
+                components.push(header);
+            }
+        }
+    }
+    let mut params = SignatureParams::new(components)
+        .with_created(chrono::Utc::now().timestamp())
+        .with_keyid(options.signer.key_id());
+    // Ed25519 is implied by Open Payments keys, other algorithms must be declared
+    let algorithm = options.signer.algorithm();
+    if algorithm != ED25519_ALGORITHM {
+        params = params.with_alg(algorithm);
     }
-    let created = chrono::Utc::now().timestamp();
 
-    let signature_base =
-        create_signature_base_string(options.request, &components, created, &options.key_id);
+    let signature_base = create_signature_base(&HttpMessage::Request(options.request), &params)?;
 
-    let signature_bytes = options.private_key.sign(signature_base.as_bytes());
-    let signature = STANDARD.encode(signature_bytes.to_bytes());
+    let signature_bytes = options.signer.sign(signature_base.as_bytes()).await?;
 
-    let signature_input = format!(
-        "sig1=({});created={};keyid=\"{}\"",
-        components.join(" "),
-        created,
-        options.key_id
-    );
+    let signature = serialize_dictionary(&[(
+        SIGNATURE_LABEL.to_string(),
+        ListEntry::Item(Item::new(BareItem::ByteSequence(signature_bytes))),
+    )])?;
+    let signature_input = serialize_dictionary(&[(
+        SIGNATURE_LABEL.to_string(),
+        ListEntry::InnerList(params.to_inner_list()),
+    )])?;
 
     Ok(SignatureHeaders {
         signature,
@@ -112,12 +236,13 @@ pub fn create_signature_headers(options: SignOptions<'_>) -> Result<SignatureHea
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::http_signature::Ed25519Signer;
     use ed25519_dalek::SigningKey;
     use http::{Method, Request, Uri};
     use rand::rngs::OsRng;
 
-    #[test]
-    fn test_signature_creation() {
+    #[tokio::test]
+    async fn test_signature_creation() {
         let mut request = Request::new(Some("test body".to_string()));
         *request.method_mut() = Method::POST;
         *request.uri_mut() = Uri::from_static("http://example.com");
@@ -125,11 +250,71 @@ mod tests {
             .headers_mut()
             .insert("Content-Type", "application/json".parse().unwrap());
 
-        let signing_key = SigningKey::generate(&mut OsRng);
-        let options = SignOptions::new(&request, &signing_key, "test-key".to_string());
+        let signer = Ed25519Signer::new("test-key", SigningKey::generate(&mut OsRng));
+        let options = SignOptions::new(&request, &signer);
+
+        let headers = create_signature_headers(options).await.unwrap();
+        assert!(headers.signature.starts_with("sig1=:"));
+        assert!(headers.signature.ends_with(':'));
+        assert!(headers
+            .signature_input
+            .starts_with(r#"sig1=("@method" "@target-uri" "content-type");created="#));
+        assert!(headers.signature_input.ends_with(r#";keyid="test-key""#));
+    }
+
+    #[tokio::test]
+    async fn test_signature_declares_non_default_algorithm() {
+        struct OtherAlgorithm(Ed25519Signer);
+
+        #[async_trait::async_trait]
+        impl HttpMessageSigner for OtherAlgorithm {
+            fn key_id(&self) -> &str {
+                self.0.key_id()
+            }
+
+            fn algorithm(&self) -> &str {
+                "rsa-pss-sha512"
+            }
+
+            async fn sign(&self, data: &[u8]) -> Result<Vec<u8>> {
+                self.0.sign(data).await
+            }
+        }
+
+        let mut request = Request::new(None::<String>);
+        *request.uri_mut() = Uri::from_static("http://example.com");
+        let signer = Ed25519Signer::new("test-key", SigningKey::generate(&mut OsRng));
+
+        let headers = create_signature_headers(SignOptions::new(&request, &signer))
+            .await
+            .unwrap();
+        assert!(!headers.signature_input.contains("alg="));
+
+        let signer = OtherAlgorithm(signer);
+        let headers = create_signature_headers(SignOptions::new(&request, &signer))
+            .await
+            .unwrap();
+        assert!(headers
+            .signature_input
+            .ends_with(r#";keyid="test-key";alg="rsa-pss-sha512""#));
+    }
+
+    #[test]
+    fn test_signature_params_roundtrip() {
+        let params = SignatureParams::new(["@method", "@authority"])
+            .with_created(1618884473)
+            .with_keyid("test-key-ed25519")
+            .with_nonce("b3k2pp5k7z-50gnwp.yemd");
+        let serialized = params.serialize().unwrap();
+        assert_eq!(
+            serialized,
+            r#"("@method" "@authority");created=1618884473;keyid="test-key-ed25519";nonce="b3k2pp5k7z-50gnwp.yemd""#
+        );
 
-        let headers = create_signature_headers(options).unwrap();
-        assert!(!headers.signature.is_empty());
-        assert!(!headers.signature_input.is_empty());
+        let parsed = SignatureParams::from_inner_list(&params.to_inner_list()).unwrap();
+        assert_eq!(parsed, params);
+        assert_eq!(parsed.created(), Some(1618884473));
+        assert_eq!(parsed.keyid(), Some("test-key-ed25519"));
+        assert_eq!(parsed.alg(), None);
     }
 }
@@ -0,0 +1,127 @@
+//! # HTTP Message Signers
+//!
+//! This module defines the [`HttpMessageSigner`] trait used to produce HTTP message
+//! signatures. Implementations decide where the private key lives: in process memory,
+//! in an HSM or KMS, or behind a separate signing process.
+//!
+//! [`Ed25519Signer`] is the in-memory implementation backed by an
+//! [`ed25519_dalek::SigningKey`].
+//!
+//! ## Example
+//!
+//! ```rust
+//! use open_payments::http_signature::{Ed25519Signer, HttpMessageSigner, HttpSignatureError};
+//! use async_trait::async_trait;
+//!
+//! struct KmsSigner {
+//!     key_id: String,
+//! }
+//!
+//! #[async_trait]
+//! impl HttpMessageSigner for KmsSigner {
+//!     fn key_id(&self) -> &str {
+//!         &self.key_id
+//!     }
+//!
+//!     async fn sign(&self, data: &[u8]) -> Result<Vec<u8>, HttpSignatureError> {
+//!         // Forward `data` to the KMS and return the raw signature bytes
+//!         Err(HttpSignatureError::Signature("not connected".to_string()))
+//!     }
+//! }
+//! ```
+use crate::http_signature::Result;
+use async_trait::async_trait;
+use ed25519_dalek::{Signer, SigningKey, VerifyingKey};
+
+/// Signature algorithm identifier for Ed25519, as registered for HTTP Message Signatures.
+pub const ED25519_ALGORITHM: &str = "ed25519";
+
+/// A signer producing HTTP message signatures.
+///
+/// The signer receives the complete signature base and returns the raw signature
+/// bytes, which are then base64 encoded into the `Signature` header.
+#[async_trait]
+pub trait HttpMessageSigner: Send + Sync {
+    /// Returns the key ID advertised in the `keyid` signature parameter.
+    fn key_id(&self) -> &str;
+
+    /// Returns the signature algorithm identifier.
+    ///
+    /// Defaults to [`ED25519_ALGORITHM`], the only algorithm used by Open Payments. Other
+    /// algorithms are declared in the `alg` signature parameter, which validation rejects
+    /// unless it is [`ED25519_ALGORITHM`].
+    fn algorithm(&self) -> &str {
+        ED25519_ALGORITHM
+    }
+
+    /// Signs the signature base and returns the raw signature bytes.
+    async fn sign(&self, data: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// In-memory Ed25519 signer.
+///
+/// The signing key is kept private to the signer; only the verifying key can be read back.
+pub struct Ed25519Signer {
+    key_id: String,
+    signing_key: SigningKey,
+}
+
+impl Ed25519Signer {
+    /// Creates a signer for the given key ID and signing key.
+    pub fn new(key_id: impl Into<String>, signing_key: SigningKey) -> Self {
+        Self {
+            key_id: key_id.into(),
+            signing_key,
+        }
+    }
+
+    /// Returns the public key matching this signer.
+    pub fn verifying_key(&self) -> VerifyingKey {
+        self.signing_key.verifying_key()
+    }
+}
+
+impl std::fmt::Debug for Ed25519Signer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Ed25519Signer")
+            .field("key_id", &self.key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl HttpMessageSigner for Ed25519Signer {
+    fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    async fn sign(&self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(self.signing_key.sign(data).to_bytes().to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signature, Verifier};
+    use rand::rngs::OsRng;
+
+    #[tokio::test]
+    async fn test_ed25519_signer_signs_verifiable_data() {
+        let signer = Ed25519Signer::new("test-key", SigningKey::generate(&mut OsRng));
+        assert_eq!(signer.key_id(), "test-key");
+        assert_eq!(signer.algorithm(), ED25519_ALGORITHM);
+
+        let signature = signer.sign(b"data").await.unwrap();
+        let signature = Signature::from_slice(&signature).unwrap();
+        assert!(signer.verifying_key().verify(b"data", &signature).is_ok());
+    }
+
+    #[test]
+    fn test_debug_does_not_expose_key() {
+        let signer = Ed25519Signer::new("test-key", SigningKey::generate(&mut OsRng));
+        let debug = format!("{signer:?}");
+        assert!(debug.contains("test-key"));
+        assert!(!debug.contains("signing_key"));
+    }
+}
@@ -0,0 +1,712 @@
+//! # Structured Field Values
+//!
+//! Parsing and serialization of [RFC 8941](https://www.rfc-editor.org/rfc/rfc8941)
+//! structured field values, as used by the `Signature-Input`, `Signature` and
+//! `Content-Digest` headers.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use open_payments::http_signature::structured_field::{parse_dictionary, serialize_dictionary};
+//!
+//! let dictionary = parse_dictionary(r#"sig1=("@method" "@target-uri");created=1618884473"#).unwrap();
+//! assert_eq!(dictionary.len(), 1);
+//! assert_eq!(
+//!     serialize_dictionary(&dictionary).unwrap(),
+//!     r#"sig1=("@method" "@target-uri");created=1618884473"#
+//! );
+//! ```
+use crate::http_signature::{HttpSignatureError, Result};
+use base64::{
+    alphabet,
+    engine::{general_purpose::STANDARD, DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+    Engine,
+};
+
+/// Base64 engine accepting byte sequences with or without padding, as RFC 8941 recommends.
+const BYTE_SEQUENCE_ENGINE: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+const MAX_INTEGER: i64 = 999_999_999_999_999;
+
+/// A bare item value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BareItem {
+    Integer(i64),
+    Decimal(f64),
+    String(String),
+    Token(String),
+    ByteSequence(Vec<u8>),
+    Boolean(bool),
+}
+
+impl BareItem {
+    /// Returns the integer value, if this is an integer.
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            BareItem::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the string value, if this is a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            BareItem::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the token value, if this is a token.
+    pub fn as_token(&self) -> Option<&str> {
+        match self {
+            BareItem::Token(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the decoded bytes, if this is a byte sequence.
+    pub fn as_byte_sequence(&self) -> Option<&[u8]> {
+        match self {
+            BareItem::ByteSequence(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// Ordered parameters attached to an item or inner list.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Parameters(pub Vec<(String, BareItem)>);
+
+impl Parameters {
+    /// Returns the value of the parameter with the given key.
+    pub fn get(&self, key: &str) -> Option<&BareItem> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Sets a parameter, replacing an existing value with the same key in place.
+    pub fn insert(&mut self, key: impl Into<String>, value: BareItem) {
+        let key = key.into();
+        match self.0.iter_mut().find(|(k, _)| *k == key) {
+            Some(entry) => entry.1 = value,
+            None => self.0.push((key, value)),
+        }
+    }
+
+    /// Returns `true` if there are no parameters.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterates over the parameters in order.
+    pub fn iter(&self) -> impl Iterator<Item = &(String, BareItem)> {
+        self.0.iter()
+    }
+}
+
+/// An item with its parameters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    pub bare_item: BareItem,
+    pub params: Parameters,
+}
+
+impl Item {
+    /// Creates an item without parameters.
+    pub fn new(bare_item: BareItem) -> Self {
+        Self {
+            bare_item,
+            params: Parameters::default(),
+        }
+    }
+}
+
+/// An inner list with its parameters.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InnerList {
+    pub items: Vec<Item>,
+    pub params: Parameters,
+}
+
+/// A member of a list or dictionary.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ListEntry {
+    Item(Item),
+    InnerList(InnerList),
+}
+
+/// An ordered dictionary.
+pub type Dictionary = Vec<(String, ListEntry)>;
+
+/// Parses a structured field dictionary.
+pub fn parse_dictionary(input: &str) -> Result<Dictionary> {
+    let mut parser = Parser::new(input)?;
+    let mut dictionary: Dictionary = Vec::new();
+    while !parser.is_empty() {
+        let key = parser.parse_key()?;
+        let member = if parser.peek() == Some(b'=') {
+            parser.advance();
+            parser.parse_list_entry()?
+        } else {
+            ListEntry::Item(Item {
+                bare_item: BareItem::Boolean(true),
+                params: parser.parse_parameters()?,
+            })
+        };
+        match dictionary.iter_mut().find(|(k, _)| *k == key) {
+            Some(entry) => entry.1 = member,
+            None => dictionary.push((key, member)),
+        }
+        if !parser.parse_member_separator()? {
+            break;
+        }
+    }
+    parser.finish()?;
+    Ok(dictionary)
+}
+
+/// Parses a structured field list.
+pub fn parse_list(input: &str) -> Result<Vec<ListEntry>> {
+    let mut parser = Parser::new(input)?;
+    let mut list = Vec::new();
+    while !parser.is_empty() {
+        list.push(parser.parse_list_entry()?);
+        if !parser.parse_member_separator()? {
+            break;
+        }
+    }
+    parser.finish()?;
+    Ok(list)
+}
+
+/// Parses a structured field item.
+pub fn parse_item(input: &str) -> Result<Item> {
+    let mut parser = Parser::new(input)?;
+    let item = parser.parse_item()?;
+    parser.finish()?;
+    Ok(item)
+}
+
+/// Serializes a bare item.
+pub fn serialize_bare_item(bare_item: &BareItem) -> Result<String> {
+    match bare_item {
+        BareItem::Integer(value) => {
+            if value.abs() > MAX_INTEGER {
+                return Err(error("integer out of range"));
+            }
+            Ok(value.to_string())
+        }
+        BareItem::Decimal(value) => serialize_decimal(*value),
+        BareItem::String(value) => {
+            let mut out = String::with_capacity(value.len() + 2);
+            out.push('"');
+            for c in value.chars() {
+                if !(' '..='~').contains(&c) {
+                    return Err(error("string contains a non-printable character"));
+                }
+                if c == '"' || c == '\\' {
+                    out.push('\\');
+                }
+                out.push(c);
+            }
+            out.push('"');
+            Ok(out)
+        }
+        BareItem::Token(value) => {
+            let mut bytes = value.bytes();
+            let valid_start = bytes
+                .next()
+                .is_some_and(|b| b.is_ascii_alphabetic() || b == b'*');
+            if !valid_start || !bytes.all(|b| is_tchar(b) || b == b':' || b == b'/') {
+                return Err(error("invalid token"));
+            }
+            Ok(value.clone())
+        }
+        BareItem::ByteSequence(value) => Ok(format!(":{}:", STANDARD.encode(value))),
+        BareItem::Boolean(value) => Ok(if *value { "?1" } else { "?0" }.to_string()),
+    }
+}
+
+/// Serializes parameters, including their leading `;`.
+pub fn serialize_parameters(params: &Parameters) -> Result<String> {
+    let mut out = String::new();
+    for (key, value) in params.iter() {
+        out.push(';');
+        out.push_str(&serialize_key(key)?);
+        if *value != BareItem::Boolean(true) {
+            out.push('=');
+            out.push_str(&serialize_bare_item(value)?);
+        }
+    }
+    Ok(out)
+}
+
+/// Serializes an item with its parameters.
+pub fn serialize_item(item: &Item) -> Result<String> {
+    Ok(format!(
+        "{}{}",
+        serialize_bare_item(&item.bare_item)?,
+        serialize_parameters(&item.params)?
+    ))
+}
+
+/// Serializes an inner list with its parameters.
+pub fn serialize_inner_list(inner_list: &InnerList) -> Result<String> {
+    let items = inner_list
+        .items
+        .iter()
+        .map(serialize_item)
+        .collect::<Result<Vec<_>>>()?;
+    Ok(format!(
+        "({}){}",
+        items.join(" "),
+        serialize_parameters(&inner_list.params)?
+    ))
+}
+
+/// Serializes a list member.
+pub fn serialize_list_entry(entry: &ListEntry) -> Result<String> {
+    match entry {
+        ListEntry::Item(item) => serialize_item(item),
+        ListEntry::InnerList(inner_list) => serialize_inner_list(inner_list),
+    }
+}
+
+/// Serializes a list.
+pub fn serialize_list(list: &[ListEntry]) -> Result<String> {
+    let members = list
+        .iter()
+        .map(serialize_list_entry)
+        .collect::<Result<Vec<_>>>()?;
+    Ok(members.join(", "))
+}
+
+/// Serializes a dictionary.
+pub fn serialize_dictionary(dictionary: &[(String, ListEntry)]) -> Result<String> {
+    let members = dictionary
+        .iter()
+        .map(|(key, member)| {
+            let key = serialize_key(key)?;
+            match member {
+                ListEntry::Item(Item {
+                    bare_item: BareItem::Boolean(true),
+                    params,
+                }) => Ok(format!("{key}{}", serialize_parameters(params)?)),
+                _ => Ok(format!("{key}={}", serialize_list_entry(member)?)),
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(members.join(", "))
+}
+
+fn serialize_key(key: &str) -> Result<String> {
+    let mut bytes = key.bytes();
+    let valid_start = bytes
+        .next()
+        .is_some_and(|b| b.is_ascii_lowercase() || b == b'*');
+    if !valid_start || !bytes.all(is_key_char) {
+        return Err(error(format!("invalid key {key:?}")));
+    }
+    Ok(key.to_string())
+}
+
+fn serialize_decimal(value: f64) -> Result<String> {
+    if !value.is_finite() {
+        return Err(error("decimal is not finite"));
+    }
+    let rounded = (value * 1000.0).round_ties_even() / 1000.0;
+    if rounded.trunc().abs() >= 1e12 {
+        return Err(error("decimal out of range"));
+    }
+    let formatted = format!("{rounded:.3}");
+    let trimmed = formatted.trim_end_matches('0');
+    Ok(if trimmed.ends_with('.') {
+        format!("{trimmed}0")
+    } else {
+        trimmed.to_string()
+    })
+}
+
+fn error(reason: impl Into<String>) -> HttpSignatureError {
+    HttpSignatureError::StructuredField(reason.into())
+}
+
+fn is_tchar(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
+
+fn is_key_char(b: u8) -> bool {
+    b.is_ascii_lowercase() || b.is_ascii_digit() || b"_-.*".contains(&b)
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Result<Self> {
+        if !input.is_ascii() {
+            return Err(error("input contains non-ASCII characters"));
+        }
+        let input = input.trim_matches(' ').as_bytes();
+        Ok(Self { input, pos: 0 })
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn advance(&mut self) {
+        self.pos += 1;
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.input.len()
+    }
+
+    fn skip_sp(&mut self) {
+        while self.peek() == Some(b' ') {
+            self.advance();
+        }
+    }
+
+    fn skip_ows(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t')) {
+            self.advance();
+        }
+    }
+
+    fn finish(&self) -> Result<()> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(error(format!(
+                "unexpected character at position {}",
+                self.pos
+            )))
+        }
+    }
+
+    /// Consumes the separator between members. Returns `false` at the end of input.
+    fn parse_member_separator(&mut self) -> Result<bool> {
+        self.skip_ows();
+        if self.is_empty() {
+            return Ok(false);
+        }
+        if self.peek() != Some(b',') {
+            return Err(error(format!("expected ',' at position {}", self.pos)));
+        }
+        self.advance();
+        self.skip_ows();
+        if self.is_empty() {
+            return Err(error("trailing comma"));
+        }
+        Ok(true)
+    }
+
+    fn parse_list_entry(&mut self) -> Result<ListEntry> {
+        if self.peek() == Some(b'(') {
+            Ok(ListEntry::InnerList(self.parse_inner_list()?))
+        } else {
+            Ok(ListEntry::Item(self.parse_item()?))
+        }
+    }
+
+    fn parse_inner_list(&mut self) -> Result<InnerList> {
+        self.advance();
+        let mut items = Vec::new();
+        loop {
+            self.skip_sp();
+            match self.peek() {
+                Some(b')') => {
+                    self.advance();
+                    let params = self.parse_parameters()?;
+                    return Ok(InnerList { items, params });
+                }
+                Some(_) => {
+                    items.push(self.parse_item()?);
+                    if !matches!(self.peek(), Some(b' ' | b')')) {
+                        return Err(error(format!(
+                            "expected ' ' or ')' at position {}",
+                            self.pos
+                        )));
+                    }
+                }
+                None => return Err(error("unterminated inner list")),
+            }
+        }
+    }
+
+    fn parse_item(&mut self) -> Result<Item> {
+        let bare_item = self.parse_bare_item()?;
+        let params = self.parse_parameters()?;
+        Ok(Item { bare_item, params })
+    }
+
+    fn parse_parameters(&mut self) -> Result<Parameters> {
+        let mut params = Parameters::default();
+        while self.peek() == Some(b';') {
+            self.advance();
+            self.skip_sp();
+            let key = self.parse_key()?;
+            let value = if self.peek() == Some(b'=') {
+                self.advance();
+                self.parse_bare_item()?
+            } else {
+                BareItem::Boolean(true)
+            };
+            params.insert(key, value);
+        }
+        Ok(params)
+    }
+
+    fn parse_key(&mut self) -> Result<String> {
+        let start = self.pos;
+        match self.peek() {
+            Some(b) if b.is_ascii_lowercase() || b == b'*' => self.advance(),
+            _ => return Err(error(format!("expected key at position {}", self.pos))),
+        }
+        while self.peek().is_some_and(is_key_char) {
+            self.advance();
+        }
+        Ok(self.slice(start))
+    }
+
+    fn parse_bare_item(&mut self) -> Result<BareItem> {
+        match self.peek() {
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            Some(b'"') => self.parse_string(),
+            Some(b':') => self.parse_byte_sequence(),
+            Some(b'?') => self.parse_boolean(),
+            Some(b) if b.is_ascii_alphabetic() || b == b'*' => self.parse_token(),
+            Some(_) => Err(error(format!(
+                "unexpected character at position {}",
+                self.pos
+            ))),
+            None => Err(error("unexpected end of input")),
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<BareItem> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.advance();
+        }
+        if !self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            return Err(error(format!("expected digit at position {}", self.pos)));
+        }
+        let mut is_decimal = false;
+        let mut integer_digits = 0;
+        let mut fraction_digits = 0;
+        while let Some(b) = self.peek() {
+            if b.is_ascii_digit() {
+                if is_decimal {
+                    fraction_digits += 1;
+                } else {
+                    integer_digits += 1;
+                }
+            } else if b == b'.' && !is_decimal {
+                if integer_digits > 12 {
+                    return Err(error("decimal has too many integer digits"));
+                }
+                is_decimal = true;
+            } else {
+                break;
+            }
+            self.advance();
+        }
+        let number = self.slice(start);
+        if is_decimal {
+            if fraction_digits == 0 || fraction_digits > 3 {
+                return Err(error("decimal has an invalid fraction"));
+            }
+            number
+                .parse::<f64>()
+                .map(BareItem::Decimal)
+                .map_err(|e| error(e.to_string()))
+        } else {
+            if integer_digits > 15 {
+                return Err(error("integer has too many digits"));
+            }
+            number
+                .parse::<i64>()
+                .map(BareItem::Integer)
+                .map_err(|e| error(e.to_string()))
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<BareItem> {
+        self.advance();
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                Some(b'\\') => {
+                    self.advance();
+                    match self.peek() {
+                        Some(c @ (b'"' | b'\\')) => value.push(c as char),
+                        _ => return Err(error("invalid escape in string")),
+                    }
+                }
+                Some(b'"') => {
+                    self.advance();
+                    return Ok(BareItem::String(value));
+                }
+                Some(c @ 0x20..=0x7e) => value.push(c as char),
+                Some(_) => return Err(error("string contains a non-printable character")),
+                None => return Err(error("unterminated string")),
+            }
+            self.advance();
+        }
+    }
+
+    fn parse_token(&mut self) -> Result<BareItem> {
+        let start = self.pos;
+        self.advance();
+        while self
+            .peek()
+            .is_some_and(|b| is_tchar(b) || b == b':' || b == b'/')
+        {
+            self.advance();
+        }
+        Ok(BareItem::Token(self.slice(start)))
+    }
+
+    fn parse_byte_sequence(&mut self) -> Result<BareItem> {
+        self.advance();
+        let start = self.pos;
+        while let Some(b) = self.peek() {
+            if b == b':' {
+                let encoded = self.slice(start);
+                self.advance();
+                return BYTE_SEQUENCE_ENGINE
+                    .decode(encoded)
+                    .map(BareItem::ByteSequence)
+                    .map_err(|e| error(format!("invalid byte sequence: {e}")));
+            }
+            if !(b.is_ascii_alphanumeric() || b"+/=".contains(&b)) {
+                return Err(error("byte sequence contains an invalid character"));
+            }
+            self.advance();
+        }
+        Err(error("unterminated byte sequence"))
+    }
+
+    fn parse_boolean(&mut self) -> Result<BareItem> {
+        self.advance();
+        let value = match self.peek() {
+            Some(b'1') => true,
+            Some(b'0') => false,
+            _ => return Err(error("invalid boolean")),
+        };
+        self.advance();
+        Ok(BareItem::Boolean(value))
+    }
+
+    fn slice(&self, start: usize) -> String {
+        // The input is checked to be ASCII, so every byte is a complete character
+        String::from_utf8_lossy(&self.input[start..self.pos]).into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_signature_input_dictionary() {
+        let dictionary = parse_dictionary(
+            r#"sig1=("@method" "content-digest";sf "@query-param";name="id");created=1618884473;keyid="test-key-ed25519", sig2=("@status");alg="ed25519""#,
+        )
+        .unwrap();
+        assert_eq!(dictionary.len(), 2);
+
+        let ListEntry::InnerList(inner_list) = &dictionary[0].1 else {
+            panic!("expected inner list");
+        };
+        assert_eq!(inner_list.items.len(), 3);
+        assert_eq!(inner_list.items[0].bare_item.as_str(), Some("@method"));
+        assert_eq!(
+            inner_list.items[1].params.get("sf"),
+            Some(&BareItem::Boolean(true))
+        );
+        assert_eq!(
+            inner_list.items[2].params.get("name"),
+            Some(&BareItem::String("id".into()))
+        );
+        assert_eq!(
+            inner_list.params.get("created"),
+            Some(&BareItem::Integer(1618884473))
+        );
+        assert_eq!(dictionary[1].0, "sig2");
+    }
+
+    #[test]
+    fn test_dictionary_roundtrip() {
+        let input = r#"sig1=("@method" "@target-uri");created=1618884473;keyid="k", sig2=:aGVsbG8=:, flag;a=?0, dec=1.5"#;
+        let dictionary = parse_dictionary(input).unwrap();
+        assert_eq!(serialize_dictionary(&dictionary).unwrap(), input);
+    }
+
+    #[test]
+    fn test_byte_sequence_decoding() {
+        let dictionary = parse_dictionary("sig1=:aGVsbG8=:").unwrap();
+        let ListEntry::Item(item) = &dictionary[0].1 else {
+            panic!("expected item");
+        };
+        assert_eq!(item.bare_item.as_byte_sequence(), Some(&b"hello"[..]));
+    }
+
+    #[test]
+    fn test_duplicate_keys_keep_last_value() {
+        let dictionary = parse_dictionary("a=1, b=2, a=3").unwrap();
+        assert_eq!(dictionary.len(), 2);
+        assert_eq!(dictionary[0].0, "a");
+        assert_eq!(
+            dictionary[0].1,
+            ListEntry::Item(Item::new(BareItem::Integer(3)))
+        );
+    }
+
+    #[test]
+    fn test_parse_list_and_item() {
+        let list = parse_list("sha-256, (a b);x=1, ?1").unwrap();
+        assert_eq!(list.len(), 3);
+        assert_eq!(serialize_list(&list).unwrap(), "sha-256, (a b);x=1, ?1");
+
+        let item = parse_item(r#""quoted \"value\"";p=tok"#).unwrap();
+        assert_eq!(item.bare_item.as_str(), Some(r#"quoted "value""#));
+        assert_eq!(item.params.get("p"), Some(&BareItem::Token("tok".into())));
+    }
+
+    #[test]
+    fn test_rejects_malformed_input() {
+        for input in [
+            "Sig1=1",
+            "sig1=(\"@method\"",
+            "sig1=\"unterminated",
+            "sig1=:not base64:",
+            "sig1=1,",
+            "sig1=1 sig2=2",
+            "sig1=(@method)",
+            "sig1=1234567890123456",
+            "sig1=:AAAAA:",
+        ] {
+            assert!(
+                parse_dictionary(input).is_err(),
+                "{input} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_serialize_decimal() {
+        assert_eq!(serialize_bare_item(&BareItem::Decimal(1.0)).unwrap(), "1.0");
+        assert_eq!(
+            serialize_bare_item(&BareItem::Decimal(-0.1235)).unwrap(),
+            "-0.124"
+        );
+    }
+}
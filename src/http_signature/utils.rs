@@ -7,34 +7,47 @@ use rand::rngs::OsRng;
 use std::fs;
 use std::path::Path;
 
+/// Parses an Ed25519 signing key from PEM content.
+///
+/// The content may be a PKCS8 `PRIVATE KEY` PEM block, either as plain text or
+/// base64 encoded. No file system access is performed.
+pub fn parse_private_key(content: &str) -> Result<SigningKey> {
+    let without_bom = content.trim_start_matches('\u{feff}');
+    let without_cr = without_bom.replace('\r', "");
+    let trimmed = without_cr.trim();
+
+    let key_str = if let Ok(decoded) = STANDARD.decode(trimmed) {
+        String::from_utf8(decoded)?
+    } else {
+        trimmed.to_string()
+    };
+
+    let pem = parse(&key_str).map_err(|e| HttpSignatureError::Pem(e.to_string()))?;
+    if pem.tag() != "PRIVATE KEY" {
+        return Err(HttpSignatureError::Pem("Not a PRIVATE KEY".to_string()));
+    }
+
+    let private_key_info = PrivateKeyInfo::from_der(pem.contents())
+        .map_err(|e| HttpSignatureError::Pkcs8(e.to_string()))?;
+    let raw = private_key_info.private_key;
+
+    let raw_private_key: [u8; 32] = raw
+        .get(2..)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(HttpSignatureError::InvalidPrivateKeyLength)?;
+
+    Ok(SigningKey::from_bytes(&raw_private_key))
+}
+
+/// Loads an Ed25519 signing key from a PEM file without ever writing to disk.
+pub fn load_key(path: &Path) -> Result<SigningKey> {
+    let file_content = fs::read_to_string(path)?;
+    parse_private_key(&file_content)
+}
+
 pub fn load_or_generate_key(path: &Path) -> Result<SigningKey> {
     if path.exists() {
-        let file_content = fs::read_to_string(path)?;
-        let without_bom = file_content.trim_start_matches('\u{feff}');
-        let without_cr = without_bom.replace('\r', "");
-        let trimmed = without_cr.trim();
-
-        let key_str = if let Ok(decoded) = STANDARD.decode(trimmed) {
-            String::from_utf8(decoded)?
-        } else {
-            trimmed.to_string()
-        };
-
-        let pem = parse(&key_str).map_err(|e| HttpSignatureError::Pem(e.to_string()))?;
-        if pem.tag() != "PRIVATE KEY" {
-            return Err(HttpSignatureError::Pem("Not a PRIVATE KEY".to_string()));
-        }
-
-        let private_key_info = PrivateKeyInfo::from_der(pem.contents())
-            .map_err(|e| HttpSignatureError::Pkcs8(e.to_string()))?;
-        let raw = private_key_info.private_key;
-
-        let raw_private_key: [u8; 32] = raw[2..]
-            .try_into()
-            .map_err(|_| HttpSignatureError::InvalidPrivateKeyLength)?;
-
-        let signing_key = SigningKey::from_bytes(&raw_private_key);
-        Ok(signing_key)
+        load_key(path)
     } else {
         // Generate new key
         let mut csprng = OsRng;
@@ -188,4 +201,25 @@ mod tests {
         let result = load_or_generate_key(&path);
         assert!(matches!(result, Err(HttpSignatureError::Utf8(_))));
     }
+
+    #[test]
+    fn test_parse_private_key_from_memory() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("test_key.pem");
+        let original_key = load_or_generate_key(&path).unwrap();
+
+        let pem_content = fs::read_to_string(&path).unwrap();
+        let parsed_key = parse_private_key(&pem_content).unwrap();
+        assert_eq!(original_key.to_bytes(), parsed_key.to_bytes());
+    }
+
+    #[test]
+    fn test_load_key_does_not_create_file() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("missing.pem");
+
+        let result = load_key(&path);
+        assert!(matches!(result, Err(HttpSignatureError::Io(_))));
+        assert!(!path.exists());
+    }
 }
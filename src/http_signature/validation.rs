@@ -1,12 +1,23 @@
+use crate::http_signature::components::{create_signature_base, HttpMessage};
+use crate::http_signature::content_digest::verify_content_digest;
 use crate::http_signature::error::{HttpSignatureError, Result};
-use base64::{engine::general_purpose::STANDARD, Engine};
+use crate::http_signature::policy::ValidationPolicy;
+use crate::http_signature::signatures::SignatureParams;
+use crate::http_signature::signer::ED25519_ALGORITHM;
+use crate::http_signature::structured_field::{parse_dictionary, BareItem, Dictionary, ListEntry};
 use ed25519_dalek::{Signature, Verifier, VerifyingKey};
-use http::{HeaderMap, Request};
+use http::{HeaderMap, Request, Response};
 
 pub struct ValidationOptions<'a> {
     pub request: &'a Request<Option<String>>,
     pub headers: &'a HeaderMap,
     pub public_key: &'a VerifyingKey,
+    /// Label of the signature to validate. Defaults to the first signature in `Signature-Input`.
+    pub label: Option<&'a str>,
+    /// Response to `request` whose signature is validated instead of the request's.
+    pub response: Option<&'a Response<Option<String>>>,
+    /// Checks applied in addition to verifying the signature.
+    pub policy: ValidationPolicy,
 }
 
 impl ValidationOptions<'_> {
@@ -19,109 +30,266 @@ impl ValidationOptions<'_> {
             request,
             headers,
             public_key,
+            label: None,
+            response: None,
+            policy: ValidationPolicy::default(),
         }
     }
 }
 
-fn create_signature_base_string(
-    request: &Request<Option<String>>,
-    components: &[&str],
-    created: i64,
-    keyid: &str,
-) -> String {
-    let mut parts = Vec::new();
-
-    for component in components {
-        let value = match *component {
-            "@method" => request.method().as_str(),
-            "@target-uri" => &request.uri().to_string(),
-            "authorization" => request
-                .headers()
-                .get("Authorization")
-                .and_then(|v| v.to_str().ok())
-                .unwrap_or(""),
-            "content-digest" => request
-                .headers()
-                .get("Content-Digest")
-                .and_then(|v| v.to_str().ok())
-                .unwrap_or(""),
-            "content-length" => request
-                .headers()
-                .get("Content-Length")
-                .and_then(|v| v.to_str().ok())
-                .unwrap_or(""),
-            "content-type" => request
-                .headers()
-                .get("Content-Type")
-                .and_then(|v| v.to_str().ok())
-                .unwrap_or(""),
-            _ => "",
-        };
-        parts.push(format!("\"{component}\": {value}"));
+impl<'a> ValidationOptions<'a> {
+    /// Selects the signature with the given label instead of the first one.
+    pub fn with_label(mut self, label: &'a str) -> Self {
+        self.label = Some(label);
+        self
     }
 
-    let sig_params = format!(
-        "({});created={};keyid=\"{}\"",
-        components.join(" "),
-        created,
-        keyid
-    );
-    parts.push(format!("\"@signature-params\": {sig_params}"));
+    /// Validates the signature of a response to `request`.
+    ///
+    /// Components such as `@status` are resolved from the response, and components with
+    /// the `req` parameter from the request.
+    pub fn with_response(mut self, response: &'a Response<Option<String>>) -> Self {
+        self.response = Some(response);
+        self
+    }
+
+    /// Sets the policy applied in addition to verifying the signature.
+    pub fn with_policy(mut self, policy: ValidationPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Returns the message whose signature is validated.
+    pub fn message(&self) -> HttpMessage<'a> {
+        match self.response {
+            Some(response) => HttpMessage::Response {
+                response,
+                request: Some(self.request),
+            },
+            None => HttpMessage::Request(self.request),
+        }
+    }
+}
+
+/// Parses a signature dictionary header, combining multiple field lines.
+///
+/// Returns `None` if the header is absent.
+pub(crate) fn parse_signature_header(
+    headers: &HeaderMap,
+    name: &str,
+) -> Result<Option<Dictionary>> {
+    let malformed = |reason: String| HttpSignatureError::MalformedHeader {
+        header: name.to_string(),
+        reason,
+    };
+
+    let mut lines = Vec::new();
+    for value in headers.get_all(name) {
+        lines.push(
+            value
+                .to_str()
+                .map_err(|_| malformed("contains non-visible characters".to_string()))?,
+        );
+    }
+    if lines.is_empty() {
+        return Ok(None);
+    }
+
+    parse_dictionary(&lines.join(", "))
+        .map(Some)
+        .map_err(|e| match e {
+            HttpSignatureError::StructuredField(reason) => malformed(reason),
+            e => e,
+        })
+}
+
+/// Parses a `Signature-Input` header value into its labelled signature parameters.
+///
+/// Every member is parsed and type checked, in header order.
+pub fn parse_signature_input(signature_input: &str) -> Result<Vec<(String, SignatureParams)>> {
+    let dictionary =
+        parse_dictionary(signature_input).map_err(|e| HttpSignatureError::MalformedHeader {
+            header: "Signature-Input".to_string(),
+            reason: e.to_string(),
+        })?;
+    signature_params(&dictionary)
+}
 
-    parts.join("\n")
+pub(crate) fn signature_params(dictionary: &Dictionary) -> Result<Vec<(String, SignatureParams)>> {
+    dictionary
+        .iter()
+        .map(|(label, member)| match member {
+            ListEntry::InnerList(inner_list) => {
+                Ok((label.clone(), SignatureParams::from_inner_list(inner_list)?))
+            }
+            ListEntry::Item(_) => Err(HttpSignatureError::MalformedHeader {
+                header: "Signature-Input".to_string(),
+                reason: format!("member {label:?} is not an inner list"),
+            }),
+        })
+        .collect()
 }
 
-fn parse_signature_input(signature_input: &str) -> Result<(Vec<&str>, i64, String)> {
-    let mut components = Vec::new();
-    let mut created = None;
-    let mut keyid = None;
-
-    // Remove the sig1= prefix if present
-    let signature_input = signature_input
-        .strip_prefix("sig1=")
-        .unwrap_or(signature_input);
-
-    for part in signature_input.split(';') {
-        if let Some(inner) = part.strip_prefix('(').and_then(|p| p.strip_suffix(')')) {
-            components = inner.split(' ').map(|s| s.trim()).collect();
-        } else if let Some(value) = part.strip_prefix("created=") {
-            created = value.parse::<i64>().ok();
-        } else if let Some(value) = part.strip_prefix("keyid=") {
-            keyid = Some(value.trim_matches('"').to_string());
+/// Checks the parameters of a selected signature.
+fn check_params(params: &SignatureParams) -> Result<()> {
+    let created = params
+        .created()
+        .ok_or_else(|| HttpSignatureError::MissingParameter("created".to_string()))?;
+    if params.keyid().is_none() {
+        return Err(HttpSignatureError::MissingParameter("keyid".to_string()));
+    }
+
+    if let Some(expires) = params.expires() {
+        if expires < created {
+            return Err(HttpSignatureError::InvalidParameter {
+                name: "expires".to_string(),
+                reason: "expires before created".to_string(),
+            });
+        }
+        if expires < chrono::Utc::now().timestamp() {
+            return Err(HttpSignatureError::InvalidParameter {
+                name: "expires".to_string(),
+                reason: "signature has expired".to_string(),
+            });
         }
     }
 
-    let created = created
-        .ok_or_else(|| HttpSignatureError::Validation("Missing created field".to_string()))?;
-    let keyid =
-        keyid.ok_or_else(|| HttpSignatureError::Validation("Missing keyid field".to_string()))?;
+    if let Some(alg) = params.alg() {
+        if alg != ED25519_ALGORITHM {
+            return Err(HttpSignatureError::InvalidParameter {
+                name: "alg".to_string(),
+                reason: format!("unsupported algorithm {alg:?}"),
+            });
+        }
+    }
 
-    Ok((components, created, keyid))
+    Ok(())
+}
+
+fn duration_secs(duration: std::time::Duration) -> i64 {
+    i64::try_from(duration.as_secs()).unwrap_or(i64::MAX)
 }
 
+/// Returns `true` if the signature covers the whole component with the given name.
+fn covers(params: &SignatureParams, name: &str) -> bool {
+    params.components().iter().any(|component| {
+        component.bare_item.as_str() == Some(name)
+            && ["key", "name", "req"]
+                .iter()
+                .all(|param| component.params.get(param).is_none())
+    })
+}
+
+/// Checks a selected signature against the validation policy.
+///
+/// Returns the Unix timestamp until which the signature's nonce must be remembered.
+fn check_policy(
+    params: &SignatureParams,
+    message: &HttpMessage<'_>,
+    policy: &ValidationPolicy,
+) -> Result<i64> {
+    let now = chrono::Utc::now().timestamp();
+    // `check_params` guarantees that `created` is present
+    let created = params.created().unwrap_or_default();
+
+    if created > now.saturating_add(duration_secs(policy.future_skew)) {
+        return Err(HttpSignatureError::InvalidParameter {
+            name: "created".to_string(),
+            reason: "signature was created in the future".to_string(),
+        });
+    }
+    let mut valid_until = i64::MAX;
+    if let Some(max_age) = policy.max_age {
+        valid_until = created.saturating_add(duration_secs(max_age));
+        if valid_until < now {
+            return Err(HttpSignatureError::InvalidParameter {
+                name: "created".to_string(),
+                reason: "signature is too old".to_string(),
+            });
+        }
+    }
+    match params.expires() {
+        Some(expires) => valid_until = valid_until.min(expires),
+        None if policy.require_expires => {
+            return Err(HttpSignatureError::MissingParameter("expires".to_string()))
+        }
+        None => {}
+    }
+
+    let with_body = message.body().is_some_and(|body| !body.is_empty());
+    let required = policy.required_components.iter().chain(
+        policy
+            .required_components_with_body
+            .iter()
+            .filter(|_| with_body),
+    );
+    let present = policy
+        .required_fields_if_present
+        .iter()
+        .filter(|name| message.headers().contains_key(name.as_str()));
+    if let Some(name) = required.chain(present).find(|name| !covers(params, name)) {
+        return Err(HttpSignatureError::UncoveredComponent(name.clone()));
+    }
+
+    if policy.nonce_store.is_some() && params.nonce().is_none() {
+        return Err(HttpSignatureError::MissingParameter("nonce".to_string()));
+    }
+
+    Ok(valid_until)
+}
+
+/// Validates an Ed25519 HTTP message signature.
+///
+/// The `Signature-Input` and `Signature` headers are parsed as RFC 8941 dictionaries.
+/// The signature selected by [`ValidationOptions::label`], or the first signature if no
+/// label is set, must declare `created` and `keyid`. If present, `expires` must not have
+/// passed and `alg` must be `ed25519`. The signature is then checked against
+/// [`ValidationOptions::policy`]. If the signature covers `content-digest`, the body of
+/// the message must match the `Content-Digest` header. The nonce is only recorded once
+/// the signature and content digest have been verified.
 pub fn validate_signature(options: ValidationOptions<'_>) -> Result<()> {
-    let signature_input = options
-        .headers
-        .get("Signature-Input")
-        .and_then(|v| v.to_str().ok())
-        .ok_or_else(|| {
+    let signature_input =
+        parse_signature_header(options.headers, "Signature-Input")?.ok_or_else(|| {
             HttpSignatureError::Validation("Missing Signature-Input header".to_string())
         })?;
 
-    let (components, created, keyid) = parse_signature_input(signature_input)?;
+    let (label, params) = {
+        let mut signatures = signature_params(&signature_input)?.into_iter();
+        match options.label {
+            Some(label) => signatures
+                .find(|(l, _)| l == label)
+                .ok_or_else(|| HttpSignatureError::SignatureNotFound(label.to_string()))?,
+            None => signatures.next().ok_or_else(|| {
+                HttpSignatureError::Validation("Missing Signature-Input header".to_string())
+            })?,
+        }
+    };
+    check_params(&params)?;
+    let message = options.message();
+    let valid_until = check_policy(&params, &message, &options.policy)?;
 
-    let signature = options
-        .headers
-        .get("Signature")
-        .and_then(|v| v.to_str().ok())
+    let signature = parse_signature_header(options.headers, "Signature")?
         .ok_or_else(|| HttpSignatureError::Validation("Missing Signature header".to_string()))?;
+    let signature_bytes = match signature.iter().find(|(l, _)| *l == label) {
+        Some((_, ListEntry::Item(item))) => match &item.bare_item {
+            BareItem::ByteSequence(bytes) => bytes.clone(),
+            _ => {
+                return Err(HttpSignatureError::MalformedHeader {
+                    header: "Signature".to_string(),
+                    reason: format!("member {label:?} is not a byte sequence"),
+                })
+            }
+        },
+        Some(_) => {
+            return Err(HttpSignatureError::MalformedHeader {
+                header: "Signature".to_string(),
+                reason: format!("member {label:?} is not a byte sequence"),
+            })
+        }
+        None => return Err(HttpSignatureError::SignatureNotFound(label)),
+    };
 
-    let signature_base =
-        create_signature_base_string(options.request, &components, created, &keyid);
-
-    let signature_bytes = STANDARD
-        .decode(signature)
-        .map_err(|_| HttpSignatureError::Validation("Base64 decode failed".to_string()))?;
+    let signature_base = create_signature_base(&message, &params)?;
 
     let signature_bytes: [u8; 64] = signature_bytes
         .try_into()
@@ -133,22 +301,47 @@ pub fn validate_signature(options: ValidationOptions<'_>) -> Result<()> {
         .verify(signature_base.as_bytes(), &signature)
         .map_err(|_| HttpSignatureError::Validation("Signature verification failed".to_string()))?;
 
+    if covers(&params, "content-digest") {
+        let content_digest = message
+            .headers()
+            .get_all("content-digest")
+            .iter()
+            .map(|value| value.to_str().unwrap_or_default())
+            .collect::<Vec<_>>()
+            .join(", ");
+        verify_content_digest(
+            message.body().unwrap_or_default().as_bytes(),
+            &content_digest,
+        )?;
+    }
+
+    if let (Some(store), Some(nonce)) = (&options.policy.nonce_store, params.nonce()) {
+        let keyid = params.keyid().unwrap_or_default();
+        let remember_until = valid_until.saturating_add(duration_secs(options.policy.future_skew));
+        if !store.insert(keyid, nonce, remember_until) {
+            return Err(HttpSignatureError::ReplayedNonce(nonce.to_string()));
+        }
+    }
+
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::http_signature::{create_signature_headers, SignOptions};
+    use crate::http_signature::structured_field::Item;
+    use crate::http_signature::{create_signature_headers, Ed25519Signer, SignOptions};
+    use base64::Engine;
     use ed25519_dalek::{SigningKey, VerifyingKey};
     use http::{HeaderMap, Method, Request, Uri};
     use rand::rngs::OsRng;
 
-    #[test]
-    fn test_signature_validation() {
+    #[tokio::test]
+    async fn test_signature_validation() {
         let mut request = Request::new(Some("test body".to_string()));
         *request.method_mut() = Method::POST;
         *request.uri_mut() = Uri::from_static("http://example.com");
+        add_content_digest(&mut request);
         request
             .headers_mut()
             .insert("Content-Type", "application/json".parse().unwrap());
@@ -156,8 +349,9 @@ mod tests {
         let signing_key = SigningKey::generate(&mut OsRng);
         let verifying_key = VerifyingKey::from(&signing_key);
 
-        let options = SignOptions::new(&request, &signing_key, "test-key".to_string());
-        let signature_headers = create_signature_headers(options).unwrap();
+        let signer = Ed25519Signer::new("test-key", signing_key);
+        let options = SignOptions::new(&request, &signer);
+        let signature_headers = create_signature_headers(options).await.unwrap();
 
         let mut headers = HeaderMap::new();
         headers.insert("Signature", signature_headers.signature.parse().unwrap());
@@ -170,8 +364,8 @@ mod tests {
         assert!(validate_signature(options).is_ok());
     }
 
-    #[test]
-    fn test_missing_signature_input_header() {
+    #[tokio::test]
+    async fn test_missing_signature_input_header() {
         let mut request = Request::new(Some("body".to_string()));
         *request.method_mut() = Method::POST;
         *request.uri_mut() = Uri::from_static("http://example.com");
@@ -190,15 +384,17 @@ mod tests {
         }
     }
 
-    #[test]
-    fn test_missing_signature_header() {
+    #[tokio::test]
+    async fn test_missing_signature_header() {
         let mut request = Request::new(Some("body".to_string()));
         *request.method_mut() = Method::POST;
         *request.uri_mut() = Uri::from_static("http://example.com");
+        add_content_digest(&mut request);
 
         let signing_key = SigningKey::generate(&mut OsRng);
-        let options = SignOptions::new(&request, &signing_key, "k".to_string());
-        let sig = create_signature_headers(options).unwrap();
+        let signer = Ed25519Signer::new("k", signing_key.clone());
+        let options = SignOptions::new(&request, &signer);
+        let sig = create_signature_headers(options).await.unwrap();
 
         let mut headers = HeaderMap::new();
         headers.insert("Signature-Input", sig.signature_input.parse().unwrap());
@@ -214,44 +410,49 @@ mod tests {
         }
     }
 
-    #[test]
-    fn test_base64_decode_failed() {
+    #[tokio::test]
+    async fn test_base64_decode_failed() {
         let mut request = Request::new(Some("body".to_string()));
         *request.method_mut() = Method::POST;
         *request.uri_mut() = Uri::from_static("http://example.com");
+        add_content_digest(&mut request);
 
         let signing_key = SigningKey::generate(&mut OsRng);
-        let options = SignOptions::new(&request, &signing_key, "k".to_string());
-        let sig = create_signature_headers(options).unwrap();
+        let signer = Ed25519Signer::new("k", signing_key.clone());
+        let options = SignOptions::new(&request, &signer);
+        let sig = create_signature_headers(options).await.unwrap();
 
         let mut headers = HeaderMap::new();
         headers.insert("Signature-Input", sig.signature_input.parse().unwrap());
-        headers.insert("Signature", "%%%".parse().unwrap());
+        headers.insert("Signature", "sig1=:%%%:".parse().unwrap());
 
         let verifying_key = VerifyingKey::from(&signing_key);
         let options = ValidationOptions::new(&request, &headers, &verifying_key);
         let err = validate_signature(options).unwrap_err();
         match err {
-            HttpSignatureError::Validation(msg) => {
-                assert_eq!(msg, "Base64 decode failed");
+            HttpSignatureError::MalformedHeader { header, reason } => {
+                assert_eq!(header, "Signature");
+                assert!(reason.contains("byte sequence"));
             }
             _ => panic!("unexpected error type"),
         }
     }
 
-    #[test]
-    fn test_invalid_signature_length() {
+    #[tokio::test]
+    async fn test_invalid_signature_length() {
         let mut request = Request::new(Some("body".to_string()));
         *request.method_mut() = Method::POST;
         *request.uri_mut() = Uri::from_static("http://example.com");
+        add_content_digest(&mut request);
 
         let signing_key = SigningKey::generate(&mut OsRng);
-        let options = SignOptions::new(&request, &signing_key, "k".to_string());
-        let sig = create_signature_headers(options).unwrap();
+        let signer = Ed25519Signer::new("k", signing_key.clone());
+        let options = SignOptions::new(&request, &signer);
+        let sig = create_signature_headers(options).await.unwrap();
 
         let mut headers = HeaderMap::new();
         headers.insert("Signature-Input", sig.signature_input.parse().unwrap());
-        headers.insert("Signature", "aGVsbG8=".parse().unwrap()); // "hello"
+        headers.insert("Signature", "sig1=:aGVsbG8=:".parse().unwrap()); // "hello"
 
         let verifying_key = VerifyingKey::from(&signing_key);
         let options = ValidationOptions::new(&request, &headers, &verifying_key);
@@ -264,11 +465,12 @@ mod tests {
         }
     }
 
-    #[test]
-    fn test_signature_verification_failed() {
+    #[tokio::test]
+    async fn test_signature_verification_failed() {
         let mut request = Request::new(Some("body".to_string()));
         *request.method_mut() = Method::POST;
         *request.uri_mut() = Uri::from_static("http://example.com");
+        add_content_digest(&mut request);
         request
             .headers_mut()
             .insert("Content-Type", "application/json".parse().unwrap());
@@ -276,8 +478,9 @@ mod tests {
         let signing_key = SigningKey::generate(&mut OsRng);
         let verifying_key = VerifyingKey::from(&signing_key);
 
-        let options = SignOptions::new(&request, &signing_key, "k".to_string());
-        let sig = create_signature_headers(options).unwrap();
+        let signer = Ed25519Signer::new("k", signing_key.clone());
+        let options = SignOptions::new(&request, &signer);
+        let sig = create_signature_headers(options).await.unwrap();
 
         // Tamper with request after signing to force verification failure
         *request.uri_mut() = Uri::from_static("http://example.com/changed");
@@ -295,4 +498,487 @@ mod tests {
             _ => panic!("unexpected error type"),
         }
     }
+
+    #[tokio::test]
+    async fn test_default_policy_requires_content_digest_with_body() {
+        let mut request = Request::new(Some("body".to_string()));
+        *request.method_mut() = Method::POST;
+        *request.uri_mut() = Uri::from_static("http://example.com");
+
+        let signing_key = SigningKey::generate(&mut OsRng);
+        let verifying_key = VerifyingKey::from(&signing_key);
+        let signer = Ed25519Signer::new("k", signing_key);
+        let sig = create_signature_headers(SignOptions::new(&request, &signer))
+            .await
+            .unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("Signature-Input", sig.signature_input.parse().unwrap());
+        headers.insert("Signature", sig.signature.parse().unwrap());
+
+        // Without a covered digest, the body could be swapped under the signature
+        let options = ValidationOptions::new(&request, &headers, &verifying_key);
+        match validate_signature(options).unwrap_err() {
+            HttpSignatureError::UncoveredComponent(name) => assert_eq!(name, "content-digest"),
+            e => panic!("unexpected error type: {e:?}"),
+        }
+        let options = ValidationOptions::new(&request, &headers, &verifying_key)
+            .with_policy(ValidationPolicy::permissive());
+        validate_signature(options).unwrap();
+    }
+
+    fn add_content_digest(request: &mut Request<Option<String>>) {
+        let digest = crate::http_signature::create_content_digest(
+            request.body().as_deref().unwrap_or_default().as_bytes(),
+            crate::http_signature::DigestAlgorithm::Sha256,
+        );
+        request
+            .headers_mut()
+            .insert("Content-Digest", digest.parse().unwrap());
+    }
+
+    fn sign_manually(
+        request: &Request<Option<String>>,
+        signing_key: &SigningKey,
+        params: &SignatureParams,
+    ) -> String {
+        use base64::{engine::general_purpose::STANDARD, Engine};
+        use ed25519_dalek::Signer;
+
+        let base = create_signature_base(&HttpMessage::Request(request), params).unwrap();
+        STANDARD.encode(signing_key.sign(base.as_bytes()).to_bytes())
+    }
+
+    fn get_request() -> Request<Option<String>> {
+        let mut request = Request::new(None);
+        *request.method_mut() = Method::GET;
+        *request.uri_mut() = Uri::from_static("https://example.com/incoming-payments");
+        request
+    }
+
+    #[test]
+    fn test_selects_signature_by_label_with_other_parameter_order() {
+        let request = get_request();
+        let signing_key = SigningKey::generate(&mut OsRng);
+        let other_key = SigningKey::generate(&mut OsRng);
+        let now = chrono::Utc::now().timestamp();
+
+        let first = SignatureParams::new(["@method"])
+            .with_keyid("other")
+            .with_created(now);
+        let second = SignatureParams::new(["@target-uri", "@method"])
+            .with_alg("ed25519")
+            .with_keyid("peer-key")
+            .with_nonce("n-1")
+            .with_tag("open-payments")
+            .with_created(now)
+            .with_expires(now + 300);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "Signature-Input",
+            format!(
+                "proxy={}, sig-b26={}",
+                first.serialize().unwrap(),
+                second.serialize().unwrap()
+            )
+            .parse()
+            .unwrap(),
+        );
+        headers.append(
+            "Signature",
+            format!("proxy=:{}:", sign_manually(&request, &other_key, &first))
+                .parse()
+                .unwrap(),
+        );
+        headers.append(
+            "Signature",
+            format!(
+                "sig-b26=:{}:",
+                sign_manually(&request, &signing_key, &second)
+            )
+            .parse()
+            .unwrap(),
+        );
+
+        let verifying_key = signing_key.verifying_key();
+        let options =
+            ValidationOptions::new(&request, &headers, &verifying_key).with_label("sig-b26");
+        validate_signature(options).unwrap();
+
+        // Without a label the first signature is used, which was made with another key
+        let options = ValidationOptions::new(&request, &headers, &verifying_key);
+        assert!(matches!(
+            validate_signature(options),
+            Err(HttpSignatureError::Validation(_))
+        ));
+
+        let options =
+            ValidationOptions::new(&request, &headers, &verifying_key).with_label("missing");
+        match validate_signature(options).unwrap_err() {
+            HttpSignatureError::SignatureNotFound(label) => assert_eq!(label, "missing"),
+            e => panic!("unexpected error type: {e:?}"),
+        }
+    }
+
+    #[test]
+    fn test_rejects_invalid_parameters() {
+        let request = get_request();
+        let signing_key = SigningKey::generate(&mut OsRng);
+        let verifying_key = signing_key.verifying_key();
+        let now = chrono::Utc::now().timestamp();
+
+        let cases = [
+            (r#"sig1=("@method");keyid="k""#.to_string(), "created"),
+            (format!(r#"sig1=("@method");created={now}"#), "keyid"),
+            (
+                format!(r#"sig1=("@method");created="{now}";keyid="k""#),
+                "created",
+            ),
+            (
+                format!(r#"sig1=("@method");created={now};keyid="k";alg="rsa-pss-sha512""#),
+                "alg",
+            ),
+            (
+                format!(
+                    r#"sig1=("@method");created={};expires={};keyid="k""#,
+                    now - 120,
+                    now - 60
+                ),
+                "expires",
+            ),
+        ];
+
+        for (signature_input, parameter) in cases {
+            let mut headers = HeaderMap::new();
+            headers.insert("Signature-Input", signature_input.parse().unwrap());
+            headers.insert("Signature", "sig1=:aGVsbG8=:".parse().unwrap());
+
+            let options = ValidationOptions::new(&request, &headers, &verifying_key);
+            match validate_signature(options).unwrap_err() {
+                HttpSignatureError::MissingParameter(name)
+                | HttpSignatureError::InvalidParameter { name, .. } => {
+                    assert_eq!(name, parameter, "{signature_input}")
+                }
+                e => panic!("unexpected error type for {signature_input}: {e:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_rejects_malformed_signature_input() {
+        let request = get_request();
+        let signing_key = SigningKey::generate(&mut OsRng);
+        let verifying_key = signing_key.verifying_key();
+
+        for signature_input in [
+            "sig1=(@method @target-uri);created=1;keyid=\"k\"",
+            "sig1=\"@method\";created=1",
+            "sig1=(\"@method\" \"@method\");created=1;keyid=\"k\"",
+        ] {
+            let mut headers = HeaderMap::new();
+            headers.insert("Signature-Input", signature_input.parse().unwrap());
+            headers.insert("Signature", "sig1=:aGVsbG8=:".parse().unwrap());
+
+            let options = ValidationOptions::new(&request, &headers, &verifying_key);
+            match validate_signature(options).unwrap_err() {
+                HttpSignatureError::MalformedHeader { header, .. } => {
+                    assert_eq!(header, "Signature-Input")
+                }
+                e => panic!("unexpected error type for {signature_input}: {e:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_signature_input() {
+        let signatures = parse_signature_input(
+            r#"sig1=("@method" "@target-uri");created=1618884473;keyid="a", sig2=("@status");keyid="b";created=1618884475"#,
+        )
+        .unwrap();
+        assert_eq!(signatures.len(), 2);
+        assert_eq!(signatures[0].0, "sig1");
+        assert_eq!(signatures[0].1.components().len(), 2);
+        assert_eq!(signatures[1].1.keyid(), Some("b"));
+        assert_eq!(
+            signatures[1].1.serialize().unwrap(),
+            r#"("@status");keyid="b";created=1618884475"#
+        );
+    }
+
+    #[test]
+    fn test_validates_response_signature() {
+        use ed25519_dalek::Signer;
+
+        let request = get_request();
+        let mut response = http::Response::new(Some("{}".to_string()));
+        *response.status_mut() = http::StatusCode::OK;
+        response
+            .headers_mut()
+            .insert("Content-Type", "application/json".parse().unwrap());
+        response.headers_mut().insert(
+            "Content-Digest",
+            crate::http_signature::create_content_digest(
+                b"{}",
+                crate::http_signature::DigestAlgorithm::Sha256,
+            )
+            .parse()
+            .unwrap(),
+        );
+
+        let signing_key = SigningKey::generate(&mut OsRng);
+        let mut method = Item::new(BareItem::String("@method".into()));
+        method.params.insert("req", BareItem::Boolean(true));
+        let params = SignatureParams::new(["@status", "content-type"])
+            .with_created(chrono::Utc::now().timestamp())
+            .with_keyid("server-key");
+        let mut inner_list = params.to_inner_list();
+        inner_list.items.push(method);
+        inner_list
+            .items
+            .push(Item::new(BareItem::String("content-digest".into())));
+        let params = SignatureParams::from_inner_list(&inner_list).unwrap();
+
+        let message = HttpMessage::Response {
+            response: &response,
+            request: Some(&request),
+        };
+        let base = create_signature_base(&message, &params).unwrap();
+        assert!(base.starts_with(
+            "\"@status\": 200\n\"content-type\": application/json\n\"@method\";req: GET\n"
+        ));
+        let signature = signing_key.sign(base.as_bytes());
+
+        let mut headers = response.headers().clone();
+        headers.insert(
+            "Signature-Input",
+            format!("sig1={}", params.serialize().unwrap())
+                .parse()
+                .unwrap(),
+        );
+        headers.insert(
+            "Signature",
+            format!(
+                "sig1=:{}:",
+                base64::engine::general_purpose::STANDARD.encode(signature.to_bytes())
+            )
+            .parse()
+            .unwrap(),
+        );
+
+        let verifying_key = signing_key.verifying_key();
+        let options =
+            ValidationOptions::new(&request, &headers, &verifying_key).with_response(&response);
+        validate_signature(options).unwrap();
+
+        // The same signature does not verify as a request signature
+        let options = ValidationOptions::new(&request, &headers, &verifying_key);
+        assert!(matches!(
+            validate_signature(options),
+            Err(HttpSignatureError::InvalidComponent { .. })
+        ));
+    }
+
+    fn signed_headers(
+        request: &Request<Option<String>>,
+        signing_key: &SigningKey,
+        params: &SignatureParams,
+    ) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "Signature-Input",
+            format!("sig1={}", params.serialize().unwrap())
+                .parse()
+                .unwrap(),
+        );
+        headers.insert(
+            "Signature",
+            format!("sig1=:{}:", sign_manually(request, signing_key, params))
+                .parse()
+                .unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_policy_rejects_old_and_future_signatures() {
+        let request = get_request();
+        let signing_key = SigningKey::generate(&mut OsRng);
+        let verifying_key = signing_key.verifying_key();
+        let now = chrono::Utc::now().timestamp();
+
+        for (created, accepted) in [(now - 600, false), (now + 600, false), (now - 60, true)] {
+            let params = SignatureParams::new(["@method"])
+                .with_created(created)
+                .with_keyid("k");
+            let headers = signed_headers(&request, &signing_key, &params);
+            let options = ValidationOptions::new(&request, &headers, &verifying_key);
+            match validate_signature(options) {
+                Ok(()) => assert!(accepted),
+                Err(HttpSignatureError::InvalidParameter { name, .. }) => {
+                    assert!(!accepted);
+                    assert_eq!(name, "created");
+                }
+                Err(e) => panic!("unexpected error type: {e:?}"),
+            }
+        }
+
+        // An old signature is accepted when the policy does not limit the age
+        let params = SignatureParams::new(["@method"])
+            .with_created(now - 600)
+            .with_keyid("k");
+        let headers = signed_headers(&request, &signing_key, &params);
+        let options = ValidationOptions::new(&request, &headers, &verifying_key)
+            .with_policy(ValidationPolicy::permissive());
+        validate_signature(options).unwrap();
+    }
+
+    #[test]
+    fn test_policy_requires_components_and_expires() {
+        let mut request = Request::new(Some("{}".to_string()));
+        *request.method_mut() = Method::POST;
+        *request.uri_mut() = Uri::from_static("https://example.com/quotes");
+        request
+            .headers_mut()
+            .insert("Authorization", "GNAP token".parse().unwrap());
+        request.headers_mut().insert(
+            "Content-Digest",
+            crate::http_signature::create_content_digest(
+                b"{}",
+                crate::http_signature::DigestAlgorithm::Sha256,
+            )
+            .parse()
+            .unwrap(),
+        );
+
+        let signing_key = SigningKey::generate(&mut OsRng);
+        let verifying_key = signing_key.verifying_key();
+        let now = chrono::Utc::now().timestamp();
+        let policy = ValidationPolicy {
+            required_components: vec!["@method".into()],
+            required_components_with_body: vec!["content-digest".into()],
+            ..ValidationPolicy::default()
+        };
+
+        for (components, uncovered) in [
+            (vec!["@method", "content-digest"], "authorization"),
+            (vec!["@method", "authorization"], "content-digest"),
+            (vec!["authorization", "content-digest"], "@method"),
+        ] {
+            let params = SignatureParams::new(components)
+                .with_created(now)
+                .with_keyid("k");
+            let headers = signed_headers(&request, &signing_key, &params);
+            let options = ValidationOptions::new(&request, &headers, &verifying_key)
+                .with_policy(policy.clone());
+            match validate_signature(options).unwrap_err() {
+                HttpSignatureError::UncoveredComponent(name) => assert_eq!(name, uncovered),
+                e => panic!("unexpected error type: {e:?}"),
+            }
+        }
+
+        let params = SignatureParams::new(["@method", "authorization", "content-digest"])
+            .with_created(now)
+            .with_keyid("k");
+        let headers = signed_headers(&request, &signing_key, &params);
+        let options =
+            ValidationOptions::new(&request, &headers, &verifying_key).with_policy(policy.clone());
+        validate_signature(options).unwrap();
+
+        let options = ValidationOptions::new(&request, &headers, &verifying_key).with_policy(
+            ValidationPolicy {
+                require_expires: true,
+                ..policy
+            },
+        );
+        match validate_signature(options).unwrap_err() {
+            HttpSignatureError::MissingParameter(name) => assert_eq!(name, "expires"),
+            e => panic!("unexpected error type: {e:?}"),
+        }
+    }
+
+    #[test]
+    fn test_policy_rejects_replayed_nonce() {
+        use crate::http_signature::InMemoryNonceStore;
+        use std::sync::Arc;
+
+        let request = get_request();
+        let signing_key = SigningKey::generate(&mut OsRng);
+        let verifying_key = signing_key.verifying_key();
+        let policy = ValidationPolicy {
+            nonce_store: Some(Arc::new(InMemoryNonceStore::new(100))),
+            ..ValidationPolicy::default()
+        };
+        let now = chrono::Utc::now().timestamp();
+
+        let params = SignatureParams::new(["@method"])
+            .with_created(now)
+            .with_keyid("k");
+        let headers = signed_headers(&request, &signing_key, &params);
+        let options =
+            ValidationOptions::new(&request, &headers, &verifying_key).with_policy(policy.clone());
+        match validate_signature(options).unwrap_err() {
+            HttpSignatureError::MissingParameter(name) => assert_eq!(name, "nonce"),
+            e => panic!("unexpected error type: {e:?}"),
+        }
+
+        let params = params.with_nonce("abc");
+        let headers = signed_headers(&request, &signing_key, &params);
+        let options =
+            ValidationOptions::new(&request, &headers, &verifying_key).with_policy(policy.clone());
+        validate_signature(options).unwrap();
+
+        let options =
+            ValidationOptions::new(&request, &headers, &verifying_key).with_policy(policy.clone());
+        match validate_signature(options).unwrap_err() {
+            HttpSignatureError::ReplayedNonce(nonce) => assert_eq!(nonce, "abc"),
+            e => panic!("unexpected error type: {e:?}"),
+        }
+
+        // A forged signature does not consume the nonce
+        let other_key = SigningKey::generate(&mut OsRng);
+        let params = params.with_nonce("def");
+        let headers = signed_headers(&request, &other_key, &params);
+        let options =
+            ValidationOptions::new(&request, &headers, &verifying_key).with_policy(policy.clone());
+        assert!(validate_signature(options).is_err());
+        let headers = signed_headers(&request, &signing_key, &params);
+        let options =
+            ValidationOptions::new(&request, &headers, &verifying_key).with_policy(policy);
+        validate_signature(options).unwrap();
+    }
+
+    #[test]
+    fn test_verifies_covered_content_digest() {
+        use crate::http_signature::{create_content_digest, DigestAlgorithm};
+
+        let mut request = Request::new(Some(r#"{"amount":"100"}"#.to_string()));
+        *request.method_mut() = Method::POST;
+        *request.uri_mut() = Uri::from_static("https://example.com/quotes");
+        request.headers_mut().insert(
+            "Content-Digest",
+            create_content_digest(br#"{"amount":"100"}"#, DigestAlgorithm::Sha512)
+                .parse()
+                .unwrap(),
+        );
+
+        let signing_key = SigningKey::generate(&mut OsRng);
+        let verifying_key = signing_key.verifying_key();
+        let params = SignatureParams::new(["@method", "content-digest"])
+            .with_created(chrono::Utc::now().timestamp())
+            .with_keyid("k");
+        let headers = signed_headers(&request, &signing_key, &params);
+
+        let options = ValidationOptions::new(&request, &headers, &verifying_key);
+        validate_signature(options).unwrap();
+
+        // The signature still matches the header, but the body was replaced
+        *request.body_mut() = Some(r#"{"amount":"999"}"#.to_string());
+        let options = ValidationOptions::new(&request, &headers, &verifying_key);
+        match validate_signature(options).unwrap_err() {
+            HttpSignatureError::ContentDigestMismatch(algorithm) => {
+                assert_eq!(algorithm, "sha-512")
+            }
+            e => panic!("unexpected error type: {e:?}"),
+        }
+    }
 }
@@ -72,7 +72,7 @@
 //!     let client = AuthenticatedClient::new(config)?;
 //!     
 //!     let request = CreateIncomingPaymentRequest {
-//!         wallet_address: "https://rafiki.money/alice".to_string(),
+//!         wallet_address: "https://rafiki.money/alice".parse()?,
 //!         incoming_amount: Some(Amount {
 //!             value: "1000".to_string(),
 //!             asset_code: "EUR".to_string(),
@@ -97,18 +97,20 @@
 //! ### HTTP Signature Creation
 //!
 //! ```rust
-//! use open_payments::http_signature::{create_signature_headers, SignOptions};
+//! use open_payments::http_signature::{create_signature_headers, Ed25519Signer, SignOptions};
 //! use http::{Request, Method, Uri};
 //! use ed25519_dalek::SigningKey;
 //!
-//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!     let mut request = Request::new(Some("test body".to_string()));
 //!     *request.method_mut() = Method::POST;
 //!     *request.uri_mut() = Uri::from_static("https://ilp.rafiki.money/incoming-payments");
 //!
 //!     let signing_key = SigningKey::generate(&mut rand::rngs::OsRng);
-//!     let options = SignOptions::new(&request, &signing_key, "test-key".to_string());
-//!     let headers = create_signature_headers(options)?;
+//!     let signer = Ed25519Signer::new("test-key", signing_key);
+//!     let options = SignOptions::new(&request, &signer);
+//!     let headers = create_signature_headers(options).await?;
 //!
 //!     println!("Signature: {}", headers.signature);
 //!     println!("Signature-Input: {}", headers.signature_input);
@@ -121,6 +123,7 @@
 //! This project is licensed under the Apache License 2.0 - see the LICENSE file for details.
 
 pub mod client;
+pub mod error;
 pub mod http_signature;
 #[cfg(feature = "snippets")]
 pub mod snippets;
@@ -25,6 +25,9 @@ async fn main() -> open_payments::client::Result<()> {
                 access_token.manage
             );
         }
+        ContinueResponse::WithSubject { subject, .. } => {
+            println!("Received subject: {subject:#?}");
+        }
         ContinueResponse::Pending { .. } => {
             println!("Pending");
         }
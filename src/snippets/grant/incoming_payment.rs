@@ -49,7 +49,7 @@ async fn main() -> open_payments::client::Result<()> {
                 access_token.manage
             );
         }
-        GrantResponse::WithInteraction { .. } => {
+        GrantResponse::WithInteraction { .. } | GrantResponse::WithSubject { .. } => {
             unreachable!("Interaction not required for incoming payments");
         }
     }
@@ -3,12 +3,11 @@ use open_payments::client::AuthenticatedResources;
 use open_payments::snippets::utils::{create_authenticated_client, get_env_var, load_env};
 use open_payments::types::{
     auth::{
-        AccessItem, AccessTokenRequest, GrantRequest, InteractFinish, InteractRequest,
-        LimitsOutgoing, OutgoingPaymentAction,
+        AccessItem, AccessTokenRequest, FinishMethod, GrantRequest, InteractFinish,
+        InteractRequest, InteractStart, LimitsOutgoing, OutgoingPaymentAction,
     },
     GrantResponse,
 };
-use uuid::Uuid;
 
 #[tokio::main]
 async fn main() -> open_payments::client::Result<()> {
@@ -45,12 +44,11 @@ async fn main() -> open_payments::client::Result<()> {
             }],
         },
         Some(InteractRequest {
-            start: vec!["redirect".to_string()],
-            finish: Some(InteractFinish {
-                method: "redirect".to_string(),
-                uri: "http://localhost".to_string(),
-                nonce: Uuid::new_v4().to_string(),
-            }),
+            start: vec![InteractStart::Redirect],
+            finish: Some(InteractFinish::new(
+                FinishMethod::Redirect,
+                "http://localhost",
+            )),
         }),
     );
 
@@ -79,6 +77,9 @@ async fn main() -> open_payments::client::Result<()> {
             println!("Received interact: {interact:#?}");
             println!("Received continue: {continue_:#?}");
         }
+        GrantResponse::WithSubject { subject, .. } => {
+            println!("Received subject: {subject:#?}");
+        }
     }
 
     Ok(())
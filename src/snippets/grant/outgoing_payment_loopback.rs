@@ -0,0 +1,62 @@
+use open_payments::client::api::UnauthenticatedResources;
+use open_payments::client::{AuthenticatedResources, LoopbackOptions};
+use open_payments::snippets::utils::{create_authenticated_client, get_env_var, load_env};
+use open_payments::types::auth::{
+    AccessItem, AccessTokenRequest, GrantRequest, LimitsOutgoing, OutgoingPaymentAction,
+};
+
+#[tokio::main]
+async fn main() -> open_payments::client::Result<()> {
+    load_env()?;
+
+    let client = create_authenticated_client()?;
+
+    let wallet_address_url = get_env_var("WALLET_ADDRESS_URL")?;
+    let quote_url = get_env_var("QUOTE_URL")?;
+    let gnap_token = get_env_var("QUOTE_ACCESS_TOKEN")?;
+
+    let wallet_address = client.wallet_address().get(&wallet_address_url).await?;
+
+    let quote = client.quotes().get(&quote_url, Some(&gnap_token)).await?;
+
+    let grant_request = GrantRequest::new(
+        AccessTokenRequest {
+            access: vec![AccessItem::OutgoingPayment {
+                actions: vec![
+                    OutgoingPaymentAction::Read,
+                    OutgoingPaymentAction::ReadAll,
+                    OutgoingPaymentAction::List,
+                    OutgoingPaymentAction::Create,
+                ],
+                identifier: wallet_address.id.to_string(),
+                limits: Some(LimitsOutgoing {
+                    receiver: None,
+                    debit_amount: Some(quote.debit_amount),
+                    receive_amount: None,
+                    interval: None,
+                }),
+            }],
+        },
+        None,
+    );
+
+    // The interaction is finished by a redirect to a local listener, so the grant is
+    // continued without copying the interact_ref by hand
+    let (access_token, _) = client
+        .grant()
+        .request_with_loopback(
+            &wallet_address.auth_server,
+            &grant_request,
+            &LoopbackOptions::default(),
+            |url| println!("Open the following URL to approve the grant: {url}"),
+        )
+        .await?;
+
+    println!("Received access token: {:#?}", access_token.value);
+    println!(
+        "Received access token manage URL: {:#?}",
+        access_token.manage
+    );
+
+    Ok(())
+}
@@ -22,7 +22,7 @@ async fn main() -> open_payments::client::Result<()> {
         .unwrap();
 
     let request = CreateIncomingPaymentRequest {
-        wallet_address: wallet_address_url,
+        wallet_address: wallet_address_url.parse()?,
         incoming_amount: Some(Amount {
             value: "1000".to_string(),
             asset_code: "EUR".to_string(),
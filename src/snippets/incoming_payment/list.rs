@@ -1,5 +1,6 @@
 use open_payments::client::api::AuthenticatedResources;
 use open_payments::client::utils::get_resource_server_url;
+use open_payments::client::ListOptions;
 use open_payments::snippets::utils::{create_authenticated_client, get_env_var, load_env};
 
 #[tokio::main]
@@ -17,9 +18,7 @@ async fn main() -> open_payments::client::Result<()> {
         .list(
             &resource_server_url,
             &wallet_address_url,
-            None,
-            Some(10),
-            None,
+            &ListOptions::new().first(10),
             Some(&gnap_token),
         )
         .await?;
@@ -34,9 +33,7 @@ async fn main() -> open_payments::client::Result<()> {
                 .list(
                     &resource_server_url,
                     &wallet_address_url,
-                    Some(&end_cursor),
-                    Some(10),
-                    None,
+                    &ListOptions::new().cursor(end_cursor).first(10),
                     Some(&gnap_token),
                 )
                 .await?;
@@ -15,7 +15,7 @@ async fn main() -> open_payments::client::Result<()> {
     let resource_server_url = get_resource_server_url(&wallet_address_url)?;
 
     let request = OutgoingPaymentRequest::FromQuote {
-        wallet_address: wallet_address_url,
+        wallet_address: wallet_address_url.parse()?,
         quote_id: quote_url,
         metadata: None,
     };
@@ -1,7 +1,7 @@
 use crate::client::{
     AuthenticatedClient, ClientConfig, OpClientError, Result, UnauthenticatedClient,
 };
-use crate::http_signature::jwk::Jwk;
+use crate::http_signature::JwkError;
 use dotenv::dotenv;
 use std::{env, path::PathBuf};
 
@@ -19,7 +19,9 @@ pub fn create_authenticated_client() -> Result<AuthenticatedClient> {
     let wallet_address_url = get_env_var("WALLET_ADDRESS_URL")?;
     let private_key_path = PathBuf::from(get_env_var("PRIVATE_KEY_PATH")?);
     let key_id = get_env_var("KEY_ID")?;
-    let key_id_clone = key_id.clone();
+    if key_id.trim().is_empty() {
+        return Err(OpClientError::other(format!("JWK error: {}", JwkError::EmptyKeyId)).into());
+    }
     let jwks_path = get_env_var("JWKS_PATH").map(PathBuf::from).ok();
 
     let config = ClientConfig {
@@ -31,8 +33,6 @@ pub fn create_authenticated_client() -> Result<AuthenticatedClient> {
 
     let client = AuthenticatedClient::new(config)
         .map_err(|e| OpClientError::other(format!("Client creation error: {e}")))?;
-    Jwk::new(key_id_clone, Some(&client.signing_key))
-        .map_err(|e| OpClientError::other(format!("JWK error: {e}")))?;
     Ok(client)
 }
 
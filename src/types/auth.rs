@@ -1,5 +1,8 @@
 use crate::types::common::{Amount, Interval, Receiver};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::{rngs::OsRng, RngCore};
 use serde::{Deserialize, Serialize};
+use url::Url;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(tag = "type", rename_all = "kebab-case")]
@@ -80,7 +83,10 @@ pub struct AccessTokenResponse {
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct GrantRequest {
-    pub access_token: AccessTokenRequest,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access_token: Option<AccessTokenRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<Subject>,
     pub(crate) client: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub interact: Option<InteractRequest>,
@@ -89,11 +95,70 @@ pub struct GrantRequest {
 impl GrantRequest {
     pub fn new(access_token: AccessTokenRequest, interact: Option<InteractRequest>) -> Self {
         Self {
-            access_token,
+            access_token: Some(access_token),
+            subject: None,
             client: String::new(), // Will be set by the client internally
             interact,
         }
     }
+
+    /// Returns the wallet address URL of the client requesting the grant.
+    ///
+    /// Servers resolve the keys the request is signed with from this wallet address.
+    pub fn client(&self) -> &str {
+        &self.client
+    }
+
+    /// Creates a request for information about a subject, without an access token.
+    ///
+    /// The authorization server requires the user to interact to release it.
+    pub fn for_subject(subject: Subject, interact: InteractRequest) -> Self {
+        Self {
+            access_token: None,
+            subject: Some(subject),
+            client: String::new(), // Will be set by the client internally
+            interact: Some(interact),
+        }
+    }
+}
+
+/// Subject of a grant, such as the owner of a wallet address.
+///
+/// In a grant request, the identifiers are those the client wants the user to prove
+/// control of. In a response, they are those the user was authenticated as.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Subject {
+    pub sub_ids: Vec<SubjectId>,
+}
+
+impl Subject {
+    /// Creates a subject identified by a wallet address.
+    pub fn wallet_address(wallet_address: impl Into<String>) -> Self {
+        Self {
+            sub_ids: vec![SubjectId {
+                id: wallet_address.into(),
+                format: SubjectIdFormat::Uri,
+            }],
+        }
+    }
+}
+
+/// Identifier of a subject.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SubjectId {
+    pub id: String,
+    pub format: SubjectIdFormat,
+}
+
+/// Format of a [`SubjectId`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SubjectIdFormat {
+    /// A URI, such as a wallet address.
+    Uri,
+    /// A format unknown to this crate.
+    #[serde(untagged)]
+    Other(String),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -103,23 +168,76 @@ pub struct AccessTokenRequest {
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct InteractRequest {
-    pub start: Vec<String>,
+    pub start: Vec<InteractStart>,
     pub finish: Option<InteractFinish>,
 }
 
+/// Mode in which the client can start an interaction.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InteractStart {
+    /// The client redirects the user to a URL of the authorization server.
+    Redirect,
+    /// The client launches an application of the authorization server.
+    App,
+    /// The user enters a code on a fixed URL of the authorization server.
+    UserCode,
+    /// The user enters a code on a URL given by the authorization server.
+    UserCodeUri,
+    /// A start mode unknown to this crate.
+    #[serde(untagged)]
+    Other(String),
+}
+
+/// Method by which the authorization server tells the client an interaction has finished.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FinishMethod {
+    /// The user's browser is redirected to the finish URI.
+    Redirect,
+    /// The authorization server sends a request to the finish URI.
+    Push,
+    /// A finish method unknown to this crate.
+    #[serde(untagged)]
+    Other(String),
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct InteractFinish {
-    pub method: String,
+    pub method: FinishMethod,
     pub uri: String,
     pub nonce: String,
 }
 
+impl InteractFinish {
+    /// Creates a finish to `uri` with a random nonce.
+    ///
+    /// The nonce is 32 bytes from the operating system's random number generator,
+    /// encoded with URL-safe base64.
+    pub fn new(method: FinishMethod, uri: impl Into<String>) -> Self {
+        let mut nonce = [0u8; 32];
+        OsRng.fill_bytes(&mut nonce);
+        Self {
+            method,
+            uri: uri.into(),
+            nonce: URL_SAFE_NO_PAD.encode(nonce),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct InteractResponse {
-    pub redirect: String,
+    pub redirect: Url,
     pub finish: String,
 }
 
+/// Body of the request sent to the finish URI by the `push` finish method.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct InteractPush {
+    pub hash: String,
+    pub interact_ref: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Continue {
     pub access_token: ContinueAccessToken,
@@ -132,9 +250,21 @@ pub struct ContinueAccessToken {
     pub value: String,
 }
 
+/// Response to a grant request.
+///
+/// Responses carrying a `subject` are always [`GrantResponse::WithSubject`], whichever
+/// other fields they have.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(untagged)]
 pub enum GrantResponse {
+    // Tried first, since the other variants would match a response with a subject too
+    WithSubject {
+        subject: Subject,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        access_token: Option<AccessToken>,
+        #[serde(rename = "continue", skip_serializing_if = "Option::is_none")]
+        continue_: Option<Continue>,
+    },
     WithInteraction {
         interact: InteractResponse,
         #[serde(rename = "continue")]
@@ -147,14 +277,55 @@ pub enum GrantResponse {
     },
 }
 
+impl GrantResponse {
+    /// Returns the access token issued by the grant, if any.
+    pub fn access_token(&self) -> Option<&AccessToken> {
+        match self {
+            GrantResponse::WithSubject { access_token, .. } => access_token.as_ref(),
+            GrantResponse::WithInteraction { .. } => None,
+            GrantResponse::WithToken { access_token, .. } => Some(access_token),
+        }
+    }
+
+    /// Returns the information to continue the grant, if any.
+    pub fn continue_(&self) -> Option<&Continue> {
+        match self {
+            GrantResponse::WithSubject { continue_, .. } => continue_.as_ref(),
+            GrantResponse::WithInteraction { continue_, .. }
+            | GrantResponse::WithToken { continue_, .. } => Some(continue_),
+        }
+    }
+
+    /// Returns the subject released by the grant, if any.
+    pub fn subject(&self) -> Option<&Subject> {
+        match self {
+            GrantResponse::WithSubject { subject, .. } => Some(subject),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct ContinueRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub interact_ref: Option<String>,
 }
 
+/// Response to a grant continuation request.
+///
+/// Responses carrying a `subject` are always [`ContinueResponse::WithSubject`], whichever
+/// other fields they have.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(untagged)]
 pub enum ContinueResponse {
+    // Tried first, since the other variants would match a response with a subject too
+    WithSubject {
+        subject: Subject,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        access_token: Option<AccessToken>,
+        #[serde(rename = "continue", skip_serializing_if = "Option::is_none")]
+        continue_: Option<Continue>,
+    },
     WithToken {
         access_token: AccessToken,
         #[serde(rename = "continue")]
@@ -165,3 +336,31 @@ pub enum ContinueResponse {
         continue_: Continue,
     },
 }
+
+impl ContinueResponse {
+    /// Returns the access token issued by the grant, if any.
+    pub fn access_token(&self) -> Option<&AccessToken> {
+        match self {
+            ContinueResponse::WithSubject { access_token, .. } => access_token.as_ref(),
+            ContinueResponse::WithToken { access_token, .. } => Some(access_token),
+            ContinueResponse::Pending { .. } => None,
+        }
+    }
+
+    /// Returns the information to continue the grant, if any.
+    pub fn continue_(&self) -> Option<&Continue> {
+        match self {
+            ContinueResponse::WithSubject { continue_, .. } => continue_.as_ref(),
+            ContinueResponse::WithToken { continue_, .. }
+            | ContinueResponse::Pending { continue_ } => Some(continue_),
+        }
+    }
+
+    /// Returns the subject released by the grant, if any.
+    pub fn subject(&self) -> Option<&Subject> {
+        match self {
+            ContinueResponse::WithSubject { subject, .. } => Some(subject),
+            _ => None,
+        }
+    }
+}
@@ -16,6 +16,8 @@
 //! ### Resources
 //!
 //! - [`WalletAddress`] - Wallet address information
+//! - [`WalletAddressUrl`] - Wallet address URL, parsed from a URL or payment pointer
+//! - [`DidDocument`] - DID document of a wallet address
 //! - [`IncomingPayment`] - Incoming payment details
 //! - [`OutgoingPayment`] - Outgoing payment details
 //! - [`Quote`] - Payment quote details
@@ -48,7 +50,7 @@
 //!
 //! // Create an incoming payment request
 //! let payment_request = CreateIncomingPaymentRequest {
-//!     wallet_address: "https://rafiki.money/alice".to_string(),
+//!     wallet_address: "$rafiki.money/alice".parse().unwrap(),
 //!     incoming_amount: Some(Amount {
 //!         value: "1000".to_string(),
 //!         asset_code: "EUR".to_string(),
@@ -75,9 +77,10 @@ pub use common::*;
 
 pub use auth::{
     AccessItem, AccessToken, AccessTokenRequest, AccessTokenResponse, Continue,
-    ContinueAccessToken, ContinueRequest, ContinueResponse, GrantRequest, GrantResponse,
-    IncomingPaymentAction, InteractFinish, InteractRequest, InteractResponse, LimitsOutgoing,
-    OutgoingPaymentAction, QuoteAction,
+    ContinueAccessToken, ContinueRequest, ContinueResponse, FinishMethod, GrantRequest,
+    GrantResponse, IncomingPaymentAction, InteractFinish, InteractPush, InteractRequest,
+    InteractResponse, InteractStart, LimitsOutgoing, OutgoingPaymentAction, QuoteAction, Subject,
+    SubjectId, SubjectIdFormat,
 };
 
 pub use resource::{
@@ -90,5 +93,7 @@ pub use resource::{
 };
 
 pub use wallet_address::{
-    JsonWebKey, JsonWebKeySet, JwkAlgorithm, JwkCurve, JwkKeyType, JwkUse, WalletAddress,
+    DidDocument, DidService, IntoWalletAddressUrl, JsonWebKey, JsonWebKeySet, JwkAlgorithm,
+    JwkCurve, JwkKeyType, JwkUse, PublicKeyJwk, VerificationMethod, VerificationRelationship,
+    WalletAddress, WalletAddressUrl,
 };
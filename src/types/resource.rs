@@ -1,4 +1,5 @@
 use crate::types::common::{Amount, Receiver};
+use crate::types::wallet_address::WalletAddressUrl;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -53,7 +54,7 @@ pub struct PublicIncomingPayment {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateIncomingPaymentRequest {
-    pub wallet_address: String,
+    pub wallet_address: WalletAddressUrl,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub incoming_amount: Option<Amount>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -88,7 +89,7 @@ pub struct OutgoingPayment {
 pub enum CreateQuoteRequest {
     FixedReceiveAmountQuote {
         #[serde(rename = "walletAddress")]
-        wallet_address: String,
+        wallet_address: WalletAddressUrl,
         receiver: Receiver,
         method: PaymentMethodType,
         #[serde(rename = "receiveAmount")]
@@ -96,7 +97,7 @@ pub enum CreateQuoteRequest {
     },
     FixedSendAmountQuote {
         #[serde(rename = "walletAddress")]
-        wallet_address: String,
+        wallet_address: WalletAddressUrl,
         receiver: Receiver,
         method: PaymentMethodType,
         #[serde(rename = "debitAmount")]
@@ -104,7 +105,7 @@ pub enum CreateQuoteRequest {
     },
     NoAmountQuote {
         #[serde(rename = "walletAddress")]
-        wallet_address: String,
+        wallet_address: WalletAddressUrl,
         receiver: Receiver,
         method: PaymentMethodType,
     },
@@ -115,7 +116,7 @@ pub enum CreateQuoteRequest {
 pub enum CreateOutgoingPaymentRequest {
     FromQuote {
         #[serde(rename = "walletAddress")]
-        wallet_address: String,
+        wallet_address: WalletAddressUrl,
         #[serde(rename = "quoteId")]
         quote_id: String,
         #[serde(skip_serializing_if = "Option::is_none")]
@@ -123,7 +124,7 @@ pub enum CreateOutgoingPaymentRequest {
     },
     FromIncomingPayment {
         #[serde(rename = "walletAddress")]
-        wallet_address: String,
+        wallet_address: WalletAddressUrl,
         #[serde(rename = "incomingPayment")]
         incoming_payment_id: String,
         #[serde(rename = "debitAmount")]
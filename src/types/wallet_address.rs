@@ -1,4 +1,210 @@
-use serde::{Deserialize, Serialize};
+use crate::error::{OpClientError, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+use url::Url;
+
+/// URL of a wallet address, parsed from a URL or a payment pointer.
+///
+/// Payment pointers are normalized as defined by the
+/// [Payment Pointers](https://paymentpointers.org/syntax-resolution/) specification:
+/// `$host` maps to `https://host/.well-known/pay` and `$host/path` to
+/// `https://host/path`. Wallet addresses must use `https`. For local development,
+/// [`WalletAddressUrl::parse_dev`] also accepts `http`, but clients only fetch such
+/// wallet addresses once `http` is allowed on the client.
+///
+/// ```rust
+/// use open_payments::types::WalletAddressUrl;
+///
+/// let url: WalletAddressUrl = "$ilp.rafiki.money/alice".parse().unwrap();
+/// assert_eq!(url.to_string(), "https://ilp.rafiki.money/alice");
+/// assert_eq!(url.payment_pointer().as_deref(), Some("$ilp.rafiki.money/alice"));
+///
+/// let url: WalletAddressUrl = "$rafiki.money".parse().unwrap();
+/// assert_eq!(url.as_str(), "https://rafiki.money/.well-known/pay");
+/// assert_eq!(url.payment_pointer().as_deref(), Some("$rafiki.money"));
+///
+/// assert!("http://localhost:3000/alice".parse::<WalletAddressUrl>().is_err());
+/// assert!(WalletAddressUrl::parse_dev("http://localhost:3000/alice").is_ok());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WalletAddressUrl(Url);
+
+/// Path a payment pointer without a path resolves to.
+const WELL_KNOWN_PATH: &str = "/.well-known/pay";
+
+impl WalletAddressUrl {
+    /// Parses a payment pointer or an `https` URL.
+    pub fn parse(input: &str) -> Result<Self> {
+        Self::parse_with(input, false)
+    }
+
+    /// Parses a payment pointer or a URL, also accepting `http` URLs.
+    ///
+    /// This is meant for local development against servers without TLS. Clients reject
+    /// `http` wallet addresses unless built with
+    /// [`ClientBuilder::allow_http_wallet_addresses`](crate::client::ClientBuilder::allow_http_wallet_addresses).
+    pub fn parse_dev(input: &str) -> Result<Self> {
+        Self::parse_with(input, true)
+    }
+
+    /// Normalizes the URL of a cached wallet address to remove it from a cache.
+    ///
+    /// Nothing is fetched, so `http` URLs are accepted whether or not the client allows
+    /// them, letting development clients remove their entries too.
+    pub(crate) fn for_invalidation(wallet_address_url: impl IntoWalletAddressUrl) -> Result<Self> {
+        wallet_address_url.into_wallet_address_url(true)
+    }
+
+    pub(crate) fn parse_with(input: &str, allow_http: bool) -> Result<Self> {
+        let input = input.trim();
+        let url = match input.strip_prefix('$') {
+            Some(pointer) => {
+                let mut url = Url::parse(&format!("https://{pointer}"))?;
+                if url.path() == "/" {
+                    url.set_path(WELL_KNOWN_PATH);
+                }
+                url
+            }
+            None => Url::parse(input)?,
+        };
+
+        let mut errors = Vec::new();
+        match url.scheme() {
+            "https" => {}
+            "http" if allow_http => {}
+            scheme => errors.push(format!("scheme must be https, got {scheme}")),
+        }
+        if !url.username().is_empty() || url.password().is_some() {
+            errors.push("credentials are not allowed".to_string());
+        }
+        if url.query().is_some() || url.fragment().is_some() {
+            errors.push("query and fragment are not allowed".to_string());
+        }
+        if url.host().is_none() {
+            errors.push("a host is required".to_string());
+        }
+
+        if errors.is_empty() {
+            Ok(Self(url))
+        } else {
+            Err(Box::new(OpClientError::validation(
+                format!("Invalid wallet address {input}"),
+                errors,
+            )))
+        }
+    }
+
+    /// Returns the URL as a string.
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+
+    /// Returns the URL.
+    pub fn as_url(&self) -> &Url {
+        &self.0
+    }
+
+    /// Returns the payment pointer form of the URL, or `None` for `http` URLs, which
+    /// payment pointers cannot express.
+    pub fn payment_pointer(&self) -> Option<String> {
+        if self.0.scheme() != "https" {
+            return None;
+        }
+        let host = self.0.host_str()?;
+        let port = self
+            .0
+            .port()
+            .map(|port| format!(":{port}"))
+            .unwrap_or_default();
+        let path = match self.0.path() {
+            WELL_KNOWN_PATH => "",
+            path => path,
+        };
+        Some(format!("${host}{port}{path}"))
+    }
+}
+
+impl fmt::Display for WalletAddressUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for WalletAddressUrl {
+    type Err = Box<OpClientError>;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl AsRef<str> for WalletAddressUrl {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<WalletAddressUrl> for String {
+    fn from(url: WalletAddressUrl) -> Self {
+        url.0.into()
+    }
+}
+
+impl Serialize for WalletAddressUrl {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for WalletAddressUrl {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Self::parse(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Conversion into a [`WalletAddressUrl`], accepted by
+/// [`WalletAddressResource::get`](crate::client::api::unauthenticated::WalletAddressResource::get).
+///
+/// `http` URLs are only accepted when `allow_http` is set, whether they are given as
+/// strings or as values built with [`WalletAddressUrl::parse_dev`].
+pub trait IntoWalletAddressUrl {
+    fn into_wallet_address_url(self, allow_http: bool) -> Result<WalletAddressUrl>;
+}
+
+impl IntoWalletAddressUrl for WalletAddressUrl {
+    fn into_wallet_address_url(self, allow_http: bool) -> Result<WalletAddressUrl> {
+        (&self).into_wallet_address_url(allow_http)
+    }
+}
+
+impl IntoWalletAddressUrl for &WalletAddressUrl {
+    fn into_wallet_address_url(self, allow_http: bool) -> Result<WalletAddressUrl> {
+        WalletAddressUrl::parse_with(self.as_str(), allow_http)
+    }
+}
+
+impl IntoWalletAddressUrl for &str {
+    fn into_wallet_address_url(self, allow_http: bool) -> Result<WalletAddressUrl> {
+        WalletAddressUrl::parse_with(self, allow_http)
+    }
+}
+
+impl IntoWalletAddressUrl for String {
+    fn into_wallet_address_url(self, allow_http: bool) -> Result<WalletAddressUrl> {
+        WalletAddressUrl::parse_with(&self, allow_http)
+    }
+}
+
+impl IntoWalletAddressUrl for &String {
+    fn into_wallet_address_url(self, allow_http: bool) -> Result<WalletAddressUrl> {
+        WalletAddressUrl::parse_with(self, allow_http)
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
@@ -28,6 +234,73 @@ pub struct JsonWebKey {
     pub x: String,
 }
 
+impl JsonWebKey {
+    /// Returns the [RFC 7638](https://www.rfc-editor.org/rfc/rfc7638) thumbprint of the
+    /// key, computed with SHA-256 and encoded with base64url.
+    ///
+    /// The thumbprint only depends on the public key, so it can be used as a
+    /// deterministic key id.
+    pub fn thumbprint(&self) -> String {
+        jwk_thumbprint("Ed25519", "OKP", &self.x)
+    }
+}
+
+/// Creates the JSON Web Key of the public key, identified by its thumbprint.
+impl From<&SigningKey> for JsonWebKey {
+    fn from(signing_key: &SigningKey) -> Self {
+        let mut jwk = JsonWebKey {
+            kid: String::new(),
+            alg: JwkAlgorithm::EdDSA,
+            use_: Some(JwkUse::Signature),
+            kty: JwkKeyType::OKP,
+            crv: JwkCurve::Ed25519,
+            x: URL_SAFE_NO_PAD.encode(signing_key.verifying_key().as_bytes()),
+        };
+        jwk.kid = jwk.thumbprint();
+        jwk
+    }
+}
+
+impl TryFrom<&JsonWebKey> for VerifyingKey {
+    type Error = Box<OpClientError>;
+
+    fn try_from(jwk: &JsonWebKey) -> Result<Self> {
+        let invalid = |error: String| {
+            Box::new(OpClientError::validation(
+                format!("Invalid JSON Web Key {}", jwk.kid),
+                vec![error],
+            ))
+        };
+        let bytes = decode_ed25519_x(&jwk.x).map_err(invalid)?;
+        VerifyingKey::from_bytes(&bytes)
+            .map_err(|_| invalid("x is not a valid Ed25519 point".to_string()))
+    }
+}
+
+/// Decodes the base64url `x` member of an Ed25519 JSON Web Key.
+fn decode_ed25519_x(x: &str) -> std::result::Result<[u8; 32], String> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(x)
+        .map_err(|_| "x is not valid base64url".to_string())?;
+    bytes
+        .try_into()
+        .map_err(|bytes: Vec<u8>| format!("x is {} bytes, expected 32", bytes.len()))
+}
+
+/// Computes the RFC 7638 thumbprint of an `OKP` key from its required members.
+///
+/// The members are serialized in lexicographic order without whitespace, as the
+/// RFC requires, before being hashed with SHA-256.
+pub(crate) fn jwk_thumbprint(crv: &str, kty: &str, x: &str) -> String {
+    let members = format!(
+        r#"{{"crv":{},"kty":{},"x":{}}}"#,
+        serde_json::Value::from(crv),
+        serde_json::Value::from(kty),
+        serde_json::Value::from(x)
+    );
+    URL_SAFE_NO_PAD.encode(Sha256::digest(members.as_bytes()))
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum JwkAlgorithm {
     EdDSA,
@@ -49,3 +322,162 @@ pub enum JwkKeyType {
 pub enum JwkCurve {
     Ed25519,
 }
+
+/// DID document of a wallet address, served at `{wallet_address}/did.json`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DidDocument {
+    #[serde(rename = "@context", skip_serializing_if = "Option::is_none")]
+    pub context: Option<serde_json::Value>,
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub verification_method: Vec<VerificationMethod>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authentication: Vec<VerificationRelationship>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub service: Vec<DidService>,
+}
+
+impl DidDocument {
+    /// Returns the verification method with the given id.
+    ///
+    /// Relative ids such as `#key-1` are resolved against the id of the document.
+    pub fn verification_method(&self, id: &str) -> Option<&VerificationMethod> {
+        let matches = |method_id: &str| self.resolve(method_id) == self.resolve(id);
+        self.verification_method
+            .iter()
+            .chain(self.authentication.iter().filter_map(|entry| match entry {
+                VerificationRelationship::Embedded(method) => Some(method),
+                VerificationRelationship::Reference(_) => None,
+            }))
+            .find(|method| matches(&method.id))
+    }
+
+    /// Returns the verification methods used for authentication, resolving references.
+    ///
+    /// References to methods missing from the document are skipped.
+    pub fn authentication_methods(&self) -> impl Iterator<Item = &VerificationMethod> {
+        self.authentication.iter().filter_map(|entry| match entry {
+            VerificationRelationship::Embedded(method) => Some(method),
+            VerificationRelationship::Reference(id) => self.verification_method(id),
+        })
+    }
+
+    fn resolve(&self, id: &str) -> String {
+        if id.starts_with('#') {
+            format!("{}{id}", self.id)
+        } else {
+            id.to_string()
+        }
+    }
+}
+
+/// Public key of a DID subject.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationMethod {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub controller: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_key_jwk: Option<PublicKeyJwk>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_key_multibase: Option<String>,
+}
+
+/// Multicodec prefix of Ed25519 public keys in `publicKeyMultibase`.
+const ED25519_MULTICODEC: [u8; 2] = [0xed, 0x01];
+
+impl VerificationMethod {
+    /// Returns the Ed25519 public key of the method.
+    ///
+    /// The key is read from `publicKeyJwk` if present, or else from a base58btc
+    /// `publicKeyMultibase`.
+    pub fn public_key_bytes(&self) -> Result<[u8; 32]> {
+        let bytes = if let Some(jwk) = &self.public_key_jwk {
+            if jwk.kty != "OKP" || jwk.crv != "Ed25519" {
+                return Err(self.invalid(format!("unsupported key type {} {}", jwk.kty, jwk.crv)));
+            }
+            return decode_ed25519_x(&jwk.x).map_err(|error| self.invalid(error));
+        } else if let Some(multibase) = &self.public_key_multibase {
+            let encoded = multibase.strip_prefix('z').ok_or_else(|| {
+                self.invalid("only base58btc multibase keys are supported".to_string())
+            })?;
+            let decoded = bs58::decode(encoded)
+                .into_vec()
+                .map_err(|_| self.invalid("key is not valid base58btc".to_string()))?;
+            match decoded.strip_prefix(&ED25519_MULTICODEC) {
+                Some(key) => key.to_vec(),
+                None => return Err(self.invalid("key is not an Ed25519 key".to_string())),
+            }
+        } else {
+            return Err(self.invalid("no public key".to_string()));
+        };
+
+        bytes.try_into().map_err(|bytes: Vec<u8>| {
+            self.invalid(format!("key is {} bytes, expected 32", bytes.len()))
+        })
+    }
+
+    /// Converts the method into a JSON Web Key identified by the method's id.
+    pub fn to_json_web_key(&self) -> Result<JsonWebKey> {
+        let bytes = self.public_key_bytes()?;
+        Ok(JsonWebKey {
+            kid: self.id.clone(),
+            alg: JwkAlgorithm::EdDSA,
+            use_: Some(JwkUse::Signature),
+            kty: JwkKeyType::OKP,
+            crv: JwkCurve::Ed25519,
+            x: URL_SAFE_NO_PAD.encode(bytes),
+        })
+    }
+
+    /// Converts the method into an Ed25519 verifying key.
+    pub fn verifying_key(&self) -> Result<VerifyingKey> {
+        VerifyingKey::from_bytes(&self.public_key_bytes()?)
+            .map_err(|_| self.invalid("key is not a valid Ed25519 point".to_string()))
+    }
+
+    fn invalid(&self, error: String) -> Box<OpClientError> {
+        Box::new(OpClientError::validation(
+            format!("Invalid verification method {}", self.id),
+            vec![error],
+        ))
+    }
+}
+
+/// Public key in JWK form within a [`VerificationMethod`].
+///
+/// Unlike [`JsonWebKey`], the key id and algorithm are optional.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PublicKeyJwk {
+    pub kty: String,
+    pub crv: String,
+    pub x: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alg: Option<String>,
+}
+
+/// Entry of a verification relationship such as `authentication`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum VerificationRelationship {
+    /// Id of a method listed in `verificationMethod`.
+    Reference(String),
+    /// Method only usable for this relationship.
+    Embedded(VerificationMethod),
+}
+
+/// Service endpoint advertised by a DID document.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DidService {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+    /// A URL, or a map or set of URLs.
+    pub service_endpoint: serde_json::Value,
+}
@@ -0,0 +1,241 @@
+mod common;
+
+use open_payments::client::{
+    CacheEntry, CacheStore, ErrorKind, InMemoryCacheStore, UnauthenticatedClient,
+    UnauthenticatedResources, WalletAddressCache,
+};
+use open_payments::types::{WalletAddress, WalletAddressUrl};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn wallet_url(server: &MockServer) -> WalletAddressUrl {
+    WalletAddressUrl::parse_dev(&format!("{}/alice", server.uri())).unwrap()
+}
+
+fn cached_client() -> (UnauthenticatedClient, Arc<WalletAddressCache>) {
+    let cache = Arc::new(WalletAddressCache::new());
+    let client = UnauthenticatedClient::new()
+        .allow_http_wallet_addresses()
+        .with_wallet_address_cache(cache.clone());
+    (client, cache)
+}
+
+#[tokio::test]
+async fn fresh_responses_are_served_from_the_cache_until_invalidated() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/alice"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("cache-control", "public, max-age=300")
+                .set_body_json(common::wallet(&server)),
+        )
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/alice/jwks.json"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("cache-control", "max-age=300")
+                .set_body_json(serde_json::json!({"keys": []})),
+        )
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let (client, cache) = cached_client();
+    for _ in 0..3 {
+        let got = client
+            .wallet_address()
+            .get(wallet_url(&server))
+            .await
+            .unwrap();
+        assert_eq!(got, common::wallet(&server));
+        let keys = client.wallet_address().get_keys(&got).await.unwrap();
+        assert!(keys.keys.is_empty());
+    }
+
+    cache.invalidate(wallet_url(&server)).await.unwrap();
+    let got = client
+        .wallet_address()
+        .get(wallet_url(&server))
+        .await
+        .unwrap();
+    client.wallet_address().get_keys(&got).await.unwrap();
+}
+
+#[tokio::test]
+async fn stale_responses_are_revalidated_with_their_etag() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/alice"))
+        .and(header("if-none-match", "\"v1\""))
+        .respond_with(ResponseTemplate::new(304).insert_header("etag", "\"v1\""))
+        .expect(2)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/alice"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("cache-control", "no-cache")
+                .insert_header("etag", "\"v1\"")
+                .set_body_json(common::wallet(&server)),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let (client, _) = cached_client();
+    for _ in 0..3 {
+        let got = client
+            .wallet_address()
+            .get(wallet_url(&server))
+            .await
+            .unwrap();
+        assert_eq!(got, common::wallet(&server));
+    }
+}
+
+#[tokio::test]
+async fn not_found_responses_are_cached_for_the_negative_ttl() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/alice"))
+        .respond_with(ResponseTemplate::new(404))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let (client, _) = cached_client();
+    for _ in 0..2 {
+        let error = client
+            .wallet_address()
+            .get(wallet_url(&server))
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::Http { status: 404 });
+    }
+
+    let cache = Arc::new(WalletAddressCache::new().negative_ttl(Duration::ZERO));
+    let client = UnauthenticatedClient::new()
+        .allow_http_wallet_addresses()
+        .with_wallet_address_cache(cache);
+    server.reset().await;
+    Mock::given(method("GET"))
+        .and(path("/alice"))
+        .respond_with(ResponseTemplate::new(404))
+        .expect(2)
+        .mount(&server)
+        .await;
+    for _ in 0..2 {
+        assert!(client
+            .wallet_address()
+            .get(wallet_url(&server))
+            .await
+            .is_err());
+    }
+}
+
+#[tokio::test]
+async fn uncacheable_responses_are_not_stored() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/alice"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("cache-control", "no-store, max-age=300")
+                .set_body_json(common::wallet(&server)),
+        )
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let (client, _) = cached_client();
+    for _ in 0..2 {
+        client
+            .wallet_address()
+            .get(wallet_url(&server))
+            .await
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn in_memory_store_evicts_the_least_recently_used_entry() {
+    let store = InMemoryCacheStore::new(2);
+    let entry = CacheEntry {
+        status: 200,
+        body: "{}".into(),
+        etag: None,
+        expires_at: SystemTime::now() + Duration::from_secs(60),
+    };
+    store.put("a", &entry).await.unwrap();
+    store.put("b", &entry).await.unwrap();
+    // Using `a` makes `b` the least recently used entry
+    assert!(store.get("a").await.unwrap().is_some());
+    store.put("c", &entry).await.unwrap();
+
+    assert!(store.get("a").await.unwrap().is_some());
+    assert!(store.get("b").await.unwrap().is_none());
+    assert!(store.get("c").await.unwrap().is_some());
+
+    store.clear().await.unwrap();
+    assert!(store.get("a").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn keys_are_invalidated_whatever_the_form_of_the_wallet_address_id() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/alice/jwks.json"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("cache-control", "max-age=300")
+                .set_body_json(serde_json::json!({"keys": []})),
+        )
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    // The server may return an id that differs from the normalized URL
+    let wallet = WalletAddress {
+        id: format!("HTTP://{}/alice/", server.address()),
+        ..common::wallet(&server)
+    };
+    let (client, cache) = cached_client();
+    client.wallet_address().get_keys(&wallet).await.unwrap();
+    client.wallet_address().get_keys(&wallet).await.unwrap();
+
+    cache.invalidate(wallet_url(&server)).await.unwrap();
+    client.wallet_address().get_keys(&wallet).await.unwrap();
+}
+
+#[tokio::test]
+async fn cached_requests_report_errors_like_uncached_requests() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/alice"))
+        .respond_with(ResponseTemplate::new(503).insert_header("retry-after", "7"))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/bob"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("not-json"))
+        .mount(&server)
+        .await;
+
+    let (cached, _) = cached_client();
+    let uncached = UnauthenticatedClient::new().allow_http_wallet_addresses();
+    for name in ["alice", "bob"] {
+        let url = format!("{}/{name}", server.uri());
+        let expected = uncached.wallet_address().get(&url).await.unwrap_err();
+        let error = cached.wallet_address().get(&url).await.unwrap_err();
+        assert_eq!(error.kind(), expected.kind());
+        assert_eq!(error.retry_after(), expected.retry_after());
+    }
+}
@@ -1,25 +1,23 @@
+mod common;
+
 use open_payments::client::{
-    AuthenticatedClient, AuthenticatedResources, ClientConfig, UnauthenticatedClient,
-    UnauthenticatedResources,
+    AccessScope, AuthenticatedClient, AuthenticatedResources, ErrorKind, GnapErrorCode,
+    GrantManager, ListOptions, OpClientError, PageDirection, PollOptions, RetryPolicy,
+    UnauthenticatedClient, UnauthenticatedResources,
 };
+use open_payments::http_signature::parse_signature_input;
 use open_payments::types::{
     Amount, CreateIncomingPaymentRequest, CreateOutgoingPaymentRequest, CreateQuoteRequest,
     IncomingPayment, PaymentMethodType, PublicIncomingPayment, Receiver, WalletAddress,
+    WalletAddressUrl,
 };
 use tempfile::tempdir;
 use url::Url;
-use wiremock::matchers::{header, header_exists, method, path};
+use wiremock::matchers::{
+    header, header_exists, method, path, query_param, query_param_is_missing,
+};
 use wiremock::{Mock, MockServer, ResponseTemplate};
 
-fn dummy_config(base: &str) -> ClientConfig {
-    ClientConfig {
-        key_id: "test-key".into(),
-        private_key_path: std::path::PathBuf::from("tests/private.key"),
-        jwks_path: None,
-        wallet_address_url: format!("{base}/alice"),
-    }
-}
-
 #[tokio::test]
 async fn unauthenticated_wallet_address_get_builds_request() {
     let server = MockServer::start().await;
@@ -42,11 +40,39 @@ async fn unauthenticated_wallet_address_get_builds_request() {
         .mount(&server)
         .await;
 
-    let client = UnauthenticatedClient::new();
+    let client = UnauthenticatedClient::new().allow_http_wallet_addresses();
     let got = client.wallet_address().get(&wallet_url).await.unwrap();
     assert_eq!(got, wallet);
 }
 
+#[tokio::test]
+async fn http_wallet_addresses_are_rejected_unless_the_client_allows_them() {
+    let server = MockServer::start().await;
+    let wallet_url = format!("{}/alice", server.uri());
+
+    // Neither strings nor development values are fetched over http by default
+    let client = UnauthenticatedClient::new();
+    let err = client.wallet_address().get(&wallet_url).await.unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::Validation);
+    let err = client
+        .wallet_address()
+        .get(WalletAddressUrl::parse_dev(&wallet_url).unwrap())
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::Validation);
+
+    let client = AuthenticatedClient::builder()
+        .key_id("test-key")
+        .wallet_address_url(&wallet_url)
+        .signing_key(ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng))
+        .build()
+        .unwrap();
+    let err = client.wallet_address().get(&wallet_url).await.unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::Validation);
+
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
+
 #[tokio::test]
 async fn authenticated_incoming_payment_create_sets_headers_and_body() {
     let server = MockServer::start().await;
@@ -75,12 +101,12 @@ async fn authenticated_incoming_payment_create_sets_headers_and_body() {
         .await;
 
     let tmp = tempdir().unwrap();
-    let mut config = dummy_config(&server.uri());
+    let mut config = common::dummy_config(&server.uri());
     config.private_key_path = tmp.path().join("private.key");
     let client = AuthenticatedClient::new(config).unwrap();
 
     let req = CreateIncomingPaymentRequest {
-        wallet_address: base.join("alice").unwrap().to_string(),
+        wallet_address: WalletAddressUrl::parse_dev(base.join("alice").unwrap().as_str()).unwrap(),
         incoming_amount: Some(Amount {
             value: "100".into(),
             asset_code: "EUR".into(),
@@ -128,12 +154,12 @@ async fn authenticated_quote_create_and_get() {
         .await;
 
     let tmp = tempdir().unwrap();
-    let mut config = dummy_config(&server.uri());
+    let mut config = common::dummy_config(&server.uri());
     config.private_key_path = tmp.path().join("private.key");
     let client = AuthenticatedClient::new(config).unwrap();
 
     let req = CreateQuoteRequest::FixedReceiveAmountQuote {
-        wallet_address: base.join("alice").unwrap().to_string(),
+        wallet_address: WalletAddressUrl::parse_dev(base.join("alice").unwrap().as_str()).unwrap(),
         receiver: Receiver(base.join("incoming-payments/123").unwrap().to_string()),
         method: PaymentMethodType::Ilp,
         receive_amount: Amount {
@@ -185,12 +211,12 @@ async fn authenticated_outgoing_payment_create_from_quote() {
         .await;
 
     let tmp = tempdir().unwrap();
-    let mut config = dummy_config(&server.uri());
+    let mut config = common::dummy_config(&server.uri());
     config.private_key_path = tmp.path().join("private.key");
     let client = AuthenticatedClient::new(config).unwrap();
 
     let req = CreateOutgoingPaymentRequest::FromQuote {
-        wallet_address: base.join("alice").unwrap().to_string(),
+        wallet_address: WalletAddressUrl::parse_dev(base.join("alice").unwrap().as_str()).unwrap(),
         quote_id: base.join("quotes/q1").unwrap().to_string(),
         metadata: None,
     };
@@ -244,6 +270,8 @@ async fn error_includes_status_code_and_reason() {
     assert_eq!(err.description, "HTTP request failed");
     assert_eq!(err.code, Some(404));
     assert_eq!(err.status.as_deref(), Some("Not Found"));
+    assert_eq!(err.kind(), &ErrorKind::Http { status: 404 });
+    assert!(!err.is_retryable());
 }
 
 #[tokio::test]
@@ -258,15 +286,16 @@ async fn json_decode_error_maps_to_client_error_without_status() {
         .mount(&server)
         .await;
 
-    let client = UnauthenticatedClient::new();
+    let client = UnauthenticatedClient::new().allow_http_wallet_addresses();
     let res = client
         .wallet_address()
-        .get(base.join("alice").unwrap().as_ref())
+        .get(base.join("alice").unwrap().as_str())
         .await;
     let err = res.expect_err("expected error");
     assert!(err.description.starts_with("HTTP error:"));
     assert!(err.code.is_none());
     assert!(err.status.is_none());
+    assert_eq!(err.kind(), &ErrorKind::Deserialize);
 }
 
 #[tokio::test]
@@ -276,7 +305,7 @@ async fn header_parse_error_with_invalid_token() {
     let base = Url::parse(&server.uri()).unwrap();
 
     let tmp = tempdir().unwrap();
-    let mut config = dummy_config(&server.uri());
+    let mut config = common::dummy_config(&server.uri());
     config.private_key_path = tmp.path().join("private.key");
     let client = AuthenticatedClient::new(config).unwrap();
 
@@ -290,6 +319,7 @@ async fn header_parse_error_with_invalid_token() {
         .await;
     let err = res.expect_err("expected error");
     assert!(err.description.starts_with("Header parse error:"));
+    assert_eq!(err.kind(), &ErrorKind::Validation);
 }
 
 #[tokio::test]
@@ -304,7 +334,7 @@ async fn revoke_token_204_no_content_succeeds() {
         .await;
 
     let tmp = tempdir().unwrap();
-    let mut config = dummy_config(&server.uri());
+    let mut config = common::dummy_config(&server.uri());
     config.private_key_path = tmp.path().join("private.key");
     let client = AuthenticatedClient::new(config).unwrap();
 
@@ -327,7 +357,7 @@ async fn cancel_grant_204_no_content_succeeds() {
         .await;
 
     let tmp = tempdir().unwrap();
-    let mut config = dummy_config(&server.uri());
+    let mut config = common::dummy_config(&server.uri());
     config.private_key_path = tmp.path().join("private.key");
     let client = AuthenticatedClient::new(config).unwrap();
 
@@ -337,3 +367,1294 @@ async fn cancel_grant_204_no_content_succeeds() {
         .await;
     assert!(res.is_ok());
 }
+
+#[tokio::test]
+async fn builder_with_in_memory_key_and_custom_http_client_signs_requests() {
+    let server = MockServer::start().await;
+
+    let base = Url::parse(&server.uri()).unwrap();
+    Mock::given(method("DELETE"))
+        .and(path(base.join("token/revoke").unwrap().path()))
+        .and(header("user-agent", "builder-test"))
+        .and(header_exists("Signature"))
+        .and(header_exists("Signature-Input"))
+        .respond_with(ResponseTemplate::new(204))
+        .mount(&server)
+        .await;
+
+    let http_client = reqwest::Client::builder()
+        .user_agent("builder-test")
+        .build()
+        .unwrap();
+    let client = common::client_builder(&server)
+        .http_client(http_client)
+        .build()
+        .unwrap();
+
+    let res = client
+        .token()
+        .revoke(base.join("token/revoke").unwrap().as_ref(), Some("token"))
+        .await;
+    assert!(res.is_ok());
+}
+
+#[test]
+fn builder_reports_missing_settings() {
+    let err = AuthenticatedClient::builder()
+        .build()
+        .err()
+        .expect("expected error");
+    assert_eq!(err.description, "Invalid client configuration");
+    assert_eq!(err.validation_errors.map(|errors| errors.len()), Some(3));
+}
+
+#[test]
+fn builder_rejects_http_settings_with_provided_client() {
+    let err = AuthenticatedClient::builder()
+        .key_id("test-key")
+        .wallet_address_url("https://rafiki.money/alice")
+        .signing_key(ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng))
+        .http_client(reqwest::Client::new())
+        .connect_timeout(std::time::Duration::from_secs(1))
+        .build()
+        .err()
+        .expect("expected error");
+    assert_eq!(
+        err.validation_errors,
+        Some(vec!["connect_timeout".to_string()])
+    );
+}
+
+#[test]
+fn builder_private_key_path_never_creates_files() {
+    let tmp = tempdir().unwrap();
+    let key_path = tmp.path().join("private.key");
+
+    let res = AuthenticatedClient::builder()
+        .key_id("test-key")
+        .wallet_address_url("https://rafiki.money/alice")
+        .private_key_path(&key_path)
+        .build();
+    assert!(res.is_err());
+    assert!(!key_path.exists());
+}
+
+/// Builds response headers signed by `signing_key` over `@status`, `content-type` and `content-digest`.
+fn signed_response_headers(
+    signing_key: &ed25519_dalek::SigningKey,
+    status: u16,
+    body: &str,
+) -> Vec<(String, String)> {
+    use ed25519_dalek::Signer;
+    use open_payments::http_signature::components::{create_signature_base, HttpMessage};
+    use open_payments::http_signature::{create_content_digest, DigestAlgorithm, SignatureParams};
+
+    let content_digest = create_content_digest(body.as_bytes(), DigestAlgorithm::Sha512);
+    let mut response = http::Response::new(Some(body.to_string()));
+    *response.status_mut() = http::StatusCode::from_u16(status).unwrap();
+    response
+        .headers_mut()
+        .insert("content-type", "application/json".parse().unwrap());
+    response
+        .headers_mut()
+        .insert("content-digest", content_digest.parse().unwrap());
+
+    let params = SignatureParams::new(["@status", "content-type", "content-digest"])
+        .with_created(chrono::Utc::now().timestamp())
+        .with_keyid("server-key");
+    let request = http::Request::new(None);
+    let base = create_signature_base(
+        &HttpMessage::Response {
+            response: &response,
+            request: Some(&request),
+        },
+        &params,
+    )
+    .unwrap();
+    let signature = signing_key.sign(base.as_bytes());
+
+    vec![
+        ("Content-Digest".into(), content_digest),
+        (
+            "Signature-Input".into(),
+            format!("sig1={}", params.serialize().unwrap()),
+        ),
+        (
+            "Signature".into(),
+            format!(
+                "sig1=:{}:",
+                base64::Engine::encode(
+                    &base64::engine::general_purpose::STANDARD,
+                    signature.to_bytes()
+                )
+            ),
+        ),
+    ]
+}
+
+#[tokio::test]
+async fn verify_responses_accepts_signed_and_rejects_tampered_responses() {
+    let server = MockServer::start().await;
+    let base = Url::parse(&server.uri()).unwrap();
+    let server_key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+
+    Mock::given(method("GET"))
+        .and(path("/jwks.json"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            open_payments::http_signature::Jwk::generate_jwks_json(&server_key, "server-key"),
+            "application/json",
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let body = serde_json::json!({
+        "access_token": {
+            "value": "new-token",
+            "manage": base.join("token/rotate").unwrap().to_string(),
+        }
+    })
+    .to_string();
+    let mut signed = ResponseTemplate::new(200).set_body_raw(body.clone(), "application/json");
+    for (name, value) in signed_response_headers(&server_key, 200, &body) {
+        signed = signed.insert_header(name.as_str(), value.as_str());
+    }
+    Mock::given(method("POST"))
+        .and(path("/token/rotate"))
+        .respond_with(signed.clone())
+        .mount(&server)
+        .await;
+
+    // Same signature headers, but a different body
+    let mut tampered = ResponseTemplate::new(200)
+        .set_body_raw(body.replace("new-token", "evil-token"), "application/json");
+    for (name, value) in signed_response_headers(&server_key, 200, &body) {
+        tampered = tampered.insert_header(name.as_str(), value.as_str());
+    }
+    Mock::given(method("POST"))
+        .and(path("/tampered/rotate"))
+        .respond_with(tampered)
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/unsigned/rotate"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(body.clone(), "application/json"))
+        .mount(&server)
+        .await;
+
+    let client = common::client_builder(&server)
+        .verify_responses()
+        .build()
+        .unwrap();
+
+    let rotated = client
+        .token()
+        .rotate(base.join("token/rotate").unwrap().as_ref(), Some("token"))
+        .await
+        .unwrap();
+    assert_eq!(rotated.access_token.value, "new-token");
+
+    for url in ["tampered/rotate", "unsigned/rotate"] {
+        let err = client
+            .token()
+            .rotate(base.join(url).unwrap().as_ref(), Some("token"))
+            .await
+            .expect_err("expected response verification to fail");
+        assert_eq!(err.kind(), &ErrorKind::ResponseSignature);
+        assert!(
+            err.description
+                .starts_with("Response signature verification failed"),
+            "{}",
+            err.description
+        );
+    }
+
+    // Without verification the unsigned response is accepted
+    let client = common::client(&server);
+    client
+        .token()
+        .rotate(
+            base.join("unsigned/rotate").unwrap().as_ref(),
+            Some("token"),
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn verify_responses_throttles_jwks_refetches_for_unknown_key_ids() {
+    let server = MockServer::start().await;
+    let base = Url::parse(&server.uri()).unwrap();
+    let server_key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+
+    // The JWKS does not contain the key ID the responses are signed with
+    Mock::given(method("GET"))
+        .and(path("/jwks.json"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            open_payments::http_signature::Jwk::generate_jwks_json(&server_key, "other-key"),
+            "application/json",
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let body = serde_json::json!({
+        "access_token": {
+            "value": "new-token",
+            "manage": base.join("token/rotate").unwrap().to_string(),
+        }
+    })
+    .to_string();
+    let mut signed = ResponseTemplate::new(200).set_body_raw(body.clone(), "application/json");
+    for (name, value) in signed_response_headers(&server_key, 200, &body) {
+        signed = signed.insert_header(name.as_str(), value.as_str());
+    }
+    Mock::given(method("POST"))
+        .and(path("/token/rotate"))
+        .respond_with(signed)
+        .expect(3)
+        .mount(&server)
+        .await;
+
+    let client = common::client_builder(&server)
+        .verify_responses()
+        .build()
+        .unwrap();
+
+    for _ in 0..3 {
+        let err = client
+            .token()
+            .rotate(base.join("token/rotate").unwrap().as_ref(), Some("token"))
+            .await
+            .expect_err("expected response verification to fail");
+        assert_eq!(err.kind(), &ErrorKind::ResponseSignature);
+    }
+}
+
+#[tokio::test]
+async fn gnap_error_body_is_parsed_into_client_error() {
+    let server = MockServer::start().await;
+
+    let base = Url::parse(&server.uri()).unwrap();
+    Mock::given(method("POST"))
+        .and(path("/auth"))
+        .respond_with(
+            ResponseTemplate::new(401)
+                .insert_header("x-request-id", "req-1")
+                .set_body_json(serde_json::json!({
+                    "error": {
+                        "code": "invalid_client",
+                        "description": "could not determine client"
+                    }
+                })),
+        )
+        .mount(&server)
+        .await;
+
+    let tmp = tempdir().unwrap();
+    let mut config = common::dummy_config(&server.uri());
+    config.private_key_path = tmp.path().join("private.key");
+    let client = AuthenticatedClient::new(config).unwrap();
+
+    let grant_request = open_payments::types::GrantRequest::new(
+        open_payments::types::AccessTokenRequest { access: vec![] },
+        None,
+    );
+    let err = client
+        .grant()
+        .request(base.join("auth").unwrap().as_ref(), &grant_request)
+        .await
+        .expect_err("expected error");
+
+    assert_eq!(
+        err.description,
+        "HTTP request failed: could not determine client"
+    );
+    assert_eq!(err.code, Some(401));
+    assert_eq!(err.gnap_error, Some(GnapErrorCode::InvalidClient));
+    assert_eq!(
+        err.kind(),
+        &ErrorKind::Gnap {
+            code: GnapErrorCode::InvalidClient
+        }
+    );
+    assert_eq!(
+        err.server_description.as_deref(),
+        Some("could not determine client")
+    );
+    let details = err.details.expect("details");
+    assert!(details["body"].as_str().unwrap().contains("invalid_client"));
+    assert_eq!(details["headers"]["x-request-id"], "req-1");
+}
+
+#[tokio::test]
+async fn openapi_validation_errors_are_collected() {
+    let server = MockServer::start().await;
+
+    let base = Url::parse(&server.uri()).unwrap();
+    Mock::given(method("GET"))
+        .and(path("/invalid"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+            "message": "request validation failed",
+            "errors": [
+                {"path": "/body/incomingAmount/value", "message": "must be string"},
+                {"instancePath": "/body/walletAddress", "message": "must match format \"uri\""},
+                "unexpected property"
+            ]
+        })))
+        .mount(&server)
+        .await;
+
+    let client = UnauthenticatedClient::new();
+    let err = client
+        .public_incoming_payments()
+        .get(base.join("invalid").unwrap().as_ref())
+        .await
+        .expect_err("expected error");
+
+    assert_eq!(
+        err.description,
+        "HTTP request failed: request validation failed"
+    );
+    assert_eq!(err.gnap_error, None);
+    assert_eq!(
+        err.validation_errors,
+        Some(vec![
+            "/body/incomingAmount/value: must be string".to_string(),
+            "/body/walletAddress: must match format \"uri\"".to_string(),
+            "unexpected property".to_string(),
+        ])
+    );
+}
+
+#[tokio::test]
+async fn rate_limited_response_is_retryable_with_retry_after() {
+    let server = MockServer::start().await;
+
+    let base = Url::parse(&server.uri()).unwrap();
+    Mock::given(method("GET"))
+        .and(path("/busy"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "7"))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/too-fast"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+            "error": {"code": "too_fast"}
+        })))
+        .mount(&server)
+        .await;
+
+    let client = UnauthenticatedClient::new();
+    let err = client
+        .public_incoming_payments()
+        .get(base.join("busy").unwrap().as_ref())
+        .await
+        .expect_err("expected error");
+    assert_eq!(err.kind(), &ErrorKind::Http { status: 429 });
+    assert!(err.is_retryable());
+    assert_eq!(err.retry_after(), Some(std::time::Duration::from_secs(7)));
+
+    let err = client
+        .public_incoming_payments()
+        .get(base.join("too-fast").unwrap().as_ref())
+        .await
+        .expect_err("expected error");
+    assert!(err.is_retryable());
+    assert_eq!(err.retry_after(), None);
+}
+
+#[tokio::test]
+async fn connection_failure_is_retryable_transport_error() {
+    // Bind and drop a listener to get a port nothing is listening on
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+
+    let client = UnauthenticatedClient::new().allow_http_wallet_addresses();
+    let err = client
+        .wallet_address()
+        .get(format!("http://{addr}/alice"))
+        .await
+        .expect_err("expected error");
+    assert_eq!(err.kind(), &ErrorKind::Transport);
+    assert!(err.is_retryable());
+}
+
+#[test]
+fn errors_without_a_response_are_told_apart_from_other_errors() {
+    let err = OpClientError::http("connection closed", None, None);
+    assert_eq!(err.kind(), &ErrorKind::Transport);
+    assert!(err.is_retryable());
+
+    let err = OpClientError::from(std::io::Error::new(
+        std::io::ErrorKind::PermissionDenied,
+        "read-only file system",
+    ));
+    assert_eq!(err.kind(), &ErrorKind::Io);
+    assert!(!err.is_retryable());
+}
+
+fn retrying_client(server: &MockServer, retry_policy: RetryPolicy) -> AuthenticatedClient {
+    common::client_builder(server)
+        .retry_policy(RetryPolicy {
+            initial_backoff: std::time::Duration::from_millis(1),
+            ..retry_policy
+        })
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn retry_policy_resends_idempotent_requests_with_fresh_signatures() {
+    let server = MockServer::start().await;
+
+    let base = Url::parse(&server.uri()).unwrap();
+    let payment = serde_json::json!({
+        "id": base.join("incoming-payments/123").unwrap().to_string(),
+        "walletAddress": base.join("alice").unwrap().to_string(),
+        "completed": true,
+        "receivedAmount": {"value": "0", "assetCode": "EUR", "assetScale": 2},
+        "createdAt": "2025-01-01T00:00:00Z",
+        "updatedAt": "2025-01-01T00:00:00Z"
+    });
+    Mock::given(method("POST"))
+        .and(path("/incoming-payments/123/complete"))
+        // `created` has a resolution of one second, so the retry waits at least that long
+        .respond_with(ResponseTemplate::new(503).insert_header("Retry-After", "1"))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/incoming-payments/123/complete"))
+        .and(header_exists("Signature"))
+        .and(header_exists("Signature-Input"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(&payment))
+        .mount(&server)
+        .await;
+
+    let client = retrying_client(&server, RetryPolicy::default());
+    let got = client
+        .incoming_payments()
+        .complete(
+            base.join("incoming-payments/123").unwrap().as_ref(),
+            Some("tok"),
+        )
+        .await
+        .unwrap();
+    assert!(got.completed);
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 2);
+    let created: Vec<i64> = requests
+        .iter()
+        .map(|request| {
+            let signature_input = request.headers["signature-input"].to_str().unwrap();
+            let (_, params) = parse_signature_input(signature_input)
+                .unwrap()
+                .into_iter()
+                .next()
+                .unwrap();
+            params.created().unwrap()
+        })
+        .collect();
+    assert!(created[1] > created[0], "{created:?}");
+    assert_ne!(
+        requests[0].headers["signature"],
+        requests[1].headers["signature"]
+    );
+}
+
+#[tokio::test]
+async fn retry_policy_does_not_resend_unsafe_requests_unless_enabled() {
+    let server = MockServer::start().await;
+
+    let base = Url::parse(&server.uri()).unwrap();
+    Mock::given(method("POST"))
+        .and(path("/outgoing-payments"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&server)
+        .await;
+
+    let req = CreateOutgoingPaymentRequest::FromQuote {
+        wallet_address: WalletAddressUrl::parse_dev(base.join("alice").unwrap().as_str()).unwrap(),
+        quote_id: base.join("quotes/q1").unwrap().to_string(),
+        metadata: None,
+    };
+
+    let client = retrying_client(&server, RetryPolicy::default());
+    let err = client
+        .outgoing_payments()
+        .create(&server.uri(), &req, Some("tok"))
+        .await
+        .expect_err("expected error");
+    assert!(err.is_retryable());
+    assert_eq!(server.received_requests().await.unwrap().len(), 1);
+
+    let client = retrying_client(
+        &server,
+        RetryPolicy {
+            max_attempts: 3,
+            retry_unsafe_methods: true,
+            ..RetryPolicy::default()
+        },
+    );
+    client
+        .outgoing_payments()
+        .create(&server.uri(), &req, Some("tok"))
+        .await
+        .expect_err("expected error");
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 4);
+    assert!(requests[1..]
+        .iter()
+        .all(|r| r.headers.contains_key("content-digest")));
+}
+
+fn incoming_payment_page(
+    base: &Url,
+    ids: &[&str],
+    end_cursor: &str,
+    has_next_page: bool,
+) -> serde_json::Value {
+    let result: Vec<_> = ids
+        .iter()
+        .map(|id| {
+            serde_json::json!({
+                "id": base.join(&format!("incoming-payments/{id}")).unwrap().to_string(),
+                "walletAddress": base.join("alice").unwrap().to_string(),
+                "completed": false,
+                "receivedAmount": {"value": "0", "assetCode": "EUR", "assetScale": 2},
+                "createdAt": "2025-01-01T00:00:00Z",
+                "updatedAt": "2025-01-01T00:00:00Z"
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "pagination": {
+            "startCursor": ids.first(),
+            "endCursor": end_cursor,
+            "hasNextPage": has_next_page,
+            "hasPreviousPage": false
+        },
+        "result": result
+    })
+}
+
+#[tokio::test]
+async fn incoming_payments_list_all_follows_cursors() {
+    let server = MockServer::start().await;
+
+    let base = Url::parse(&server.uri()).unwrap();
+    Mock::given(method("GET"))
+        .and(path("/incoming-payments"))
+        .and(query_param("first", "2"))
+        .and(query_param_is_missing("cursor"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(incoming_payment_page(
+                &base,
+                &["p1", "p2"],
+                "p2",
+                true,
+            )),
+        )
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/incoming-payments"))
+        .and(query_param("cursor", "p2"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(incoming_payment_page(
+                &base,
+                &["p3"],
+                "p3",
+                false,
+            )),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let tmp = tempdir().unwrap();
+    let mut config = common::dummy_config(&server.uri());
+    config.private_key_path = tmp.path().join("private.key");
+    let client = AuthenticatedClient::new(config).unwrap();
+    let wallet_address = base.join("alice").unwrap().to_string();
+
+    let payments = client
+        .incoming_payments()
+        .list_all(
+            &server.uri(),
+            &wallet_address,
+            ListOptions::new().first(2),
+            Some("tok"),
+        )
+        .await
+        .unwrap();
+    let ids: Vec<_> = payments
+        .iter()
+        .map(|p| p.id.rsplit('/').next().unwrap())
+        .collect();
+    assert_eq!(ids, ["p1", "p2", "p3"]);
+
+    // The second page is not requested once the item cap is reached
+    let payments = client
+        .incoming_payments()
+        .list_all(
+            &server.uri(),
+            &wallet_address,
+            ListOptions::new().first(2).max_items(1),
+            Some("tok"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(payments.len(), 1);
+
+    let err = client
+        .incoming_payments()
+        .list_all(
+            &server.uri(),
+            &wallet_address,
+            ListOptions::new().last(2),
+            Some("tok"),
+        )
+        .await
+        .expect_err("expected error");
+    assert_eq!(err.kind(), &ErrorKind::Validation);
+
+    // A single page request would ignore the item cap, so it is rejected unsent
+    let err = client
+        .incoming_payments()
+        .list(
+            &server.uri(),
+            &wallet_address,
+            &ListOptions::new().first(2).max_items(1),
+            Some("tok"),
+        )
+        .await
+        .expect_err("expected error");
+    assert_eq!(err.kind(), &ErrorKind::Validation);
+}
+
+#[tokio::test]
+async fn list_options_are_validated_and_sent_as_query() {
+    let server = MockServer::start().await;
+
+    let base = Url::parse(&server.uri()).unwrap();
+    Mock::given(method("GET"))
+        .and(path("/outgoing-payments"))
+        .and(query_param("cursor", "op5"))
+        .and(query_param("last", "10"))
+        .and(query_param("state", "COMPLETED"))
+        .and(query_param_is_missing("first"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "pagination": {"hasNextPage": true, "hasPreviousPage": false},
+            "result": []
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let tmp = tempdir().unwrap();
+    let mut config = common::dummy_config(&server.uri());
+    config.private_key_path = tmp.path().join("private.key");
+    let client = AuthenticatedClient::new(config).unwrap();
+    let wallet_address = base.join("alice").unwrap().to_string();
+
+    let page = client
+        .outgoing_payments()
+        .list(
+            &server.uri(),
+            &wallet_address,
+            &ListOptions::new()
+                .cursor("op5")
+                .last(10)
+                .filter("state", "COMPLETED"),
+            Some("tok"),
+        )
+        .await
+        .unwrap();
+    assert!(page.result.is_empty());
+
+    let err = client
+        .outgoing_payments()
+        .list(
+            &server.uri(),
+            &wallet_address,
+            &ListOptions::new().first(0).filter("cursor", "op1"),
+            Some("tok"),
+        )
+        .await
+        .expect_err("expected error");
+    assert_eq!(err.kind(), &ErrorKind::Validation);
+    assert_eq!(err.validation_errors.map(|e| e.len()), Some(2));
+}
+
+#[tokio::test]
+async fn backward_paging_without_a_page_size_sends_last() {
+    let server = MockServer::start().await;
+
+    let base = Url::parse(&server.uri()).unwrap();
+    Mock::given(method("GET"))
+        .and(path("/incoming-payments"))
+        .and(query_param("cursor", "ip5"))
+        .and(query_param("last", "100"))
+        .and(query_param_is_missing("first"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "pagination": {"hasNextPage": true, "hasPreviousPage": false},
+            "result": []
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let tmp = tempdir().unwrap();
+    let mut config = common::dummy_config(&server.uri());
+    config.private_key_path = tmp.path().join("private.key");
+    let client = AuthenticatedClient::new(config).unwrap();
+    let wallet_address = base.join("alice").unwrap().to_string();
+
+    let page = client
+        .incoming_payments()
+        .list(
+            &server.uri(),
+            &wallet_address,
+            &ListOptions::new()
+                .cursor("ip5")
+                .direction(PageDirection::Backward),
+            Some("tok"),
+        )
+        .await
+        .unwrap();
+    assert!(page.result.is_empty());
+}
+
+#[tokio::test]
+async fn grant_manager_rotates_expiring_tokens_and_selects_by_scope() {
+    use open_payments::types::{AccessItem, AccessToken, IncomingPaymentAction, QuoteAction};
+
+    let server = MockServer::start().await;
+
+    let base = Url::parse(&server.uri()).unwrap();
+    let wallet_address = base.join("alice").unwrap().to_string();
+    let access = vec![AccessItem::IncomingPayment {
+        actions: vec![
+            IncomingPaymentAction::Create,
+            IncomingPaymentAction::ReadAll,
+        ],
+        identifier: Some(wallet_address.clone()),
+    }];
+    Mock::given(method("POST"))
+        .and(path("/token/1"))
+        .and(header("authorization", "GNAP old-token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "access_token": {
+                "value": "new-token",
+                "manage": base.join("token/2").unwrap().to_string(),
+                "expires_in": 3600
+            }
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/incoming-payments/123"))
+        .and(header("authorization", "GNAP new-token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "id": base.join("incoming-payments/123").unwrap().to_string(),
+            "walletAddress": wallet_address,
+            "completed": false,
+            "receivedAmount": {"value": "0", "assetCode": "EUR", "assetScale": 2},
+            "createdAt": "2025-01-01T00:00:00Z",
+            "updatedAt": "2025-01-01T00:00:00Z"
+        })))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let tmp = tempdir().unwrap();
+    let mut config = common::dummy_config(&server.uri());
+    config.private_key_path = tmp.path().join("private.key");
+    let client = AuthenticatedClient::new(config).unwrap();
+
+    let manager = GrantManager::new(&client);
+    manager
+        .insert(
+            AccessToken {
+                value: "old-token".into(),
+                manage: base.join("token/1").unwrap().to_string(),
+                expires_in: Some(10),
+                access: Some(access.clone()),
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    let payment_url = base.join("incoming-payments/123").unwrap().to_string();
+    // Concurrent calls share one rotation, and the rotated token keeps the original access
+    let (first, second) = tokio::join!(
+        manager.get_incoming_payment(&payment_url, &wallet_address),
+        manager.get_incoming_payment(&payment_url, &wallet_address),
+    );
+    first.unwrap();
+    second.unwrap();
+    let tokens = manager.tokens().await;
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0].access_token.access, Some(access));
+
+    let err = manager
+        .token(&AccessScope::Quote {
+            action: QuoteAction::Create,
+        })
+        .await
+        .expect_err("expected error");
+    assert_eq!(err.kind(), &ErrorKind::Validation);
+    assert!(manager
+        .token(&AccessScope::IncomingPayment {
+            action: IncomingPaymentAction::Create,
+            wallet_address: Some(base.join("bob").unwrap().to_string()),
+        })
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn grant_manager_selects_the_token_of_the_payment_wallet_address() {
+    use open_payments::types::{AccessItem, AccessToken, OutgoingPaymentAction};
+
+    let server = MockServer::start().await;
+
+    let base = Url::parse(&server.uri()).unwrap();
+    let alice = base.join("alice").unwrap().to_string();
+    let bob = base.join("bob").unwrap().to_string();
+    Mock::given(method("GET"))
+        .and(path("/outgoing-payments/456"))
+        .and(header("authorization", "GNAP bob-token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "id": base.join("outgoing-payments/456").unwrap().to_string(),
+            "walletAddress": bob,
+            "failed": false,
+            "receiver": base.join("incoming-payments/123").unwrap().to_string(),
+            "debitAmount": {"value": "100", "assetCode": "EUR", "assetScale": 2},
+            "receiveAmount": {"value": "100", "assetCode": "EUR", "assetScale": 2},
+            "sentAmount": {"value": "0", "assetCode": "EUR", "assetScale": 2},
+            "grantSpentDebitAmount": {"value": "0", "assetCode": "EUR", "assetScale": 2},
+            "grantSpentReceiveAmount": {"value": "0", "assetCode": "EUR", "assetScale": 2},
+            "createdAt": "2025-01-01T00:00:00Z",
+            "updatedAt": "2025-01-01T00:00:00Z"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let tmp = tempdir().unwrap();
+    let mut config = common::dummy_config(&server.uri());
+    config.private_key_path = tmp.path().join("private.key");
+    let client = AuthenticatedClient::new(config).unwrap();
+
+    let manager = GrantManager::new(&client);
+    for (value, wallet_address) in [("alice-token", &alice), ("bob-token", &bob)] {
+        manager
+            .insert(
+                AccessToken {
+                    value: value.into(),
+                    manage: base.join(&format!("token/{value}")).unwrap().to_string(),
+                    expires_in: None,
+                    access: Some(vec![AccessItem::OutgoingPayment {
+                        actions: vec![OutgoingPaymentAction::Read],
+                        identifier: wallet_address.clone(),
+                        limits: None,
+                    }]),
+                },
+                None,
+            )
+            .await
+            .unwrap();
+    }
+
+    let payment_url = base.join("outgoing-payments/456").unwrap().to_string();
+    manager
+        .get_outgoing_payment(&payment_url, &bob)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn grant_manager_keeps_token_when_rotation_fails_transiently() {
+    use open_payments::types::{AccessItem, AccessToken, IncomingPaymentAction};
+
+    let server = MockServer::start().await;
+
+    let base = Url::parse(&server.uri()).unwrap();
+    Mock::given(method("POST"))
+        .and(path("/token/1"))
+        .respond_with(ResponseTemplate::new(503))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let tmp = tempdir().unwrap();
+    let mut config = common::dummy_config(&server.uri());
+    config.private_key_path = tmp.path().join("private.key");
+    let client = AuthenticatedClient::new(config).unwrap();
+
+    let manager = GrantManager::new(&client);
+    manager
+        .insert(
+            AccessToken {
+                value: "old-token".into(),
+                manage: base.join("token/1").unwrap().to_string(),
+                expires_in: Some(10),
+                access: Some(vec![AccessItem::IncomingPayment {
+                    actions: vec![IncomingPaymentAction::Read],
+                    identifier: None,
+                }]),
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    let scope = AccessScope::IncomingPayment {
+        action: IncomingPaymentAction::Read,
+        wallet_address: None,
+    };
+    for _ in 0..2 {
+        let err = manager.token(&scope).await.expect_err("expected error");
+        assert!(err.is_retryable());
+        // The token may still be valid, so it is kept for the next attempt
+        assert_eq!(manager.tokens().await.len(), 1);
+    }
+}
+
+#[tokio::test]
+async fn poll_until_granted_follows_continuations_until_a_token_is_issued() {
+    use open_payments::types::{Continue, ContinueAccessToken};
+    use wiremock::matchers::body_json;
+
+    let server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/continue/1"))
+        .and(header("authorization", "GNAP continue-1"))
+        .and(body_json(serde_json::json!({})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "continue": {
+                "access_token": {"value": "continue-2"},
+                "uri": format!("{}/continue/2", server.uri()),
+                "wait": 0
+            }
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/continue/2"))
+        .and(header("authorization", "GNAP continue-2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "access_token": {
+                "value": "granted",
+                "manage": format!("{}/token/1", server.uri())
+            },
+            "continue": {
+                "access_token": {"value": "continue-3"},
+                "uri": format!("{}/continue/3", server.uri())
+            }
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/continue/denied"))
+        .respond_with(ResponseTemplate::new(401).set_body_json(serde_json::json!({
+            "error": {"code": "user_denied"}
+        })))
+        .mount(&server)
+        .await;
+
+    let tmp = tempdir().unwrap();
+    let mut config = common::dummy_config(&server.uri());
+    config.private_key_path = tmp.path().join("private.key");
+    let client = AuthenticatedClient::new(config).unwrap();
+    let continuation = |uri: &str, token: &str, wait: i64| Continue {
+        access_token: ContinueAccessToken {
+            value: token.into(),
+        },
+        uri: format!("{}{uri}", server.uri()),
+        wait: Some(wait),
+    };
+
+    let (access_token, continue_) = client
+        .grant()
+        .poll_until_granted(
+            &continuation("/continue/1", "continue-1", 0),
+            &PollOptions::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(access_token.value, "granted");
+    assert_eq!(continue_.access_token.value, "continue-3");
+
+    let err = client
+        .grant()
+        .poll_until_granted(
+            &continuation("/continue/denied", "continue-1", 0),
+            &PollOptions::default(),
+        )
+        .await
+        .expect_err("expected error");
+    assert_eq!(err.gnap_error, Some(GnapErrorCode::UserDenied));
+
+    let err = client
+        .grant()
+        .poll_until_granted(
+            &continuation("/continue/denied", "continue-1", 60),
+            &PollOptions {
+                timeout: Some(std::time::Duration::from_secs(1)),
+                ..PollOptions::default()
+            },
+        )
+        .await
+        .expect_err("expected error");
+    assert_eq!(err.kind(), &ErrorKind::Timeout);
+}
+
+#[tokio::test]
+async fn subject_request_asks_for_the_wallet_address_without_an_access_token() {
+    use open_payments::types::{InteractRequest, InteractStart, SubjectIdFormat};
+    use wiremock::matchers::body_json;
+
+    let server = MockServer::start().await;
+    let wallet_address = format!("{}/alice", server.uri());
+    Mock::given(method("POST"))
+        .and(path("/auth"))
+        .and(body_json(serde_json::json!({
+            "subject": {"sub_ids": [{"id": wallet_address, "format": "uri"}]},
+            "client": wallet_address,
+            "interact": {"start": ["redirect"], "finish": null}
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "subject": {"sub_ids": [{"id": wallet_address, "format": "uri"}]},
+            "continue": {
+                "access_token": {"value": "continue-1"},
+                "uri": format!("{}/continue/1", server.uri())
+            }
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let tmp = tempdir().unwrap();
+    let mut config = common::dummy_config(&server.uri());
+    config.private_key_path = tmp.path().join("private.key");
+    let client = AuthenticatedClient::new(config).unwrap();
+
+    let response = client
+        .grant()
+        .request_subject(
+            &format!("{}/auth", server.uri()),
+            &wallet_address,
+            InteractRequest {
+                start: vec![InteractStart::Redirect],
+                finish: None,
+            },
+        )
+        .await
+        .unwrap();
+
+    let subject = response.subject().expect("subject");
+    assert_eq!(subject.sub_ids[0].id, wallet_address);
+    assert_eq!(subject.sub_ids[0].format, SubjectIdFormat::Uri);
+    assert!(response.access_token().is_none());
+    assert_eq!(
+        response.continue_().map(|c| c.access_token.value.as_str()),
+        Some("continue-1")
+    );
+}
+
+#[tokio::test]
+async fn wallet_address_did_document_is_fetched_and_keys_converted() {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+    use ed25519_dalek::SigningKey;
+
+    let server = MockServer::start().await;
+    let wallet = common::wallet(&server);
+    let did = format!(
+        "did:web:{}",
+        server.address().to_string().replace(':', "%3A")
+    );
+    let jwk_key = SigningKey::from_bytes(&[1; 32]).verifying_key();
+    let multibase_key = SigningKey::from_bytes(&[2; 32]).verifying_key();
+    let mut multicodec = vec![0xed, 0x01];
+    multicodec.extend_from_slice(multibase_key.as_bytes());
+
+    Mock::given(method("GET"))
+        .and(path("/alice/did.json"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "@context": ["https://www.w3.org/ns/did/v1"],
+            "id": did,
+            "verificationMethod": [{
+                "id": format!("{did}#key-1"),
+                "type": "JsonWebKey2020",
+                "controller": did,
+                "publicKeyJwk": {
+                    "kty": "OKP",
+                    "crv": "Ed25519",
+                    "x": URL_SAFE_NO_PAD.encode(jwk_key.as_bytes())
+                }
+            }],
+            "authentication": [
+                "#key-1",
+                {
+                    "id": format!("{did}#key-2"),
+                    "type": "Ed25519VerificationKey2020",
+                    "controller": did,
+                    "publicKeyMultibase": format!("z{}", bs58::encode(multicodec).into_string())
+                },
+                {
+                    "id": format!("{did}#key-3"),
+                    "type": "JsonWebKey2020",
+                    "controller": did,
+                    "publicKeyJwk": {"kty": "OKP", "crv": "Ed25519", "x": "c2hvcnQ"}
+                }
+            ],
+            "service": [{
+                "id": format!("{did}#open-payments"),
+                "type": "OpenPayments",
+                "serviceEndpoint": wallet.id
+            }]
+        })))
+        .mount(&server)
+        .await;
+
+    let client = UnauthenticatedClient::new();
+    let document = client
+        .wallet_address()
+        .get_did_document(&wallet)
+        .await
+        .unwrap();
+    assert_eq!(document.id, did);
+    assert_eq!(document.service[0].type_, "OpenPayments");
+
+    let methods: Vec<_> = document.authentication_methods().collect();
+    assert_eq!(methods.len(), 3);
+    assert_eq!(methods[0].verifying_key().unwrap(), jwk_key);
+    assert_eq!(methods[1].verifying_key().unwrap(), multibase_key);
+    let jwk = methods[1].to_json_web_key().unwrap();
+    assert_eq!(jwk.kid, format!("{did}#key-2"));
+    assert_eq!(jwk.x, URL_SAFE_NO_PAD.encode(multibase_key.as_bytes()));
+
+    // Malformed keys are reported as errors rather than panicking
+    let error = methods[2].verifying_key().unwrap_err();
+    assert_eq!(error.kind(), &ErrorKind::Validation);
+
+    // So are wallet addresses without a DID document
+    let missing = WalletAddress {
+        id: format!("{}/bob", server.uri()),
+        ..wallet
+    };
+    let error = client
+        .wallet_address()
+        .get_did_document(&missing)
+        .await
+        .unwrap_err();
+    assert_eq!(error.kind(), &ErrorKind::Http { status: 404 });
+}
+
+#[tokio::test]
+async fn clients_managing_tokens_use_their_granted_tokens_for_resource_calls() {
+    use open_payments::types::{AccessItem, AccessTokenRequest, GrantRequest, QuoteAction};
+
+    let server = MockServer::start().await;
+
+    let base = Url::parse(&server.uri()).unwrap();
+    let manage = base.join("token/1").unwrap().to_string();
+    Mock::given(method("POST"))
+        .and(path("/auth"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "access_token": {"value": "granted-token", "manage": manage},
+            "continue": {
+                "access_token": {"value": "continue-token"},
+                "uri": base.join("continue/1").unwrap().to_string()
+            }
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/token/1"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/quotes/q1"))
+        .and(header("authorization", "GNAP granted-token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "id": base.join("quotes/q1").unwrap().to_string(),
+            "walletAddress": base.join("alice").unwrap().to_string(),
+            "receiver": base.join("incoming-payments/123").unwrap().to_string(),
+            "receiveAmount": {"value": "10", "assetCode": "EUR", "assetScale": 2},
+            "debitAmount": {"value": "110", "assetCode": "EUR", "assetScale": 2},
+            "method": "ilp",
+            "createdAt": "2025-01-01T00:00:00Z"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = common::client_builder(&server)
+        .manage_tokens()
+        .build()
+        .unwrap();
+    let grant = GrantRequest::new(
+        AccessTokenRequest {
+            access: vec![AccessItem::Quote {
+                actions: vec![QuoteAction::Read],
+            }],
+        },
+        None,
+    );
+    client
+        .grant()
+        .request(base.join("auth").unwrap().as_ref(), &grant)
+        .await
+        .unwrap();
+    assert_eq!(GrantManager::new(&client).tokens().await.len(), 1);
+
+    let quote_url = base.join("quotes/q1").unwrap().to_string();
+    client.quotes().get(&quote_url, None).await.unwrap();
+
+    // Calls fail instead of going out without a token once no tracked token grants them
+    let err = client
+        .quotes()
+        .create(
+            &server.uri(),
+            &CreateQuoteRequest::NoAmountQuote {
+                wallet_address: WalletAddressUrl::parse_dev(base.join("alice").unwrap().as_str())
+                    .unwrap(),
+                receiver: Receiver(base.join("incoming-payments/123").unwrap().to_string()),
+                method: PaymentMethodType::Ilp,
+            },
+            None,
+        )
+        .await
+        .expect_err("expected error");
+    assert_eq!(err.kind(), &ErrorKind::Validation);
+
+    client
+        .token()
+        .revoke(&manage, Some("granted-token"))
+        .await
+        .unwrap();
+    assert!(GrantManager::new(&client).tokens().await.is_empty());
+    let err = client
+        .quotes()
+        .get(&quote_url, None)
+        .await
+        .expect_err("expected error");
+    assert_eq!(err.kind(), &ErrorKind::Validation);
+}
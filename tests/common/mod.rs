@@ -0,0 +1,44 @@
+//! Fixtures shared by the integration tests.
+#![allow(dead_code)]
+
+use open_payments::client::{AuthenticatedClient, ClientBuilder, ClientConfig};
+use open_payments::types::WalletAddress;
+use wiremock::MockServer;
+
+/// Returns a configuration for the wallet address `{base}/alice`.
+pub fn dummy_config(base: &str) -> ClientConfig {
+    ClientConfig {
+        key_id: "test-key".into(),
+        private_key_path: std::path::PathBuf::from("tests/private.key"),
+        jwks_path: None,
+        wallet_address_url: format!("{base}/alice"),
+    }
+}
+
+/// Returns a builder for a client of `{server}/alice` with a fresh signing key.
+///
+/// The mock server does not use TLS, so `http` wallet addresses are allowed.
+pub fn client_builder(server: &MockServer) -> ClientBuilder {
+    AuthenticatedClient::builder()
+        .allow_http_wallet_addresses()
+        .key_id("test-key")
+        .wallet_address_url(format!("{}/alice", server.uri()))
+        .signing_key(ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng))
+}
+
+/// Returns a client of `{server}/alice` with a fresh signing key.
+pub fn client(server: &MockServer) -> AuthenticatedClient {
+    client_builder(server).build().unwrap()
+}
+
+/// Returns the wallet address `{server}/alice`, served by the mock server.
+pub fn wallet(server: &MockServer) -> WalletAddress {
+    WalletAddress {
+        id: format!("{}/alice", server.uri()),
+        public_name: None,
+        asset_code: "EUR".into(),
+        asset_scale: 2,
+        auth_server: format!("{}/auth", server.uri()),
+        resource_server: server.uri(),
+    }
+}
@@ -49,7 +49,7 @@ async fn test_grant_flows() {
             assert!(!continue_.access_token.value.is_empty());
             continue_
         }
-        GrantResponse::WithInteraction { .. } => {
+        GrantResponse::WithInteraction { .. } | GrantResponse::WithSubject { .. } => {
             panic!("Unexpected interaction required for incoming payments");
         }
     };
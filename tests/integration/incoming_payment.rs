@@ -1,5 +1,5 @@
 use crate::integration::common::TestSetup;
-use open_payments::client::{AuthenticatedResources, UnauthenticatedResources};
+use open_payments::client::{AuthenticatedResources, ListOptions, UnauthenticatedResources};
 use open_payments::types::{
     AccessItem, AccessTokenRequest, Amount, GrantRequest, GrantResponse, IncomingPaymentAction,
     IncomingPaymentRequest,
@@ -38,7 +38,7 @@ async fn get_access_token(test_setup: &mut TestSetup) -> String {
 
     let access_token = match response {
         GrantResponse::WithToken { access_token, .. } => access_token,
-        GrantResponse::WithInteraction { .. } => {
+        GrantResponse::WithInteraction { .. } | GrantResponse::WithSubject { .. } => {
             panic!("Unexpected interaction required for incoming payments");
         }
     };
@@ -53,7 +53,7 @@ async fn test_incoming_payment_flows() {
     let access_token = get_access_token(&mut test_setup).await;
 
     let request = IncomingPaymentRequest {
-        wallet_address: test_setup.wallet_address.clone(),
+        wallet_address: test_setup.wallet_address.parse().unwrap(),
         incoming_amount: Some(Amount {
             value: "100".to_string(),
             asset_code: "EUR".to_string(),
@@ -96,9 +96,7 @@ async fn test_incoming_payment_flows() {
         .list(
             &test_setup.resource_server_url,
             &test_setup.wallet_address,
-            None,
-            Some(10),
-            None,
+            &ListOptions::new().first(10),
             Some(&access_token),
         )
         .await
@@ -2,9 +2,9 @@ use crate::integration::common::TestSetup;
 use open_payments::client::{AuthenticatedResources, UnauthenticatedResources};
 use open_payments::types::{
     AccessItem, AccessTokenRequest, Amount, ContinueResponse, CreateOutgoingPaymentRequest,
-    CreateQuoteRequest, GrantRequest, GrantResponse, IncomingPaymentAction, IncomingPaymentRequest,
-    InteractFinish, InteractRequest, OutgoingPaymentAction, PaymentMethodType, QuoteAction,
-    Receiver,
+    CreateQuoteRequest, FinishMethod, GrantRequest, GrantResponse, IncomingPaymentAction,
+    IncomingPaymentRequest, InteractFinish, InteractRequest, InteractStart, OutgoingPaymentAction,
+    PaymentMethodType, QuoteAction, Receiver,
 };
 use thirtyfour::prelude::*;
 
@@ -67,13 +67,13 @@ async fn test_outgoing_payment_flow_with_interaction() {
 
     let ip_access_token = match ip_grant {
         GrantResponse::WithToken { access_token, .. } => access_token.value,
-        GrantResponse::WithInteraction { .. } => {
+        GrantResponse::WithInteraction { .. } | GrantResponse::WithSubject { .. } => {
             panic!("Unexpected interaction for incoming payment creation")
         }
     };
 
     let incoming_req = IncomingPaymentRequest {
-        wallet_address: wallet_address.id.clone(),
+        wallet_address: wallet_address.id.parse().unwrap(),
         incoming_amount: Some(Amount {
             value: "1000".into(),
             asset_code: wallet_address.asset_code.clone(),
@@ -273,13 +273,13 @@ async fn test_outgoing_payment_flow_with_interaction() {
         .expect("Request quote grant");
     let quote_token = match quote_grant {
         GrantResponse::WithToken { access_token, .. } => access_token.value,
-        GrantResponse::WithInteraction { .. } => {
+        GrantResponse::WithInteraction { .. } | GrantResponse::WithSubject { .. } => {
             panic!("Unexpected interaction required for quote grant")
         }
     };
 
     let quote_req = CreateQuoteRequest::FixedReceiveAmountQuote {
-        wallet_address: wallet_address.id.clone(),
+        wallet_address: wallet_address.id.parse().unwrap(),
         receiver: Receiver(incoming_payment.id.clone()),
         method: PaymentMethodType::Ilp,
         receive_amount: Amount {
@@ -303,9 +303,9 @@ async fn test_outgoing_payment_flow_with_interaction() {
     let consent_selector =
         std::env::var("CONSENT_SELECTOR").unwrap_or_else(|_| "button[aria-label='accept']".into());
     let op_interact = InteractRequest {
-        start: vec!["redirect".into()],
+        start: vec![InteractStart::Redirect],
         finish: Some(InteractFinish {
-            method: "redirect".into(),
+            method: FinishMethod::Redirect,
             uri: "http://localhost/callback".into(),
             nonce: "op-nonce".into(),
         }),
@@ -338,7 +338,7 @@ async fn test_outgoing_payment_flow_with_interaction() {
         } => {
             perform_interaction_and_continue(
                 &webdriver_url,
-                &interact.redirect,
+                interact.redirect.as_str(),
                 &consent_selector,
                 &continue_.uri,
                 &continue_.access_token.value,
@@ -348,11 +348,12 @@ async fn test_outgoing_payment_flow_with_interaction() {
             .await
         }
         GrantResponse::WithToken { access_token, .. } => Some(access_token.value),
+        GrantResponse::WithSubject { access_token, .. } => access_token.map(|t| t.value),
     }
     .expect("Get outgoing payment token");
 
     let req = CreateOutgoingPaymentRequest::FromQuote {
-        wallet_address: wallet_address.id.clone(),
+        wallet_address: wallet_address.id.parse().unwrap(),
         quote_id: quote.id,
         metadata: None,
     };
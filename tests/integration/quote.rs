@@ -37,7 +37,7 @@ async fn get_access_token(test_setup: &mut TestSetup) -> String {
 
     let access_token = match response {
         GrantResponse::WithToken { access_token, .. } => access_token,
-        GrantResponse::WithInteraction { .. } => {
+        GrantResponse::WithInteraction { .. } | GrantResponse::WithSubject { .. } => {
             panic!("Unexpected interaction required for grant request");
         }
     };
@@ -47,7 +47,7 @@ async fn get_access_token(test_setup: &mut TestSetup) -> String {
 
 async fn create_incoming_payment(test_setup: &TestSetup, access_token: &str) -> String {
     let request = IncomingPaymentRequest {
-        wallet_address: test_setup.wallet_address.clone(),
+        wallet_address: test_setup.wallet_address.parse().unwrap(),
         incoming_amount: Some(Amount {
             value: "1000".to_string(),
             asset_code: "EUR".to_string(),
@@ -81,7 +81,7 @@ async fn test_quote_flows() {
 
     // Test quote with no amount
     let request = QuoteRequest::NoAmountQuote {
-        wallet_address: test_setup.wallet_address.clone(),
+        wallet_address: test_setup.wallet_address.parse().unwrap(),
         receiver: Receiver(incoming_payment_url.clone()),
         method: PaymentMethodType::Ilp,
     };
@@ -101,7 +101,7 @@ async fn test_quote_flows() {
 
     // Test quote with fixed send/debit amount
     let request = QuoteRequest::FixedSendAmountQuote {
-        wallet_address: test_setup.wallet_address.clone(),
+        wallet_address: test_setup.wallet_address.parse().unwrap(),
         receiver: Receiver(incoming_payment_url.clone()),
         method: PaymentMethodType::Ilp,
         debit_amount: Amount {
@@ -137,7 +137,7 @@ async fn test_quote_flows() {
 
     // Test quote with fixed receive amount
     let request = QuoteRequest::FixedReceiveAmountQuote {
-        wallet_address: test_setup.wallet_address.clone(),
+        wallet_address: test_setup.wallet_address.parse().unwrap(),
         receiver: Receiver(incoming_payment_url),
         method: PaymentMethodType::Ilp,
         receive_amount: Amount {
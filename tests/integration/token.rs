@@ -31,7 +31,7 @@ async fn get_access_token(test_setup: &mut TestSetup) -> AccessToken {
 
     match response {
         GrantResponse::WithToken { access_token, .. } => access_token,
-        GrantResponse::WithInteraction { .. } => {
+        GrantResponse::WithInteraction { .. } | GrantResponse::WithSubject { .. } => {
             panic!("Unexpected interaction required for grant request");
         }
     }
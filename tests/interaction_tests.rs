@@ -0,0 +1,270 @@
+mod common;
+
+use open_payments::client::{
+    interaction_hash, verify_interaction_hash, AuthenticatedResources, ErrorKind, InteractRedirect,
+    PushFinishHandler,
+};
+use open_payments::http_signature::DigestAlgorithm;
+use open_payments::types::{
+    ContinueResponse, FinishMethod, InteractFinish, InteractPush, InteractResponse,
+};
+use wiremock::matchers::{body_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const GRANT_ENDPOINT: &str = "https://auth.example/";
+
+#[test]
+fn interaction_hash_matches_known_values() {
+    assert_eq!(
+        interaction_hash(
+            "client-nonce",
+            "finish-nonce",
+            "ref-123",
+            GRANT_ENDPOINT,
+            DigestAlgorithm::Sha512
+        ),
+        "73Ac7ugNYEwqxFY7_Paz75NRH2lFeEp_iBIQ-8RGesKYLwxTlnZQqtv1jAs8TTq8ZUrIo-Fhfxk_vNE1HWDlGQ"
+    );
+
+    // Rafiki encodes the hash with standard base64
+    let rafiki_hash = "pjuBCXYdcDn+1LYbXZ4HU55kN2K8Jl/sEmXYKJnnWps=";
+    verify_interaction_hash(
+        "client-nonce",
+        "finish-nonce",
+        "ref-123",
+        GRANT_ENDPOINT,
+        rafiki_hash,
+        DigestAlgorithm::Sha256,
+    )
+    .unwrap();
+}
+
+#[test]
+fn hash_computed_with_another_algorithm_is_rejected() {
+    let sha512 = interaction_hash(
+        "client-nonce",
+        "finish-nonce",
+        "ref-123",
+        GRANT_ENDPOINT,
+        DigestAlgorithm::Sha512,
+    );
+    verify_interaction_hash(
+        "client-nonce",
+        "finish-nonce",
+        "ref-123",
+        GRANT_ENDPOINT,
+        &sha512,
+        DigestAlgorithm::Sha512,
+    )
+    .unwrap();
+
+    // A client expecting sha-256 does not fall back to the algorithm the hash suggests
+    let error = verify_interaction_hash(
+        "client-nonce",
+        "finish-nonce",
+        "ref-123",
+        GRANT_ENDPOINT,
+        &sha512,
+        DigestAlgorithm::Sha256,
+    )
+    .unwrap_err();
+    assert_eq!(error.kind(), &ErrorKind::Signature);
+}
+
+#[test]
+fn redirect_is_parsed_and_verified() {
+    let hash = interaction_hash(
+        "client-nonce",
+        "finish-nonce",
+        "ref-123",
+        GRANT_ENDPOINT,
+        DigestAlgorithm::Sha256,
+    );
+    let redirect = InteractRedirect::parse(&format!(
+        "http://localhost/?hash={hash}&interact_ref=ref-123"
+    ))
+    .unwrap();
+    assert_eq!(redirect.interact_ref, "ref-123");
+    redirect
+        .verify(
+            "client-nonce",
+            "finish-nonce",
+            GRANT_ENDPOINT,
+            DigestAlgorithm::Sha256,
+        )
+        .unwrap();
+
+    // Any change to the bound values is detected
+    let forged = InteractRedirect {
+        interact_ref: "ref-456".into(),
+        ..redirect.clone()
+    };
+    let error = forged
+        .verify(
+            "client-nonce",
+            "finish-nonce",
+            GRANT_ENDPOINT,
+            DigestAlgorithm::Sha256,
+        )
+        .unwrap_err();
+    assert_eq!(error.kind(), &ErrorKind::Signature);
+    assert!(redirect
+        .verify(
+            "client-nonce",
+            "finish-nonce",
+            "https://evil.example/",
+            DigestAlgorithm::Sha256
+        )
+        .is_err());
+    assert!(redirect
+        .verify(
+            "client-nonce",
+            "finish-nonce-2",
+            GRANT_ENDPOINT,
+            DigestAlgorithm::Sha256
+        )
+        .is_err());
+
+    let truncated = InteractRedirect {
+        hash: hash[..20].to_string(),
+        ..redirect
+    };
+    assert!(truncated
+        .verify(
+            "client-nonce",
+            "finish-nonce",
+            GRANT_ENDPOINT,
+            DigestAlgorithm::Sha256
+        )
+        .is_err());
+}
+
+#[test]
+fn redirect_without_parameters_is_rejected() {
+    let error = InteractRedirect::parse("http://localhost/?result=grant_rejected").unwrap_err();
+    assert_eq!(error.kind(), &ErrorKind::Validation);
+    assert_eq!(
+        error.validation_errors.as_deref(),
+        Some(
+            &[
+                "missing hash parameter".to_string(),
+                "missing interact_ref parameter".to_string(),
+                "interaction result: grant_rejected".to_string(),
+            ][..]
+        )
+    );
+}
+
+fn push_request(push: &InteractPush) -> http::Request<Vec<u8>> {
+    http::Request::post("https://app.example/gnap/push")
+        .header("content-type", "application/json")
+        .body(serde_json::to_vec(push).unwrap())
+        .unwrap()
+}
+
+#[test]
+fn push_handler_only_accepts_verified_pushes() {
+    let handler = PushFinishHandler::new();
+    let finish = InteractFinish::new(FinishMethod::Push, "https://app.example/gnap/push");
+    let interact = InteractResponse {
+        redirect: "https://auth.example/interact/1".parse().unwrap(),
+        finish: "finish-nonce".into(),
+    };
+    let _pending = handler.expect(&finish, &interact, GRANT_ENDPOINT);
+
+    let forged = InteractPush {
+        hash: interaction_hash(
+            "guessed-nonce",
+            "finish-nonce",
+            "ref-1",
+            GRANT_ENDPOINT,
+            DigestAlgorithm::Sha256,
+        ),
+        interact_ref: "ref-1".into(),
+    };
+    assert_eq!(handler.handle(&push_request(&forged)).status(), 400);
+
+    let get = http::Request::get("https://app.example/gnap/push")
+        .body(Vec::new())
+        .unwrap();
+    assert_eq!(handler.handle(&get).status(), 405);
+
+    let push = InteractPush {
+        hash: interaction_hash(
+            &finish.nonce,
+            "finish-nonce",
+            "ref-1",
+            GRANT_ENDPOINT,
+            DigestAlgorithm::Sha256,
+        ),
+        interact_ref: "ref-1".into(),
+    };
+    push.verify(
+        &finish.nonce,
+        "finish-nonce",
+        GRANT_ENDPOINT,
+        DigestAlgorithm::Sha256,
+    )
+    .unwrap();
+    assert_eq!(handler.handle(&push_request(&push)).status(), 200);
+    // The grant is resolved once, so a replayed push is rejected
+    assert_eq!(handler.handle(&push_request(&push)).status(), 400);
+}
+
+#[tokio::test]
+async fn continue_after_push_continues_with_the_pushed_reference() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/continue/1"))
+        .and(body_json(serde_json::json!({"interact_ref": "ref-1"})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "access_token": {
+                "value": "granted-token",
+                "manage": format!("{}/token/1", server.uri())
+            },
+            "continue": {
+                "access_token": {"value": "continue-token-2"},
+                "uri": format!("{}/continue/1", server.uri())
+            }
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = common::client(&server);
+
+    let handler = PushFinishHandler::new();
+    let finish = InteractFinish::new(FinishMethod::Push, "https://app.example/gnap/push");
+    let interact = InteractResponse {
+        redirect: "https://auth.example/interact/1".parse().unwrap(),
+        finish: "finish-nonce".into(),
+    };
+    let pending = handler.expect(&finish, &interact, GRANT_ENDPOINT);
+
+    let push = InteractPush {
+        hash: interaction_hash(
+            &finish.nonce,
+            "finish-nonce",
+            "ref-1",
+            GRANT_ENDPOINT,
+            DigestAlgorithm::Sha256,
+        ),
+        interact_ref: "ref-1".into(),
+    };
+    // The reference is kept until the grant waits for it
+    assert_eq!(handler.handle(&push_request(&push)).status(), 200);
+
+    let response = client
+        .grant()
+        .continue_after_push(
+            pending,
+            &format!("{}/continue/1", server.uri()),
+            Some("continue-token"),
+        )
+        .await
+        .unwrap();
+    assert!(matches!(
+        response,
+        ContinueResponse::WithToken { access_token, .. } if access_token.value == "granted-token"
+    ));
+}
@@ -0,0 +1,234 @@
+mod common;
+
+use ed25519_dalek::SigningKey;
+use http::{HeaderMap, Request};
+use open_payments::client::ErrorKind;
+use open_payments::client::{JwksKeyResolver, UnauthenticatedClient};
+use open_payments::http_signature::{
+    create_signature_headers, validate_signature_with_resolver, Ed25519Signer, HttpSignatureError,
+    Jwk, KeyResolver, SignOptions, ValidationPolicy,
+};
+use open_payments::types::WalletAddress;
+use std::time::Duration;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn mount_jwks(server: &MockServer, signing_key: &SigningKey, kid: &str, times: u64) {
+    mount_wallet_jwks(server, "alice", signing_key, kid, times).await;
+}
+
+async fn mount_wallet_jwks(
+    server: &MockServer,
+    name: &str,
+    signing_key: &SigningKey,
+    kid: &str,
+    times: u64,
+) {
+    let wallet = WalletAddress {
+        id: format!("{}/{name}", server.uri()),
+        ..common::wallet(server)
+    };
+    Mock::given(method("GET"))
+        .and(path(format!("/{name}")))
+        .respond_with(ResponseTemplate::new(200).set_body_json(wallet))
+        .mount(server)
+        .await;
+    let jwks: serde_json::Value =
+        serde_json::from_str(&Jwk::generate_jwks_json(signing_key, kid)).unwrap();
+    Mock::given(method("GET"))
+        .and(path(format!("/{name}/jwks.json")))
+        .respond_with(ResponseTemplate::new(200).set_body_json(jwks))
+        .expect(times)
+        .mount(server)
+        .await;
+}
+
+fn resolver() -> JwksKeyResolver<UnauthenticatedClient> {
+    JwksKeyResolver::new(UnauthenticatedClient::new().allow_http_wallet_addresses())
+}
+
+fn alice(server: &MockServer) -> String {
+    format!("{}/alice", server.uri())
+}
+
+async fn signed_request(
+    signing_key: &SigningKey,
+    kid: &str,
+) -> (Request<Option<String>>, HeaderMap) {
+    let mut request = Request::new(None);
+    *request.uri_mut() = "https://ilp.rafiki.money/incoming-payments"
+        .parse()
+        .unwrap();
+    let signer = Ed25519Signer::new(kid, signing_key.clone());
+    let signature = create_signature_headers(SignOptions::new(&request, &signer))
+        .await
+        .unwrap();
+
+    let mut headers = HeaderMap::new();
+    headers.insert("Signature", signature.signature.parse().unwrap());
+    headers.insert(
+        "Signature-Input",
+        signature.signature_input.parse().unwrap(),
+    );
+    (request, headers)
+}
+
+#[tokio::test]
+async fn signatures_are_validated_with_the_wallet_address_keys() {
+    let server = MockServer::start().await;
+    let signing_key = SigningKey::generate(&mut rand::rngs::OsRng);
+    mount_jwks(&server, &signing_key, "key-1", 1).await;
+
+    let resolver = resolver();
+    let resolver = resolver.for_wallet_address(alice(&server)).unwrap();
+    // The keys are fetched once and then served from the cache
+    for _ in 0..3 {
+        let (request, headers) = signed_request(&signing_key, "key-1").await;
+        validate_signature_with_resolver(
+            &request,
+            &headers,
+            &resolver,
+            ValidationPolicy::default(),
+        )
+        .await
+        .unwrap();
+    }
+
+    let other_key = SigningKey::generate(&mut rand::rngs::OsRng);
+    let (request, headers) = signed_request(&other_key, "key-1").await;
+    let error = validate_signature_with_resolver(
+        &request,
+        &headers,
+        &resolver,
+        ValidationPolicy::default(),
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(error, HttpSignatureError::Validation(_)));
+}
+
+#[tokio::test]
+async fn unknown_key_ids_refetch_the_keys() {
+    let server = MockServer::start().await;
+    let old_key = SigningKey::generate(&mut rand::rngs::OsRng);
+    mount_jwks(&server, &old_key, "key-1", 1).await;
+
+    let resolver = resolver().refetch_interval(Duration::ZERO);
+    let resolver = resolver.for_wallet_address(alice(&server)).unwrap();
+    resolver.resolve("key-1").await.unwrap();
+
+    // The client rotates its key
+    server.reset().await;
+    let new_key = SigningKey::generate(&mut rand::rngs::OsRng);
+    mount_jwks(&server, &new_key, "key-2", 2).await;
+
+    let (request, headers) = signed_request(&new_key, "key-2").await;
+    validate_signature_with_resolver(&request, &headers, &resolver, ValidationPolicy::default())
+        .await
+        .unwrap();
+
+    let error = resolver.resolve("key-3").await.unwrap_err();
+    assert!(matches!(error, HttpSignatureError::UnknownKey(kid) if kid == "key-3"));
+}
+
+#[tokio::test]
+async fn unknown_key_ids_do_not_refetch_within_the_refetch_interval() {
+    let server = MockServer::start().await;
+    let signing_key = SigningKey::generate(&mut rand::rngs::OsRng);
+    mount_jwks(&server, &signing_key, "key-1", 1).await;
+
+    let resolver = resolver();
+    let resolver = resolver.for_wallet_address(alice(&server)).unwrap();
+    resolver.resolve("key-1").await.unwrap();
+    for kid in ["made-up-1", "made-up-2"] {
+        assert!(matches!(
+            resolver.resolve(kid).await,
+            Err(HttpSignatureError::UnknownKey(_))
+        ));
+    }
+}
+
+#[tokio::test]
+async fn keys_are_resolved_and_cached_per_wallet_address() {
+    let server = MockServer::start().await;
+    let alice_key = SigningKey::generate(&mut rand::rngs::OsRng);
+    let bob_key = SigningKey::generate(&mut rand::rngs::OsRng);
+    mount_wallet_jwks(&server, "alice", &alice_key, "key-1", 2).await;
+    mount_wallet_jwks(&server, "bob", &bob_key, "key-1", 1).await;
+
+    // One resolver serves every client, each with the keys of its own wallet address
+    let resolver = resolver();
+    let bob = format!("{}/bob", server.uri());
+    for (wallet_address_url, signing_key, other_key) in [
+        (alice(&server), &alice_key, &bob_key),
+        (bob.clone(), &bob_key, &alice_key),
+        (alice(&server), &alice_key, &bob_key),
+    ] {
+        let keys = resolver.for_wallet_address(&wallet_address_url).unwrap();
+        let (request, headers) = signed_request(signing_key, "key-1").await;
+        validate_signature_with_resolver(&request, &headers, &keys, ValidationPolicy::default())
+            .await
+            .unwrap();
+
+        let (request, headers) = signed_request(other_key, "key-1").await;
+        assert!(validate_signature_with_resolver(
+            &request,
+            &headers,
+            &keys,
+            ValidationPolicy::default()
+        )
+        .await
+        .is_err());
+    }
+
+    // Invalidating a wallet address only refetches its own keys
+    resolver.invalidate(alice(&server)).await.unwrap();
+    let keys = resolver.for_wallet_address(alice(&server)).unwrap();
+    keys.resolve("key-1").await.unwrap();
+    resolver
+        .for_wallet_address(&bob)
+        .unwrap()
+        .resolve("key-1")
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn http_wallet_addresses_are_rejected_unless_the_client_allows_them() {
+    let server = MockServer::start().await;
+    let resolver = JwksKeyResolver::new(UnauthenticatedClient::new());
+    let error = resolver.for_wallet_address(alice(&server)).err().unwrap();
+    assert_eq!(error.kind(), &ErrorKind::Validation);
+}
+
+#[tokio::test]
+async fn keys_are_cached_for_a_bounded_number_of_existing_wallet_addresses() {
+    let server = MockServer::start().await;
+    let alice_key = SigningKey::generate(&mut rand::rngs::OsRng);
+    let bob_key = SigningKey::generate(&mut rand::rngs::OsRng);
+    mount_wallet_jwks(&server, "alice", &alice_key, "key-1", 2).await;
+    mount_wallet_jwks(&server, "bob", &bob_key, "key-1", 1).await;
+
+    let resolver = resolver().capacity(1);
+    let resolve = |wallet_address_url: String| {
+        let resolver = &resolver;
+        async move {
+            resolver
+                .for_wallet_address(wallet_address_url)
+                .unwrap()
+                .resolve("key-1")
+                .await
+        }
+    };
+    resolve(alice(&server)).await.unwrap();
+
+    // Unknown wallet addresses are not cached, so they do not evict alice
+    for name in ["carol", "dave"] {
+        assert!(resolve(format!("{}/{name}", server.uri())).await.is_err());
+    }
+    resolve(alice(&server)).await.unwrap();
+
+    // Bob evicts alice, whose keys are then fetched again
+    resolve(format!("{}/bob", server.uri())).await.unwrap();
+    resolve(alice(&server)).await.unwrap();
+}
@@ -0,0 +1,224 @@
+#![cfg(feature = "loopback")]
+
+mod common;
+
+use open_payments::client::{interaction_hash, AuthenticatedResources, ErrorKind, LoopbackOptions};
+use open_payments::http_signature::DigestAlgorithm;
+use open_payments::types::{AccessItem, AccessTokenRequest, GrantRequest, QuoteAction};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use wiremock::matchers::{body_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn grant() -> GrantRequest {
+    GrantRequest::new(
+        AccessTokenRequest {
+            access: vec![AccessItem::Quote {
+                actions: vec![QuoteAction::Create],
+            }],
+        },
+        None,
+    )
+}
+
+async fn mount_interactive_grant(server: &MockServer) {
+    Mock::given(method("POST"))
+        .and(path("/auth"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "interact": {
+                "redirect": format!("{}/interact/1", server.uri()),
+                "finish": "finish-nonce"
+            },
+            "continue": {
+                "access_token": {"value": "continue-token"},
+                "uri": format!("{}/continue/1", server.uri()),
+                "wait": 0
+            }
+        })))
+        .expect(1)
+        .mount(server)
+        .await;
+}
+
+fn options() -> LoopbackOptions {
+    LoopbackOptions {
+        timeout: Some(Duration::from_secs(10)),
+        ..LoopbackOptions::default()
+    }
+}
+
+fn valid_redirect(nonce: &str, grant_endpoint: &str) -> String {
+    let hash = interaction_hash(
+        nonce,
+        "finish-nonce",
+        "ref-1",
+        grant_endpoint,
+        DigestAlgorithm::Sha256,
+    );
+    format!("hash={hash}&interact_ref=ref-1")
+}
+
+fn forged_redirect() -> String {
+    let hash = interaction_hash(
+        "guessed-nonce",
+        "finish-nonce",
+        "ref-1",
+        "https://auth.example/",
+        DigestAlgorithm::Sha256,
+    );
+    format!("hash={hash}&interact_ref=ref-1")
+}
+
+/// Acts as the browser: follows the finish redirects the auth server would send, with
+/// the queries built by `queries` from the nonce in the grant request, and returns the
+/// status of each response.
+fn finish_interaction(
+    server: Arc<MockServer>,
+    queries: impl FnOnce(&str) -> Vec<String> + Send + 'static,
+) -> JoinHandle<Vec<u16>> {
+    tokio::spawn(async move {
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = requests[0].body_json().unwrap();
+        let finish = &body["interact"]["finish"];
+        assert_eq!(finish["method"], "redirect");
+        assert_eq!(body["interact"]["start"], serde_json::json!(["redirect"]));
+        let uri = finish["uri"].as_str().unwrap();
+
+        let favicon = uri.replace("/callback", "/favicon.ico");
+        assert_eq!(reqwest::get(favicon).await.unwrap().status(), 404);
+        let mut statuses = Vec::new();
+        for query in queries(finish["nonce"].as_str().unwrap()) {
+            let response = reqwest::get(format!("{uri}?{query}")).await.unwrap();
+            statuses.push(response.status().as_u16());
+        }
+        statuses
+    })
+}
+
+async fn mount_continuation(server: &MockServer) {
+    Mock::given(method("POST"))
+        .and(path("/continue/1"))
+        .and(body_json(serde_json::json!({"interact_ref": "ref-1"})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "access_token": {
+                "value": "granted-token",
+                "manage": format!("{}/token/1", server.uri())
+            },
+            "continue": {
+                "access_token": {"value": "continue-token-2"},
+                "uri": format!("{}/continue/1", server.uri())
+            }
+        })))
+        .expect(1)
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn loopback_grant_verifies_the_redirect_and_continues() {
+    let server = Arc::new(MockServer::start().await);
+    mount_interactive_grant(&server).await;
+    mount_continuation(&server).await;
+
+    let client = common::client(&server);
+    let auth_url = format!("{}/auth", server.uri());
+    let grant_endpoint = auth_url.clone();
+    let mut browser = None;
+    let (access_token, _) = client
+        .grant()
+        .request_with_loopback(&auth_url, &grant(), &options(), |url| {
+            assert!(url.ends_with("/interact/1"));
+            browser = Some(finish_interaction(server.clone(), move |nonce| {
+                vec![valid_redirect(nonce, &grant_endpoint)]
+            }));
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(access_token.value, "granted-token");
+    assert_eq!(browser.unwrap().await.unwrap(), vec![200]);
+}
+
+#[tokio::test]
+async fn loopback_grant_ignores_a_forged_redirect() {
+    let server = Arc::new(MockServer::start().await);
+    mount_interactive_grant(&server).await;
+    mount_continuation(&server).await;
+
+    let client = common::client(&server);
+    let auth_url = format!("{}/auth", server.uri());
+    let grant_endpoint = auth_url.clone();
+    let mut browser = None;
+    let (access_token, _) = client
+        .grant()
+        .request_with_loopback(&auth_url, &grant(), &options(), |_| {
+            browser = Some(finish_interaction(server.clone(), move |nonce| {
+                vec![forged_redirect(), valid_redirect(nonce, &grant_endpoint)]
+            }));
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(access_token.value, "granted-token");
+    assert_eq!(browser.unwrap().await.unwrap(), vec![400, 200]);
+}
+
+#[tokio::test]
+async fn loopback_grant_times_out_without_a_verified_redirect() {
+    let server = Arc::new(MockServer::start().await);
+    mount_interactive_grant(&server).await;
+    Mock::given(method("POST"))
+        .and(path("/continue/1"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let client = common::client(&server);
+    let auth_url = format!("{}/auth", server.uri());
+    let options = LoopbackOptions {
+        timeout: Some(Duration::from_secs(2)),
+        ..LoopbackOptions::default()
+    };
+    let mut browser = None;
+    let error = client
+        .grant()
+        .request_with_loopback(&auth_url, &grant(), &options, |_| {
+            browser = Some(finish_interaction(server.clone(), |_| {
+                vec![forged_redirect(), "interact_ref=ref-1".to_string()]
+            }));
+        })
+        .await
+        .unwrap_err();
+
+    assert_eq!(error.kind(), &ErrorKind::Timeout);
+    assert_eq!(browser.unwrap().await.unwrap(), vec![400, 400]);
+}
+
+#[tokio::test]
+async fn loopback_grant_ignores_a_forged_result() {
+    let server = Arc::new(MockServer::start().await);
+    mount_interactive_grant(&server).await;
+    mount_continuation(&server).await;
+
+    let client = common::client(&server);
+    let auth_url = format!("{}/auth", server.uri());
+    let grant_endpoint = auth_url.clone();
+    let mut browser = None;
+    let (access_token, _) = client
+        .grant()
+        .request_with_loopback(&auth_url, &grant(), &options(), |_| {
+            browser = Some(finish_interaction(server.clone(), move |nonce| {
+                vec![
+                    "result=grant_rejected".to_string(),
+                    valid_redirect(nonce, &grant_endpoint),
+                ]
+            }));
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(access_token.value, "granted-token");
+    assert_eq!(browser.unwrap().await.unwrap(), vec![400, 200]);
+}
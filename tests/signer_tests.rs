@@ -0,0 +1,126 @@
+use async_trait::async_trait;
+use ed25519_dalek::{Signer, SigningKey, VerifyingKey};
+use http::{HeaderMap, Request};
+use open_payments::client::{AuthenticatedClient, AuthenticatedResources};
+use open_payments::http_signature::{
+    validate_signature, HttpMessageSigner, HttpSignatureError, ValidationOptions,
+};
+use rand::rngs::OsRng;
+use std::net::SocketAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use url::Url;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Signs through a separate signing service reachable over a local socket.
+///
+/// The wire format is a big-endian `u32` length followed by the signature base;
+/// the service answers with the 64 byte Ed25519 signature. The private key never
+/// enters the client.
+struct SocketSigner {
+    key_id: String,
+    addr: SocketAddr,
+}
+
+#[async_trait]
+impl HttpMessageSigner for SocketSigner {
+    fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    async fn sign(&self, data: &[u8]) -> Result<Vec<u8>, HttpSignatureError> {
+        let mut stream = TcpStream::connect(self.addr).await?;
+        let len = u32::try_from(data.len())
+            .map_err(|_| HttpSignatureError::Signature("Signature base too large".into()))?;
+        stream.write_all(&len.to_be_bytes()).await?;
+        stream.write_all(data).await?;
+
+        let mut signature = vec![0u8; 64];
+        stream.read_exact(&mut signature).await?;
+        Ok(signature)
+    }
+}
+
+/// Starts a signing service holding the private key and returns its address and public key.
+async fn spawn_signing_service() -> (SocketAddr, VerifyingKey) {
+    let signing_key = SigningKey::generate(&mut OsRng);
+    let verifying_key = signing_key.verifying_key();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut len = [0u8; 4];
+            if stream.read_exact(&mut len).await.is_err() {
+                continue;
+            }
+            let mut data = vec![0u8; u32::from_be_bytes(len) as usize];
+            if stream.read_exact(&mut data).await.is_err() {
+                continue;
+            }
+            let signature = signing_key.sign(&data);
+            let _ = stream.write_all(&signature.to_bytes()).await;
+        }
+    });
+
+    (addr, verifying_key)
+}
+
+#[tokio::test]
+async fn socket_signer_produces_verifiable_signature() {
+    let (addr, verifying_key) = spawn_signing_service().await;
+    let signer = SocketSigner {
+        key_id: "remote-key".into(),
+        addr,
+    };
+
+    let server = MockServer::start().await;
+    let base = Url::parse(&server.uri()).unwrap();
+    Mock::given(method("POST"))
+        .and(path(base.join("token/rotate").unwrap().path()))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "access_token": {
+                "value": "new-token",
+                "manage": base.join("token/rotate").unwrap().to_string(),
+            }
+        })))
+        .mount(&server)
+        .await;
+
+    let client = AuthenticatedClient::builder()
+        .wallet_address_url(format!("{}/alice", server.uri()))
+        .signer(signer)
+        .build()
+        .unwrap();
+    assert_eq!(client.config.key_id, "remote-key");
+
+    let rotated = client
+        .token()
+        .rotate(
+            base.join("token/rotate").unwrap().as_ref(),
+            Some("old-token"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(rotated.access_token.value, "new-token");
+
+    let received = server.received_requests().await.unwrap();
+    let received = received.first().expect("request was sent");
+
+    // wiremock reports a normalised URL, so rebuild the target URI the client signed
+    let host = received.headers.get("host").unwrap().to_str().unwrap();
+    let mut request = Request::new(None);
+    *request.method_mut() = received.method.clone();
+    *request.uri_mut() = format!("http://{host}{}", received.url.path())
+        .parse()
+        .unwrap();
+    let mut headers = HeaderMap::new();
+    for (name, value) in received.headers.iter() {
+        request.headers_mut().append(name.clone(), value.clone());
+        headers.append(name.clone(), value.clone());
+    }
+
+    let options = ValidationOptions::new(&request, &headers, &verifying_key);
+    validate_signature(options).expect("signature from socket signer should verify");
+}
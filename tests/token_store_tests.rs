@@ -0,0 +1,319 @@
+mod common;
+
+use async_trait::async_trait;
+use open_payments::client::{
+    AccessScope, AuthenticatedResources, EncryptedFileTokenStore, ErrorKind, GrantManager,
+    InMemoryTokenStore, ManagedToken, OpClientError, Result, TokenKey, TokenStore,
+};
+use open_payments::types::{
+    AccessItem, AccessToken, AccessTokenRequest, Continue, ContinueAccessToken, GrantRequest,
+    IncomingPaymentAction,
+};
+use std::sync::Arc;
+use tempfile::tempdir;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn incoming_payment_access() -> Vec<AccessItem> {
+    vec![AccessItem::IncomingPayment {
+        actions: vec![IncomingPaymentAction::Create, IncomingPaymentAction::Read],
+        identifier: None,
+    }]
+}
+
+fn token(value: &str) -> ManagedToken {
+    ManagedToken::new(
+        AccessToken {
+            value: value.into(),
+            manage: "https://auth.example/token/1".into(),
+            expires_in: Some(600),
+            access: Some(incoming_payment_access()),
+        },
+        None,
+    )
+}
+
+#[test]
+fn token_key_does_not_depend_on_access_order() {
+    let quote = AccessItem::Quote { actions: vec![] };
+    let mut access = incoming_payment_access();
+    access.push(quote.clone());
+    let mut reversed = vec![quote];
+    reversed.extend(incoming_payment_access());
+
+    assert_eq!(
+        TokenKey::new("https://wallet.example/alice", &access),
+        TokenKey::new("https://wallet.example/alice", &reversed)
+    );
+    assert_ne!(
+        TokenKey::new("https://wallet.example/alice", &access),
+        TokenKey::new("https://wallet.example/bob", &access)
+    );
+}
+
+#[tokio::test]
+async fn in_memory_store_puts_gets_and_deletes() {
+    let store = InMemoryTokenStore::new();
+    let key = TokenKey::new("https://wallet.example/alice", &incoming_payment_access());
+
+    let replacement = token("b");
+    assert_eq!(store.get(&key).await.unwrap(), None);
+    store.put(&key, &token("a")).await.unwrap();
+    store.put(&key, &replacement).await.unwrap();
+    assert_eq!(store.get(&key).await.unwrap(), Some(replacement));
+    store.delete(&key).await.unwrap();
+    assert_eq!(store.get(&key).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn encrypted_file_store_round_trips_and_requires_the_key() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("tokens.bin");
+    let key = TokenKey::new("https://wallet.example/alice", &incoming_payment_access());
+    let stored = token("secret-token-value");
+
+    let store = EncryptedFileTokenStore::new(&file, [7; 32]);
+    store.put(&key, &stored).await.unwrap();
+
+    let contents = std::fs::read(&file).unwrap();
+    assert!(!String::from_utf8_lossy(&contents).contains("secret-token-value"));
+    assert!(!dir.path().join("tokens.bin.tmp").exists());
+
+    let reopened = EncryptedFileTokenStore::new(&file, [7; 32]);
+    assert_eq!(reopened.get(&key).await.unwrap(), Some(stored));
+
+    let wrong_key = EncryptedFileTokenStore::new(&file, [8; 32]);
+    assert!(wrong_key.get(&key).await.is_err());
+
+    reopened.delete(&key).await.unwrap();
+    assert_eq!(reopened.get(&key).await.unwrap(), None);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn encrypted_file_store_is_only_readable_by_its_owner() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("tokens.bin");
+    let key = TokenKey::new("https://wallet.example/alice", &incoming_payment_access());
+
+    let stored = token("a");
+    let store = EncryptedFileTokenStore::new(&file, [7; 32]);
+    store.put(&key, &stored).await.unwrap();
+
+    let mode = std::fs::metadata(&file).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    assert_eq!(
+        store
+            .find_by_manage("https://auth.example/token/1")
+            .await
+            .unwrap(),
+        Some((key, stored))
+    );
+}
+
+#[tokio::test]
+async fn granted_tokens_are_stored_and_restored_by_a_new_manager() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/auth"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "access_token": {
+                "value": "granted-token",
+                "manage": format!("{}/token/1", server.uri()),
+                "expires_in": 600
+            },
+            "continue": {
+                "access_token": {"value": "continue-token"},
+                "uri": format!("{}/continue/1", server.uri())
+            }
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let store = Arc::new(InMemoryTokenStore::new());
+    let client = common::client_builder(&server)
+        .token_store(store.clone())
+        .build()
+        .unwrap();
+
+    let grant = GrantRequest::new(
+        AccessTokenRequest {
+            access: incoming_payment_access(),
+        },
+        None,
+    );
+    client
+        .grant()
+        .request(&format!("{}/auth", server.uri()), &grant)
+        .await
+        .unwrap();
+
+    let key = TokenKey::new(
+        format!("{}/alice", server.uri()),
+        &incoming_payment_access(),
+    );
+    let stored = store.get(&key).await.unwrap().expect("stored token");
+    assert_eq!(stored.access_token.value, "granted-token");
+    assert_eq!(
+        stored.continue_.map(|c| c.access_token.value).as_deref(),
+        Some("continue-token")
+    );
+
+    // A manager created after a restart picks the token up without a new grant
+    let manager = GrantManager::new(&client);
+    assert!(manager.restore(&incoming_payment_access()).await.unwrap());
+    let token = manager
+        .token(&AccessScope::IncomingPayment {
+            action: IncomingPaymentAction::Read,
+            wallet_address: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(token, "granted-token");
+}
+
+#[tokio::test]
+async fn rotated_tokens_replace_the_stored_token_without_repeated_access() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/token/1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "access_token": {
+                "value": "rotated-token",
+                "manage": format!("{}/token/2", server.uri()),
+                "expires_in": 600
+            }
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let store = Arc::new(InMemoryTokenStore::new());
+    let client = common::client_builder(&server)
+        .token_store(store.clone())
+        .build()
+        .unwrap();
+
+    let key = TokenKey::new(
+        format!("{}/alice", server.uri()),
+        &incoming_payment_access(),
+    );
+    let mut stored = token("old-token");
+    stored.access_token.manage = format!("{}/token/1", server.uri());
+    stored.continue_ = Some(Continue {
+        access_token: ContinueAccessToken {
+            value: "continue-token".into(),
+        },
+        uri: format!("{}/continue/1", server.uri()),
+        wait: None,
+    });
+    store.put(&key, &stored).await.unwrap();
+
+    client
+        .token()
+        .rotate(&format!("{}/token/1", server.uri()), Some("old-token"))
+        .await
+        .unwrap();
+
+    // The rotation response has no access list, so the stored access and key are kept
+    let rotated = store.get(&key).await.unwrap().expect("stored token");
+    assert_eq!(rotated.access_token.value, "rotated-token");
+    assert_eq!(
+        rotated.access_token.manage,
+        format!("{}/token/2", server.uri())
+    );
+    assert_eq!(rotated.access_token.access, Some(incoming_payment_access()));
+    assert_eq!(rotated.continue_, stored.continue_);
+}
+
+/// Store whose writes fail, e.g. because its disk is full.
+struct ReadOnlyTokenStore(InMemoryTokenStore);
+
+#[async_trait]
+impl TokenStore for ReadOnlyTokenStore {
+    async fn get(&self, key: &TokenKey) -> Result<Option<ManagedToken>> {
+        self.0.get(key).await
+    }
+
+    async fn put(&self, _key: &TokenKey, _token: &ManagedToken) -> Result<()> {
+        Err(Box::new(OpClientError::other("No space left on device")))
+    }
+
+    async fn delete(&self, key: &TokenKey) -> Result<()> {
+        self.0.delete(key).await
+    }
+
+    async fn find_by_manage(&self, manage: &str) -> Result<Option<(TokenKey, ManagedToken)>> {
+        self.0.find_by_manage(manage).await
+    }
+}
+
+#[tokio::test]
+async fn store_failures_are_returned_with_the_unsaved_token() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/auth"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "access_token": {
+                "value": "granted-token",
+                "manage": format!("{}/token/1", server.uri())
+            },
+            "continue": {
+                "access_token": {"value": "continue-token"},
+                "uri": format!("{}/continue/1", server.uri())
+            }
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/token/1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "access_token": {
+                "value": "rotated-token",
+                "manage": format!("{}/token/2", server.uri()),
+                "access": incoming_payment_access()
+            }
+        })))
+        .mount(&server)
+        .await;
+
+    let client = common::client_builder(&server)
+        .token_store(Arc::new(ReadOnlyTokenStore(InMemoryTokenStore::new())))
+        .build()
+        .unwrap();
+
+    let grant = GrantRequest::new(
+        AccessTokenRequest {
+            access: incoming_payment_access(),
+        },
+        None,
+    );
+    let error = client
+        .grant()
+        .request(&format!("{}/auth", server.uri()), &grant)
+        .await
+        .unwrap_err();
+    assert_eq!(error.kind(), &ErrorKind::TokenStore);
+    let unsaved = error.unsaved_token().expect("unsaved token");
+    assert_eq!(unsaved.access_token.value, "granted-token");
+    assert_eq!(
+        unsaved.continue_.map(|c| c.access_token.value).as_deref(),
+        Some("continue-token")
+    );
+
+    // The old token is no longer valid after a rotation, so its failure must be reported
+    let error = client
+        .token()
+        .rotate(&format!("{}/token/1", server.uri()), Some("granted-token"))
+        .await
+        .unwrap_err();
+    assert_eq!(error.kind(), &ErrorKind::TokenStore);
+    assert!(!error.is_retryable());
+    assert_eq!(
+        error.unsaved_token().unwrap().access_token.value,
+        "rotated-token"
+    );
+}